@@ -0,0 +1,203 @@
+use crate::provider::LicenseProvider;
+use crate::{LicenseDetail, LicenseMeta, Result};
+use async_trait::async_trait;
+
+/// A license template compiled into the binary, mirroring GitHub's `/licenses`
+/// data but with current SPDX ids (`GPL-3.0-only` where GitHub says `GPL-3.0`).
+struct BundledLicense {
+    key: &'static str,
+    name: &'static str,
+    spdx_id: &'static str,
+    body: &'static str,
+}
+
+const BUNDLED: &[BundledLicense] = &[
+    BundledLicense {
+        key: "agpl-3.0",
+        name: "GNU Affero General Public License v3.0",
+        spdx_id: "AGPL-3.0-only",
+        body: include_str!("../licenses/agpl-3.0.txt"),
+    },
+    BundledLicense {
+        key: "apache-2.0",
+        name: "Apache License 2.0",
+        spdx_id: "Apache-2.0",
+        body: include_str!("../licenses/apache-2.0.txt"),
+    },
+    BundledLicense {
+        key: "bsd-2-clause",
+        name: "BSD 2-Clause \"Simplified\" License",
+        spdx_id: "BSD-2-Clause",
+        body: include_str!("../licenses/bsd-2-clause.txt"),
+    },
+    BundledLicense {
+        key: "bsd-3-clause",
+        name: "BSD 3-Clause \"New\" or \"Revised\" License",
+        spdx_id: "BSD-3-Clause",
+        body: include_str!("../licenses/bsd-3-clause.txt"),
+    },
+    BundledLicense {
+        key: "bsl-1.0",
+        name: "Boost Software License 1.0",
+        spdx_id: "BSL-1.0",
+        body: include_str!("../licenses/bsl-1.0.txt"),
+    },
+    BundledLicense {
+        key: "cc0-1.0",
+        name: "Creative Commons Zero v1.0 Universal",
+        spdx_id: "CC0-1.0",
+        body: include_str!("../licenses/cc0-1.0.txt"),
+    },
+    BundledLicense {
+        key: "epl-2.0",
+        name: "Eclipse Public License 2.0",
+        spdx_id: "EPL-2.0",
+        body: include_str!("../licenses/epl-2.0.txt"),
+    },
+    BundledLicense {
+        key: "gpl-2.0",
+        name: "GNU General Public License v2.0",
+        spdx_id: "GPL-2.0-only",
+        body: include_str!("../licenses/gpl-2.0.txt"),
+    },
+    BundledLicense {
+        key: "gpl-3.0",
+        name: "GNU General Public License v3.0",
+        spdx_id: "GPL-3.0-only",
+        body: include_str!("../licenses/gpl-3.0.txt"),
+    },
+    BundledLicense {
+        key: "lgpl-2.1",
+        name: "GNU Lesser General Public License v2.1",
+        spdx_id: "LGPL-2.1-only",
+        body: include_str!("../licenses/lgpl-2.1.txt"),
+    },
+    BundledLicense {
+        key: "mit",
+        name: "MIT License",
+        spdx_id: "MIT",
+        body: include_str!("../licenses/mit.txt"),
+    },
+    BundledLicense {
+        key: "mpl-2.0",
+        name: "Mozilla Public License 2.0",
+        spdx_id: "MPL-2.0",
+        body: include_str!("../licenses/mpl-2.0.txt"),
+    },
+    BundledLicense {
+        key: "unlicense",
+        name: "The Unlicense",
+        spdx_id: "Unlicense",
+        body: include_str!("../licenses/unlicense.txt"),
+    },
+];
+
+pub fn licenses_list() -> Vec<LicenseMeta> {
+    BUNDLED
+        .iter()
+        .map(|l| LicenseMeta {
+            key: l.key.to_string(),
+            name: l.name.to_string(),
+            spdx_id: l.spdx_id.to_string(),
+        })
+        .collect()
+}
+
+/// Looks up a bundled template by GitHub key or SPDX id, ignoring case.
+pub fn license_body(key: &str) -> Option<LicenseDetail> {
+    BUNDLED
+        .iter()
+        .find(|l| l.key.eq_ignore_ascii_case(key) || l.spdx_id.eq_ignore_ascii_case(key))
+        .map(|l| LicenseDetail {
+            name: l.name.to_string(),
+            body: l.body.to_string(),
+        })
+}
+
+/// The templates compiled into the binary.
+pub struct BundledProvider;
+
+#[async_trait]
+impl LicenseProvider for BundledProvider {
+    fn name(&self) -> &str {
+        "bundled"
+    }
+
+    async fn list(&self) -> Result<Vec<LicenseMeta>> {
+        Ok(licenses_list())
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<LicenseDetail>> {
+        Ok(license_body(key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ProviderChain, expression, find_placeholders, render_licenses};
+
+    #[test]
+    fn bundled_ids_are_current_spdx_ids() {
+        for license in licenses_list() {
+            let parsed = expression::parse(&license.spdx_id).unwrap();
+            assert!(parsed.validate().is_empty(), "{}", license.spdx_id);
+            assert_eq!(expression::current_id(&license.spdx_id), license.spdx_id);
+        }
+    }
+
+    #[tokio::test]
+    async fn every_bundled_license_resolves_and_renders() {
+        let providers = ProviderChain::offline();
+        for license in licenses_list() {
+            for key in [&license.key, &license.spdx_id] {
+                assert_eq!(providers.resolve(key).await.unwrap().name, license.name);
+                assert_eq!(providers.spdx_id(key).await.unwrap(), license.spdx_id);
+            }
+            let rendered = render_licenses(
+                &providers,
+                std::slice::from_ref(&license.key),
+                "2024",
+                "Jane Doe",
+            )
+            .await
+            .unwrap();
+            assert_eq!(rendered.len(), 1);
+            assert_eq!(rendered[0].spdx_id, license.spdx_id);
+            assert!(!rendered[0].content.trim().is_empty(), "{}", license.key);
+            assert_eq!(
+                find_placeholders(&rendered[0].content),
+                Vec::<&str>::new(),
+                "{}",
+                license.key
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn deprecated_github_ids_map_to_only_ids() {
+        let providers = ProviderChain::offline();
+        for (deprecated, current) in [
+            ("GPL-2.0", "GPL-2.0-only"),
+            ("GPL-3.0", "GPL-3.0-only"),
+            ("LGPL-2.1", "LGPL-2.1-only"),
+            ("AGPL-3.0", "AGPL-3.0-only"),
+        ] {
+            assert!(license_body(deprecated).is_some(), "{deprecated}");
+            assert_eq!(providers.spdx_id(deprecated).await.unwrap(), current);
+        }
+        assert_eq!(expression::current_id("LGPL-3.0"), "LGPL-3.0-only");
+        assert_eq!(expression::current_id("mit"), "MIT");
+        assert_eq!(expression::current_id("NOASSERTION"), "NOASSERTION");
+    }
+
+    #[test]
+    fn mit_fills_in_year_and_holder() {
+        let body = license_body("mit").unwrap().body;
+        let rendered = crate::replace_placeholders(&body, "2024", "Jane Doe");
+        assert!(
+            rendered.contains("Copyright (c) 2024 Jane Doe"),
+            "{rendered}"
+        );
+    }
+}
@@ -0,0 +1,166 @@
+use crate::{Error, Result};
+use chrono::{DateTime, Utc};
+use reqwest::header::{ETAG, IF_NONE_MATCH};
+use reqwest::{RequestBuilder, Response, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A cached API response together with the validator needed to revalidate it.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Entry<T> {
+    pub etag: Option<String>,
+    pub fetched_at: DateTime<Utc>,
+    pub data: T,
+}
+
+/// On-disk cache of license API responses, stored under `$XDG_CACHE_HOME/lic`.
+pub struct Cache {
+    dir: PathBuf,
+    ttl: chrono::Duration,
+}
+
+impl Cache {
+    pub fn new(ttl_secs: u64) -> Self {
+        let dir = dirs::cache_dir()
+            .unwrap_or_else(std::env::temp_dir)
+            .join("lic");
+        Self::in_dir(dir, ttl_secs)
+    }
+
+    /// A cache stored in `dir` instead of the user cache directory.
+    pub fn in_dir(dir: PathBuf, ttl_secs: u64) -> Self {
+        let ttl = chrono::Duration::seconds(ttl_secs.try_into().unwrap_or(i64::MAX));
+        Self { dir, ttl }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn path(&self, name: &str) -> PathBuf {
+        self.dir.join(format!("{name}.json"))
+    }
+
+    pub fn read<T: DeserializeOwned>(&self, name: &str) -> Option<Entry<T>> {
+        let raw = fs::read_to_string(self.path(name)).ok()?;
+        serde_json::from_str(&raw).ok()
+    }
+
+    fn write<T: Serialize>(&self, name: &str, entry: &Entry<T>) {
+        let path = self.path(name);
+        let result = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| fs::write(&path, serde_json::to_vec_pretty(entry)?));
+        if let Err(e) = result {
+            eprintln!("warning: failed to write cache {}: {e}", path.display());
+        }
+    }
+
+    pub fn is_fresh<T>(&self, entry: &Entry<T>) -> bool {
+        Utc::now() - entry.fetched_at < self.ttl
+    }
+
+    /// Lists every cached entry as `(name, entry)` with the payload left undecoded.
+    pub fn entries(&self) -> Vec<(String, Entry<serde_json::Value>)> {
+        let mut entries = Vec::new();
+        collect_entries(&self.dir, &self.dir, &mut entries);
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        entries
+    }
+
+    pub fn clear(&self) -> Result<()> {
+        if self.dir.exists() {
+            fs::remove_dir_all(&self.dir).map_err(|e| Error::io(&self.dir, e))?;
+        }
+        Ok(())
+    }
+}
+
+fn collect_entries(root: &Path, dir: &Path, out: &mut Vec<(String, Entry<serde_json::Value>)>) {
+    let Ok(read_dir) = fs::read_dir(dir) else {
+        return;
+    };
+    for item in read_dir.flatten() {
+        let path = item.path();
+        if path.is_dir() {
+            collect_entries(root, &path, out);
+        } else if path.extension().is_some_and(|ext| ext == "json") {
+            let name = path
+                .strip_prefix(root)
+                .unwrap_or(&path)
+                .with_extension("")
+                .to_string_lossy()
+                .replace('\\', "/");
+            let entry = fs::read_to_string(&path)
+                .ok()
+                .and_then(|raw| serde_json::from_str(&raw).ok());
+            if let Some(entry) = entry {
+                out.push((name, entry));
+            }
+        }
+    }
+}
+
+/// Fetches `request` as JSON through the cache entry `name`.
+///
+/// Fresh entries are returned without touching the network, stale ones are
+/// revalidated with `If-None-Match`, and when the request fails (offline,
+/// rate limited, server error) a stale entry is used with a warning.
+/// `check` turns error statuses into errors; `force` skips the freshness check.
+pub async fn fetch_json<T>(
+    cache: &Cache,
+    request: RequestBuilder,
+    name: &str,
+    force: bool,
+    check: impl FnOnce(Response) -> Result<Response>,
+) -> Result<T>
+where
+    T: Serialize + DeserializeOwned,
+{
+    let cached = match cache.read::<T>(name) {
+        Some(entry) if !force && cache.is_fresh(&entry) => return Ok(entry.data),
+        cached => cached,
+    };
+
+    let mut request = request;
+    if let Some(etag) = cached.as_ref().and_then(|e| e.etag.as_deref()) {
+        request = request.header(IF_NONE_MATCH, etag);
+    }
+
+    let response = match request.send().await {
+        Ok(response) => check(response),
+        Err(e) => Err(e.into()),
+    };
+    let response = match (response, cached) {
+        (Ok(response), Some(mut entry)) if response.status() == StatusCode::NOT_MODIFIED => {
+            entry.fetched_at = Utc::now();
+            cache.write(name, &entry);
+            return Ok(entry.data);
+        }
+        (Ok(response), _) => response,
+        (Err(e), Some(entry)) => {
+            eprintln!(
+                "warning: {e}; using cached {name} from {}",
+                entry.fetched_at.format("%Y-%m-%d %H:%M UTC")
+            );
+            return Ok(entry.data);
+        }
+        (Err(e), None) => return Err(e),
+    };
+
+    let etag = response
+        .headers()
+        .get(ETAG)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    let entry = Entry {
+        etag,
+        fetched_at: Utc::now(),
+        data: response.json::<T>().await?,
+    };
+    cache.write(name, &entry);
+    Ok(entry.data)
+}
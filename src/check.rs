@@ -0,0 +1,513 @@
+//! Project license checks for CI, as run by `lic check`.
+
+use crate::detect::{self, Detection};
+use crate::expression::{self, Expression};
+use crate::manifest;
+use crate::{Error, LICENSE_FILE_NAMES, Result, copyright, find_license_files, find_placeholders};
+use serde::Serialize;
+use serde_json::{Value, json};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// What a check can find wrong, in order of severity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Problem {
+    NoLicenseFile,
+    Unrecognized,
+    Placeholders,
+    StaleYear,
+    Mismatch,
+    ManifestMismatch,
+}
+
+impl Problem {
+    pub const ALL: [Problem; 6] = [
+        Problem::NoLicenseFile,
+        Problem::Unrecognized,
+        Problem::Placeholders,
+        Problem::StaleYear,
+        Problem::Mismatch,
+        Problem::ManifestMismatch,
+    ];
+
+    /// The process exit code `lic check` uses for this problem, 3 to 8.
+    pub fn exit_code(self) -> i32 {
+        match self {
+            Problem::NoLicenseFile => 3,
+            Problem::Unrecognized => 4,
+            Problem::Placeholders => 5,
+            Problem::StaleYear => 6,
+            Problem::Mismatch => 7,
+            Problem::ManifestMismatch => 8,
+        }
+    }
+
+    /// The SARIF rule id.
+    pub fn id(self) -> &'static str {
+        match self {
+            Problem::NoLicenseFile => "no-license-file",
+            Problem::Unrecognized => "unrecognized-license",
+            Problem::Placeholders => "unreplaced-placeholders",
+            Problem::StaleYear => "stale-copyright-year",
+            Problem::Mismatch => "license-mismatch",
+            Problem::ManifestMismatch => "manifest-mismatch",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Problem::NoLicenseFile => "The project has no license file.",
+            Problem::Unrecognized => "The license text does not match any known license.",
+            Problem::Placeholders => "The license still contains template placeholders.",
+            Problem::StaleYear => "The copyright notice does not reach the current year.",
+            Problem::Mismatch => "The license differs from the expected SPDX expression.",
+            Problem::ManifestMismatch => {
+                "A package manifest declares a different license than the license files."
+            }
+        }
+    }
+}
+
+/// One problem, located in a file when it concerns one.
+#[derive(Debug, Clone, Serialize)]
+pub struct Finding {
+    pub problem: Problem,
+    pub path: Option<PathBuf>,
+    /// 1-based line number within `path`.
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+/// A license file and what it was recognized as.
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckedFile {
+    pub path: PathBuf,
+    pub detection: Option<Detection>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Report {
+    pub files: Vec<CheckedFile>,
+    pub findings: Vec<Finding>,
+}
+
+pub struct CheckOptions {
+    /// The license the project is meant to have, e.g. `MIT OR Apache-2.0`.
+    pub expected: Option<Expression>,
+    /// Copyright notices must reach this year.
+    pub year: u32,
+}
+
+/// Checks the license files in `dir`.
+pub fn check(dir: &Path, options: &CheckOptions) -> Result<Report> {
+    let mut report = Report {
+        files: Vec::new(),
+        findings: Vec::new(),
+    };
+    let paths = find_license_files(dir);
+    if paths.is_empty() {
+        report.findings.push(Finding {
+            problem: Problem::NoLicenseFile,
+            path: None,
+            line: None,
+            message: format!(
+                "No license file found in {} (looked for {}).",
+                dir.display(),
+                LICENSE_FILE_NAMES.join(", ")
+            ),
+        });
+        return Ok(report);
+    }
+
+    for path in paths {
+        let text = fs::read_to_string(&path).map_err(|e| Error::io(&path, e))?;
+        let finding = |problem, line, message| Finding {
+            problem,
+            path: Some(path.clone()),
+            line,
+            message,
+        };
+
+        let detection = detect::detect(&text);
+        match &detection {
+            Some(detection) if detection.is_confident() => {}
+            Some(detection) => report.findings.push(finding(
+                Problem::Unrecognized,
+                None,
+                format!(
+                    "{} is not a recognized license (closest is {} at {:.1}%).",
+                    path.display(),
+                    detection.spdx_id,
+                    detection.confidence * 100.0
+                ),
+            )),
+            None => report.findings.push(finding(
+                Problem::Unrecognized,
+                None,
+                format!("{} is empty.", path.display()),
+            )),
+        }
+
+        for placeholder in find_placeholders(&text) {
+            report.findings.push(finding(
+                Problem::Placeholders,
+                line_of(&text, |line| line.contains(placeholder)),
+                format!("{} still contains {placeholder}.", path.display()),
+            ));
+        }
+
+        let notices = detection.as_ref().map_or(&[][..], |d| &d.copyrights[..]);
+        let latest = notices
+            .iter()
+            .flat_map(|n| copyright::years_in(&n.years))
+            .map(|(_, _, year)| year)
+            .max();
+        if let Some(latest) = latest
+            && latest < options.year
+        {
+            report.findings.push(finding(
+                Problem::StaleYear,
+                line_of(&text, |line| {
+                    copyright::is_copyright_line(line) && line.contains(&latest.to_string())
+                }),
+                format!(
+                    "The copyright notice in {} ends in {latest}, not {}.",
+                    path.display(),
+                    options.year
+                ),
+            ));
+        }
+
+        report.files.push(CheckedFile { path, detection });
+    }
+
+    if let Some(expected) = &options.expected {
+        report.findings.extend(mismatches(expected, &report.files));
+    }
+    report.findings.extend(manifest_mismatches(
+        dir,
+        options.expected.as_ref(),
+        &report.files,
+    )?);
+    Ok(report)
+}
+
+/// Expected licenses without a license file, and license files not in `expected`.
+fn mismatches(expected: &Expression, files: &[CheckedFile]) -> Vec<Finding> {
+    let expected_ids = expected.license_ids();
+    let detections: Vec<(&PathBuf, &Detection)> = files
+        .iter()
+        .filter_map(|f| Some((&f.path, f.detection.as_ref()?)))
+        .filter(|(_, d)| d.is_confident())
+        .collect();
+
+    let mut findings = Vec::new();
+    for id in &expected_ids {
+        if !detections.iter().any(|(_, d)| covers(d, id)) {
+            findings.push(Finding {
+                problem: Problem::Mismatch,
+                path: None,
+                line: None,
+                message: format!("Expected {expected}, but no license file contains {id}."),
+            });
+        }
+    }
+    for (path, detection) in detections {
+        if !expected_ids.iter().any(|id| covers(detection, id)) {
+            findings.push(Finding {
+                problem: Problem::Mismatch,
+                path: Some(path.clone()),
+                line: None,
+                message: format!(
+                    "{} contains {}, which is not part of {expected}.",
+                    path.display(),
+                    detection.spdx_id
+                ),
+            });
+        }
+    }
+    findings
+}
+
+/// Manifests whose license differs from `expected`, or from the recognized
+/// license files when nothing is expected. Licenses that are not SPDX
+/// expressions, such as `SEE LICENSE IN LICENSE`, are not compared.
+fn manifest_mismatches(
+    dir: &Path,
+    expected: Option<&Expression>,
+    files: &[CheckedFile],
+) -> Result<Vec<Finding>> {
+    let detections: Vec<&Detection> = files
+        .iter()
+        .filter_map(|f| f.detection.as_ref())
+        .filter(|d| d.is_confident())
+        .collect();
+    if expected.is_none() && detections.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut findings = Vec::new();
+    for manifest in manifest::find_manifests(dir)? {
+        let text = manifest.text()?;
+        let Ok(Some(license)) = manifest.license(&text) else {
+            continue;
+        };
+        let Ok(declared) = expression::parse(&license) else {
+            continue;
+        };
+        let declared = declared.normalize();
+        let agrees = match expected {
+            Some(expected) => declared.equivalent(expected),
+            None => {
+                let ids = declared.license_ids();
+                ids.iter()
+                    .all(|id| detections.iter().any(|d| covers(d, id)))
+                    && detections
+                        .iter()
+                        .all(|d| ids.iter().any(|id| covers(d, id)))
+            }
+        };
+        if agrees {
+            continue;
+        }
+        let want = match expected {
+            Some(expected) => format!("{expected} is expected"),
+            None => {
+                let ids: Vec<&str> = detections.iter().map(|d| d.spdx_id.as_str()).collect();
+                format!("the license files contain {}", ids.join(", "))
+            }
+        };
+        let first_id = declared
+            .license_ids()
+            .into_iter()
+            .next()
+            .unwrap_or_default();
+        findings.push(Finding {
+            problem: Problem::ManifestMismatch,
+            line: line_of(&text, |line| line.contains(&first_id)),
+            message: format!(
+                "{} declares {license}, but {want}.",
+                manifest.path.display()
+            ),
+            path: Some(manifest.path),
+        });
+    }
+    Ok(findings)
+}
+
+/// Whether `detection` is the license `id`, or an equivalent of it.
+fn covers(detection: &Detection, id: &str) -> bool {
+    detection.spdx_id.eq_ignore_ascii_case(id)
+        || detection
+            .alternatives
+            .iter()
+            .any(|alt| alt.eq_ignore_ascii_case(id))
+}
+
+fn line_of(text: &str, pred: impl Fn(&str) -> bool) -> Option<usize> {
+    text.lines().position(pred).map(|i| i + 1)
+}
+
+impl Report {
+    /// The exit code of the most severe finding, or 0.
+    pub fn exit_code(&self) -> i32 {
+        self.findings
+            .iter()
+            .map(|f| f.problem)
+            .min()
+            .map_or(0, Problem::exit_code)
+    }
+
+    /// The report as a SARIF 2.1.0 log, with paths relative to `root`.
+    pub fn to_sarif(&self, root: &Path) -> Value {
+        let rules: Vec<Value> = Problem::ALL
+            .iter()
+            .map(|p| {
+                json!({
+                    "id": p.id(),
+                    "shortDescription": { "text": p.description() },
+                })
+            })
+            .collect();
+        let results: Vec<Value> = self
+            .findings
+            .iter()
+            .map(|finding| {
+                let mut result = json!({
+                    "ruleId": finding.problem.id(),
+                    "level": "error",
+                    "message": { "text": finding.message },
+                });
+                // Code scanning needs a file for every result, so project-wide
+                // findings point at the license file (or where it should be).
+                let path = finding
+                    .path
+                    .clone()
+                    .or_else(|| self.files.first().map(|f| f.path.clone()))
+                    .unwrap_or_else(|| root.join("LICENSE"));
+                let uri = path
+                    .strip_prefix(root)
+                    .unwrap_or(&path)
+                    .to_string_lossy()
+                    .replace('\\', "/");
+                let mut location = json!({ "artifactLocation": { "uri": uri } });
+                if let Some(line) = finding.line {
+                    location["region"] = json!({ "startLine": line });
+                }
+                result["locations"] = json!([{ "physicalLocation": location }]);
+                result
+            })
+            .collect();
+
+        json!({
+            "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+            "version": "2.1.0",
+            "runs": [{
+                "tool": {
+                    "driver": {
+                        "name": "lic",
+                        "version": env!("CARGO_PKG_VERSION"),
+                        "rules": rules,
+                    }
+                },
+                "results": results,
+            }]
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn fixture(name: &str) -> String {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/licenses")
+            .join(name);
+        fs::read_to_string(path).unwrap()
+    }
+
+    /// A project directory with the given files.
+    fn project(files: &[(&str, &str)]) -> TempDir {
+        let dir = TempDir::new().unwrap();
+        for (name, contents) in files {
+            fs::write(dir.path().join(name), contents).unwrap();
+        }
+        dir
+    }
+
+    fn dual_licensed(manifest_license: &str) -> TempDir {
+        project(&[
+            ("LICENSE-APACHE", &fixture("rust-LICENSE-APACHE")),
+            ("LICENSE-MIT", &fixture("rust-LICENSE-MIT")),
+            (
+                "Cargo.toml",
+                &format!("[package]\nname = \"demo\"\nlicense = \"{manifest_license}\"\n"),
+            ),
+        ])
+    }
+
+    fn run(dir: &TempDir, expected: Option<&str>) -> Report {
+        let options = CheckOptions {
+            expected: expected.map(|e| expression::parse(e).unwrap().normalize()),
+            year: 2026,
+        };
+        check(dir.path(), &options).unwrap()
+    }
+
+    fn problems(report: &Report) -> Vec<Problem> {
+        report.findings.iter().map(|f| f.problem).collect()
+    }
+
+    #[test]
+    fn dual_licensed_crate_passes_in_either_order() {
+        for license in ["MIT OR Apache-2.0", "Apache-2.0 OR MIT"] {
+            let dir = dual_licensed(license);
+            for expected in [None, Some("Apache-2.0 OR MIT"), Some("MIT OR Apache-2.0")] {
+                let report = run(&dir, expected);
+                assert_eq!(
+                    report.exit_code(),
+                    0,
+                    "{license} vs {expected:?}: {:?}",
+                    report.findings
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn no_license_file() {
+        let report = run(&project(&[]), None);
+        assert_eq!(problems(&report), [Problem::NoLicenseFile]);
+        assert_eq!(report.exit_code(), 3);
+    }
+
+    #[test]
+    fn unrecognized_text() {
+        let report = run(&project(&[("LICENSE", "All rights reserved.\n")]), None);
+        assert_eq!(problems(&report), [Problem::Unrecognized]);
+        assert_eq!(report.exit_code(), 4);
+    }
+
+    #[test]
+    fn placeholders_are_located() {
+        let text = spdx::license_id("MIT").unwrap().text();
+        let report = run(&project(&[("LICENSE", text)]), None);
+        let finding = &report.findings[0];
+        assert_eq!(finding.problem, Problem::Placeholders);
+        assert_eq!(finding.line, line_of(text, |l| l.contains("<year>")));
+        assert_eq!(report.exit_code(), 5);
+    }
+
+    #[test]
+    fn stale_year() {
+        let report = run(&project(&[("LICENSE", &fixture("node-LICENSE"))]), None);
+        assert_eq!(problems(&report), [Problem::StaleYear]);
+        assert_eq!(report.findings[0].line, Some(5));
+        assert!(
+            report.findings[0]
+                .message
+                .contains("ends in 2015, not 2026")
+        );
+        assert_eq!(report.exit_code(), 6);
+    }
+
+    #[test]
+    fn expected_license_mismatch() {
+        let dir = dual_licensed("MIT OR Apache-2.0");
+        let report = run(&dir, Some("MIT"));
+        let mismatch = report
+            .findings
+            .iter()
+            .find(|f| f.problem == Problem::Mismatch)
+            .unwrap();
+        assert!(mismatch.path.as_ref().unwrap().ends_with("LICENSE-APACHE"));
+        assert_eq!(report.exit_code(), 7);
+    }
+
+    #[test]
+    fn manifest_mismatch() {
+        let report = run(&dual_licensed("MIT"), None);
+        assert_eq!(problems(&report), [Problem::ManifestMismatch]);
+        let finding = &report.findings[0];
+        assert!(finding.path.as_ref().unwrap().ends_with("Cargo.toml"));
+        assert_eq!(finding.line, Some(3));
+        assert_eq!(report.exit_code(), 8);
+    }
+
+    #[test]
+    fn most_severe_problem_wins() {
+        let text = spdx::license_id("MIT").unwrap().text();
+        let dir = project(&[
+            ("LICENSE", text),
+            (
+                "Cargo.toml",
+                "[package]\nname = \"demo\"\nlicense = \"ISC\"\n",
+            ),
+        ]);
+        let report = run(&dir, None);
+        assert!(problems(&report).contains(&Problem::ManifestMismatch));
+        assert_eq!(report.exit_code(), 5);
+    }
+}
@@ -0,0 +1,337 @@
+//! Whether code under one license can be part of a project under another.
+//!
+//! Licenses fall into four [`Category`]s by how far their copyleft reaches.
+//! Permissive and weak copyleft code can go into any project, with a few
+//! well-known conflicts such as Apache-2.0's patent clause and GPL-2.0-only.
+//! Strong and network copyleft code needs a project under a compatible
+//! version of the same license family.
+
+use crate::expression::{Expression, LicenseTerm};
+use serde::Serialize;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Category {
+    /// Notice and attribution only: MIT, BSD, Apache-2.0, ...
+    Permissive,
+    /// Copyleft limited to the library or file: LGPL, MPL, EPL, ...
+    WeakCopyleft,
+    /// Copyleft over the whole distributed work: GPL, EUPL, ...
+    StrongCopyleft,
+    /// Copyleft that also covers use over a network: AGPL, SSPL, ...
+    NetworkCopyleft,
+}
+
+impl fmt::Display for Category {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Category::Permissive => "permissive",
+            Category::WeakCopyleft => "weak copyleft",
+            Category::StrongCopyleft => "strong copyleft",
+            Category::NetworkCopyleft => "network copyleft",
+        })
+    }
+}
+
+/// The outcome of a compatibility check, with the reason when it is not compatible.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Compatibility {
+    Compatible,
+    Incompatible(String),
+    /// A license the model does not know, such as a `LicenseRef-`.
+    Unknown(String),
+}
+
+const PERMISSIVE: &[&str] = &[
+    "0BSD",
+    "AFL-3.0",
+    "Apache-1.1",
+    "Apache-2.0",
+    "Artistic-2.0",
+    "BlueOak-1.0.0",
+    "BSL-1.0",
+    "bzip2-1.0.6",
+    "CC-BY-3.0",
+    "CC-BY-4.0",
+    "CC0-1.0",
+    "CDLA-Permissive-2.0",
+    "curl",
+    "ICU",
+    "ISC",
+    "Libpng",
+    "libpng-2.0",
+    "MulanPSL-2.0",
+    "NCSA",
+    "OpenSSL",
+    "PostgreSQL",
+    "PSF-2.0",
+    "Python-2.0",
+    "Ruby",
+    "Unicode-3.0",
+    "Unicode-DFS-2016",
+    "Unlicense",
+    "UPL-1.0",
+    "W3C",
+    "WTFPL",
+    "X11",
+    "Zlib",
+    "zlib-acknowledgement",
+];
+
+const WEAK_COPYLEFT: &[&str] = &["CDDL-", "CPL-1.0", "EPL-", "LGPL-", "Ms-RL", "MPL-"];
+
+const STRONG_COPYLEFT: &[&str] = &["CC-BY-SA-", "EUPL-", "GPL-", "Sleepycat"];
+
+const NETWORK_COPYLEFT: &[&str] = &["AGPL-", "OSL-3.0", "RPL-", "SSPL-"];
+
+/// Exceptions that let GPL code be linked with code under any license.
+const LINKING_EXCEPTIONS: &[&str] = &[
+    "Autoconf-exception-3.0",
+    "Bison-exception-2.2",
+    "Classpath-exception-2.0",
+    "GCC-exception-3.1",
+    "LGPL-3.0-linking-exception",
+    "LLVM-exception",
+    "OCaml-LGPL-linking-exception",
+];
+
+/// Permissive licenses with terms the GNU licenses count as further
+/// restrictions, and the GNU versions they conflict with.
+const GNU_CONFLICTS: &[(&str, &[Gnu], &str)] = &[
+    (
+        "Apache-2.0",
+        &[Gnu::V1, Gnu::V2],
+        "its patent termination and indemnity terms are restrictions GPL-2.0 does not allow",
+    ),
+    (
+        "BSD-4-Clause",
+        &[Gnu::V1, Gnu::V2, Gnu::V3, Gnu::Affero3],
+        "its advertising clause is a restriction the GPL does not allow",
+    ),
+    (
+        "OpenSSL",
+        &[Gnu::V1, Gnu::V2, Gnu::V3, Gnu::Affero3],
+        "its advertising clause is a restriction the GPL does not allow",
+    ),
+];
+
+/// Weak copyleft licenses whose own copyleft conflicts with the GPL's.
+const GPL_INCOMPATIBLE_COPYLEFT: &[&str] = &[
+    "CDDL-1.0", "CDDL-1.1", "CPL-1.0", "EPL-1.0", "EPL-2.0", "MPL-1.0", "MPL-1.1",
+];
+
+/// The version of the GNU GPL a term can be distributed under.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Gnu {
+    V1,
+    V2,
+    V3,
+    Affero3,
+}
+
+/// How far the copyleft of `term` reaches, or `None` for licenses the model
+/// does not know. GPL code with a linking exception counts as weak copyleft.
+pub fn category(term: &LicenseTerm) -> Option<Category> {
+    let id = term.id.as_str();
+    let matches = |prefixes: &[&str]| prefixes.iter().any(|p| id.starts_with(p));
+    let category = if PERMISSIVE.contains(&id) || id.starts_with("BSD-") || id.starts_with("MIT") {
+        Category::Permissive
+    } else if matches(NETWORK_COPYLEFT) {
+        Category::NetworkCopyleft
+    } else if matches(WEAK_COPYLEFT) {
+        Category::WeakCopyleft
+    } else if matches(STRONG_COPYLEFT) {
+        Category::StrongCopyleft
+    } else {
+        return None;
+    };
+    let linking = term
+        .exception
+        .as_deref()
+        .is_some_and(|e| LINKING_EXCEPTIONS.contains(&e));
+    Some(match category {
+        Category::StrongCopyleft if linking => Category::WeakCopyleft,
+        category => category,
+    })
+}
+
+/// The GNU license versions `term` allows distribution under: the version
+/// itself, and later ones for `-or-later`.
+fn gnu_versions(term: &LicenseTerm) -> Vec<Gnu> {
+    let id = term.id.as_str();
+    let or_later = term.or_later || id.ends_with("-or-later");
+    if id.starts_with("AGPL-3.0") {
+        return vec![Gnu::Affero3];
+    }
+    let version = ["GPL-", "LGPL-"]
+        .iter()
+        .find_map(|family| id.strip_prefix(family).and_then(|rest| rest.chars().next()));
+    let from = match version {
+        Some('1') => Gnu::V1,
+        Some('2') => Gnu::V2,
+        Some('3') => Gnu::V3,
+        _ => return Vec::new(),
+    };
+    let all = [Gnu::V1, Gnu::V2, Gnu::V3];
+    let start = all.iter().position(|v| *v == from).unwrap_or(0);
+    if or_later {
+        all[start..].to_vec()
+    } else {
+        vec![from]
+    }
+}
+
+/// Whether a work can be distributed under one of `a` and one of `b` at once.
+/// GPL-3.0 section 13 allows combining with AGPL-3.0.
+fn gnu_overlap(a: &[Gnu], b: &[Gnu]) -> bool {
+    a.iter().any(|v| b.contains(v))
+        || (a.contains(&Gnu::V3) && b.contains(&Gnu::Affero3))
+        || (a.contains(&Gnu::Affero3) && b.contains(&Gnu::V3))
+}
+
+/// Whether code under `dependency` can be part of a project under `project`.
+pub fn term_compatibility(dependency: &LicenseTerm, project: &LicenseTerm) -> Compatibility {
+    let (Some(dependency_category), Some(project_category)) =
+        (category(dependency), category(project))
+    else {
+        let unknown = match category(dependency) {
+            None => dependency,
+            Some(_) => project,
+        };
+        return Compatibility::Unknown(format!("{unknown} is not in the compatibility model"));
+    };
+    if dependency == project {
+        return Compatibility::Compatible;
+    }
+    let project_versions = gnu_versions(project);
+    let project_is_gnu = !project_versions.is_empty()
+        && project_category != Category::WeakCopyleft
+        && project.exception.is_none();
+    let incompatible = |reason: String| Compatibility::Incompatible(reason);
+
+    match dependency_category {
+        Category::Permissive => {
+            let conflict = GNU_CONFLICTS.iter().find(|(id, versions, _)| {
+                dependency.id == *id
+                    && dependency.exception.is_none()
+                    && !project_versions.is_empty()
+                    && project_versions.iter().all(|v| versions.contains(v))
+            });
+            match conflict {
+                Some((_, _, reason)) => incompatible(format!(
+                    "{dependency} cannot be combined with {project}: {reason}"
+                )),
+                None => Compatibility::Compatible,
+            }
+        }
+        Category::WeakCopyleft => {
+            if project_is_gnu && GPL_INCOMPATIBLE_COPYLEFT.contains(&dependency.id.as_str()) {
+                return incompatible(format!(
+                    "{dependency} is weak copyleft with terms that conflict with {project}"
+                ));
+            }
+            let versions = gnu_versions(dependency);
+            if project_is_gnu
+                && versions.contains(&Gnu::V3)
+                && !versions.contains(&Gnu::V2)
+                && !gnu_overlap(&versions, &project_versions)
+            {
+                return incompatible(format!(
+                    "{dependency} is GPL-3.0 with extra permissions, which {project} cannot include"
+                ));
+            }
+            Compatibility::Compatible
+        }
+        Category::StrongCopyleft | Category::NetworkCopyleft => {
+            let versions = gnu_versions(dependency);
+            if project_is_gnu && gnu_overlap(&versions, &project_versions) {
+                return Compatibility::Compatible;
+            }
+            if project_is_gnu && !versions.is_empty() {
+                return incompatible(format!(
+                    "{dependency} and {project} have no GPL version in common"
+                ));
+            }
+            incompatible(format!(
+                "{dependency} is {dependency_category}: a project that includes it must be \
+                 distributed under {dependency} as a whole, not {project}"
+            ))
+        }
+    }
+}
+
+/// Whether code under `dependency` can be part of a project under `project`.
+/// The dependency needs one choice of alternatives that works; a project with
+/// alternatives of its own must work under each of them, since its users may
+/// pick any.
+pub fn compatibility(dependency: &Expression, project: &Expression) -> Compatibility {
+    let project_terms = project.terms();
+    evaluate(dependency, &|term| {
+        let verdicts: Vec<Compatibility> = project_terms
+            .iter()
+            .map(|project| term_compatibility(term, project))
+            .collect();
+        verdicts
+            .iter()
+            .find(|v| matches!(v, Compatibility::Incompatible(_)))
+            .or_else(|| {
+                verdicts
+                    .iter()
+                    .find(|v| matches!(v, Compatibility::Unknown(_)))
+            })
+            .cloned()
+            .unwrap_or(Compatibility::Compatible)
+    })
+}
+
+/// Combines the verdicts for each term: every term of an AND must be
+/// compatible, one alternative of an OR is enough.
+pub fn evaluate(
+    expression: &Expression,
+    check: &dyn Fn(&LicenseTerm) -> Compatibility,
+) -> Compatibility {
+    match expression {
+        Expression::License(term) => check(term),
+        Expression::And(terms) => {
+            let verdicts: Vec<Compatibility> = terms.iter().map(|t| evaluate(t, check)).collect();
+            let reasons = |unknown: bool| -> Vec<String> {
+                verdicts
+                    .iter()
+                    .filter_map(|v| match (v, unknown) {
+                        (Compatibility::Incompatible(reason), false)
+                        | (Compatibility::Unknown(reason), true) => Some(reason.clone()),
+                        _ => None,
+                    })
+                    .collect()
+            };
+            match (reasons(false), reasons(true)) {
+                (incompatible, _) if !incompatible.is_empty() => {
+                    Compatibility::Incompatible(incompatible.join("; "))
+                }
+                (_, unknown) if !unknown.is_empty() => Compatibility::Unknown(unknown.join("; ")),
+                _ => Compatibility::Compatible,
+            }
+        }
+        Expression::Or(terms) => {
+            let verdicts: Vec<Compatibility> = terms.iter().map(|t| evaluate(t, check)).collect();
+            if verdicts.contains(&Compatibility::Compatible) {
+                return Compatibility::Compatible;
+            }
+            if let Some(unknown) = verdicts
+                .iter()
+                .find(|v| matches!(v, Compatibility::Unknown(_)))
+            {
+                return unknown.clone();
+            }
+            let reasons: Vec<String> = verdicts
+                .into_iter()
+                .filter_map(|v| match v {
+                    Compatibility::Incompatible(reason) => Some(reason),
+                    _ => None,
+                })
+                .collect();
+            Compatibility::Incompatible(reasons.join("; and "))
+        }
+    }
+}
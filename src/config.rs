@@ -0,0 +1,40 @@
+use crate::{Error, Result};
+use serde::Deserialize;
+use std::fs;
+use std::path::PathBuf;
+
+/// Settings read from `$XDG_CONFIG_HOME/lic/config.toml` (or `$LIC_CONFIG`).
+///
+/// Command line flags and environment variables take precedence over these.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct Config {
+    /// Base URL of a GitHub-compatible licenses API, e.g. a GitHub Enterprise instance.
+    pub api_url: Option<String>,
+    /// Seconds before cached API responses are revalidated.
+    pub cache_ttl: Option<u64>,
+    /// Local checkout of `spdx/license-list-data` used by the SPDX source.
+    pub spdx_data: Option<PathBuf>,
+    /// Directory of custom license templates used by the local source.
+    pub templates_dir: Option<PathBuf>,
+    /// Base URL of the GitLab API used by the gitlab source.
+    pub gitlab_url: Option<String>,
+}
+
+impl Config {
+    pub fn path() -> Option<PathBuf> {
+        match std::env::var_os("LIC_CONFIG") {
+            Some(path) => Some(PathBuf::from(path)),
+            None => dirs::config_dir().map(|dir| dir.join("lic").join("config.toml")),
+        }
+    }
+
+    /// Loads the config file, returning defaults when it does not exist.
+    pub fn load() -> Result<Self> {
+        let Some(path) = Self::path().filter(|p| p.exists()) else {
+            return Ok(Self::default());
+        };
+        let raw = fs::read_to_string(&path).map_err(|e| Error::io(&path, e))?;
+        toml::from_str(&raw).map_err(|source| Error::Config { path, source })
+    }
+}
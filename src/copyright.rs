@@ -0,0 +1,70 @@
+//! Reading and updating `Copyright ...` notices in license text.
+
+/// Extends the copyright years on every line mentioning `holder` up to `year`.
+///
+/// `2020` becomes `2020-2024`, `2019-2022` becomes `2019-2024`, and lines whose
+/// last year is already `year` or later are left alone. Returns the new text and
+/// whether anything changed.
+pub fn bump_years(text: &str, holder: &str, year: u32) -> (String, bool) {
+    let mut changed = false;
+    let lines: Vec<String> = text
+        .split_inclusive('\n')
+        .map(|line| {
+            if !is_copyright_line(line) || !line.contains(holder) {
+                return line.to_string();
+            }
+            match bump_line(line, year) {
+                Some(bumped) => {
+                    changed = true;
+                    bumped
+                }
+                None => line.to_string(),
+            }
+        })
+        .collect();
+    (lines.concat(), changed)
+}
+
+pub fn is_copyright_line(line: &str) -> bool {
+    let lower = line.to_lowercase();
+    lower.contains("copyright") || line.contains('©')
+}
+
+/// Byte ranges and values of the four digit years in `line`.
+pub fn years_in(line: &str) -> Vec<(usize, usize, u32)> {
+    let bytes = line.as_bytes();
+    let mut years = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        if !bytes[i].is_ascii_digit() {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < bytes.len() && bytes[i].is_ascii_digit() {
+            i += 1;
+        }
+        if i - start == 4
+            && let Ok(value) = line[start..i].parse::<u32>()
+            && (1900..=2999).contains(&value)
+        {
+            years.push((start, i, value));
+        }
+    }
+    years
+}
+
+fn bump_line(line: &str, year: u32) -> Option<String> {
+    let years = years_in(line);
+    let &(start, end, last) = years.last()?;
+    if last >= year {
+        return None;
+    }
+    let before = line[..start].trim_end();
+    let in_range = before.ends_with('-') || before.ends_with('–');
+    Some(if in_range && years.len() > 1 {
+        format!("{}{year}{}", &line[..start], &line[end..])
+    } else {
+        format!("{}-{year}{}", &line[..end], &line[end..])
+    })
+}
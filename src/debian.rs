@@ -0,0 +1,656 @@
+//! Debian's [machine-readable `debian/copyright`](https://www.debian.org/doc/packaging-manuals/copyright-format/1.0/)
+//! format (DEP-5): `Files:` stanzas grouped by license, with copyright from
+//! file headers, REUSE annotations or git history.
+
+use crate::copyright::years_in;
+use crate::detect::{self, copyright_notices};
+use crate::expression::{self, Expression, LicenseTerm, Severity};
+use crate::provider::ProviderChain;
+use crate::reuse::{self, Annotations};
+use crate::{Error, Result};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+pub const COPYRIGHT_PATH: &str = "debian/copyright";
+
+const FORMAT: &str = "https://www.debian.org/doc/packaging-manuals/copyright-format/1.0/";
+
+/// Licenses whose text Debian ships in `/usr/share/common-licenses`, by
+/// Debian short name.
+const COMMON_LICENSES: &[&str] = &[
+    "Apache-2.0",
+    "CC0-1.0",
+    "GFDL-1.2",
+    "GFDL-1.3",
+    "GPL-1",
+    "GPL-2",
+    "GPL-3",
+    "LGPL-2",
+    "LGPL-2.1",
+    "LGPL-3",
+    "MPL-1.1",
+    "MPL-2.0",
+];
+
+/// Copyright holders and the years each of them holds copyright for.
+pub type Holders = BTreeMap<String, BTreeSet<u32>>;
+
+/// A `Files:` stanza.
+#[derive(Debug, Clone)]
+pub struct Stanza {
+    /// Patterns relative to the package root, `*` for everything.
+    pub files: Vec<String>,
+    pub copyright: Holders,
+    pub license: Expression,
+}
+
+/// The header paragraph.
+#[derive(Debug, Clone, Default)]
+pub struct Upstream {
+    pub name: String,
+    pub contact: Option<String>,
+    pub source: Option<String>,
+}
+
+/// The `Files:` stanzas for a package.
+#[derive(Debug, Clone, Default)]
+pub struct Stanzas {
+    pub stanzas: Vec<Stanza>,
+    /// Files left out because their license is not a valid SPDX expression,
+    /// with that license.
+    pub invalid: Vec<(String, String)>,
+}
+
+/// Groups the files under `root` by license. Files without licensing
+/// information get `default`, and files without copyright information get
+/// their authors from git history, or `fallback` outside a repository. The
+/// stanza for `default` covers `*` and comes first.
+pub fn stanzas(root: &Path, default: Option<&Expression>, fallback: &Holders) -> Result<Stanzas> {
+    let annotations = Annotations::load(root)?;
+    let history = git_history(root);
+    let mut invalid = Vec::new();
+    let mut groups: Vec<Stanza> = Vec::new();
+    let mut group_of: BTreeMap<String, usize> = BTreeMap::new();
+    for path in reuse::covered_files(root)? {
+        let relative = reuse::relative_path(root, &path);
+        if relative == COPYRIGHT_PATH {
+            continue;
+        }
+        let own = reuse::file_info(&path)?;
+        let info = annotations.info(&relative, || own);
+
+        let license = match &info.licenses[..] {
+            [] => default.cloned().ok_or_else(|| Error::Parse {
+                path: path.clone(),
+                message: "no license information, and no project license to assume".to_string(),
+            })?,
+            licenses => {
+                let mut parsed = Vec::new();
+                for license in licenses {
+                    match valid_expression(license) {
+                        Some(expression) => parsed.push(expression),
+                        None => invalid.push((relative.clone(), license.clone())),
+                    }
+                }
+                if parsed.len() < licenses.len() {
+                    continue;
+                }
+                Expression::and(parsed)
+            }
+        };
+        let copyright = if !info.copyright.is_empty() {
+            holders(&info.copyright)
+        } else {
+            history
+                .as_ref()
+                .and_then(|history| history.get(&relative).cloned())
+                .unwrap_or_else(|| fallback.clone())
+        };
+
+        let index = match groups.iter().position(|g| g.license == license) {
+            Some(index) => index,
+            None => {
+                groups.push(Stanza {
+                    files: Vec::new(),
+                    copyright: Holders::new(),
+                    license,
+                });
+                groups.len() - 1
+            }
+        };
+        merge(&mut groups[index].copyright, &copyright);
+        groups[index].files.push(relative.clone());
+        group_of.insert(relative, index);
+    }
+
+    // The project license covers `*`, or else the license most files have.
+    let main = default
+        .and_then(|default| groups.iter().position(|g| g.license == *default))
+        .or_else(|| (0..groups.len()).max_by_key(|&i| groups[i].files.len()));
+    if let Some(main) = main {
+        let mut group = groups.remove(main);
+        group.files = vec!["*".to_string()];
+        groups.insert(0, group);
+        for index in group_of.values_mut() {
+            *index = match *index {
+                i if i == main => 0,
+                i if i < main => i + 1,
+                i => i,
+            };
+        }
+    }
+    for (index, group) in groups.iter_mut().enumerate().skip(1) {
+        group.files = patterns(&group.files, index, &group_of);
+    }
+    if groups.is_empty()
+        && let Some(default) = default
+    {
+        groups.push(Stanza {
+            files: vec!["*".to_string()],
+            copyright: fallback.clone(),
+            license: default.clone(),
+        });
+    }
+    Ok(Stanzas {
+        stanzas: groups,
+        invalid,
+    })
+}
+
+/// `license` parsed and normalized, `None` unless it is a valid SPDX expression.
+fn valid_expression(license: &str) -> Option<Expression> {
+    let parsed = expression::parse(license).ok()?;
+    let valid = !parsed
+        .validate()
+        .iter()
+        .any(|issue| issue.severity == Severity::Error);
+    valid.then(|| parsed.normalize())
+}
+
+/// `files`, with every directory whose files all belong to `group` collapsed to `dir/*`.
+fn patterns(files: &[String], group: usize, group_of: &BTreeMap<String, usize>) -> Vec<String> {
+    let mut patterns: Vec<String> = Vec::new();
+    for file in files {
+        let dirs = file.match_indices('/').map(|(i, _)| &file[..i]);
+        let whole_dir = dirs.into_iter().find(|dir| {
+            let prefix = format!("{dir}/");
+            group_of
+                .range(prefix.clone()..)
+                .take_while(|(path, _)| path.starts_with(&prefix))
+                .all(|(_, &g)| g == group)
+        });
+        let pattern = match whole_dir {
+            Some(dir) => format!("{}/*", escape(dir)),
+            None => escape(file),
+        };
+        if !patterns.contains(&pattern) {
+            patterns.push(pattern);
+        }
+    }
+    patterns
+}
+
+/// A path as a `Files:` pattern: `*`, `?` and `\` escaped, spaces matched by `?`.
+fn escape(path: &str) -> String {
+    path.replace('\\', "\\\\")
+        .replace('*', "\\*")
+        .replace('?', "\\?")
+        .replace(' ', "?")
+}
+
+/// Holders and years from copyright statements such as `2019-2024 Jane Doe`.
+pub fn holders(statements: &[String]) -> Holders {
+    let mut holders = Holders::new();
+    for statement in statements {
+        let line = format!("Copyright {statement}");
+        match copyright_notices(&line).first() {
+            Some((_, notice)) => holders
+                .entry(notice.holder.clone())
+                .or_default()
+                .extend(years(&notice.years)),
+            None => {
+                let holder = statement.trim_start_matches("Copyright").trim();
+                holders.entry(holder.to_string()).or_default();
+            }
+        }
+    }
+    holders
+}
+
+/// The holders in the copyright notices of the license files in `root`, for
+/// files without notices outside a git repository.
+pub fn license_file_holders(root: &Path) -> Result<Holders> {
+    let mut statements = Vec::new();
+    for path in crate::find_license_files(root) {
+        let text = fs::read_to_string(&path).map_err(|e| Error::io(&path, e))?;
+        for (_, notice) in copyright_notices(&text) {
+            statements.push(format!("{} {}", notice.years, notice.holder));
+        }
+    }
+    Ok(holders(&statements))
+}
+
+fn merge(into: &mut Holders, from: &Holders) {
+    for (holder, years) in from {
+        into.entry(holder.clone()).or_default().extend(years);
+    }
+}
+
+/// Every year in `2019-2021, 2023`.
+fn years(text: &str) -> BTreeSet<u32> {
+    let mut years = BTreeSet::new();
+    let mut previous: Option<(usize, u32)> = None;
+    for (start, end, year) in years_in(text) {
+        match previous {
+            Some((previous_end, from)) if text[previous_end..start].trim() == "-" => {
+                years.extend(from..=year)
+            }
+            _ => {
+                years.insert(year);
+            }
+        }
+        previous = Some((end, year));
+    }
+    years
+}
+
+/// `2019-2021, 2023`.
+fn year_ranges(years: &BTreeSet<u32>) -> String {
+    let mut ranges: Vec<(u32, u32)> = Vec::new();
+    for &year in years {
+        match ranges.last_mut() {
+            Some((_, last)) if *last + 1 == year => *last = year,
+            _ => ranges.push((year, year)),
+        }
+    }
+    ranges
+        .iter()
+        .map(|&(first, last)| {
+            if first == last {
+                first.to_string()
+            } else {
+                format!("{first}-{last}")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Authors and commit years of every file in the git repository at `root`,
+/// by path relative to `root`. `None` outside a repository.
+fn git_history(root: &Path) -> Option<BTreeMap<String, Holders>> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(root)
+        .args([
+            "log",
+            "--relative",
+            "--name-only",
+            "--format=%x00%aN%x00%ad",
+        ])
+        .arg("--date=format:%Y")
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let mut history: BTreeMap<String, Holders> = BTreeMap::new();
+    let mut commit: Option<(String, u32)> = None;
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        if let Some(header) = line.strip_prefix('\0') {
+            commit = header
+                .split_once('\0')
+                .and_then(|(author, year)| Some((author.to_string(), year.parse().ok()?)));
+        } else if !line.is_empty()
+            && let Some((author, year)) = &commit
+        {
+            history
+                .entry(line.to_string())
+                .or_default()
+                .entry(author.clone())
+                .or_default()
+                .insert(*year);
+        }
+    }
+    Some(history)
+}
+
+/// The `origin` remote as a web URL, for the `Source:` field.
+pub fn upstream_source(root: &Path) -> Option<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(root)
+        .args(["remote", "get-url", "origin"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let url = String::from_utf8(output.stdout).ok()?;
+    let url = url.trim().trim_end_matches(".git");
+    // `git@github.com:owner/repo` is `https://github.com/owner/repo`.
+    let url = match url.strip_prefix("git@") {
+        Some(rest) => format!("https://{}", rest.replacen(':', "/", 1)),
+        None => url.to_string(),
+    };
+    url.starts_with("https://").then_some(url)
+}
+
+/// Debian's short name for an SPDX license: `Expat` for `MIT`, `GPL-2+` for
+/// `GPL-2.0-or-later`, and the SPDX id otherwise.
+pub fn short_name(id: &str) -> String {
+    if id == "MIT" {
+        return "Expat".to_string();
+    }
+    for family in ["AGPL", "GFDL", "GPL", "LGPL"] {
+        let Some(rest) = id.strip_prefix(family).and_then(|r| r.strip_prefix('-')) else {
+            continue;
+        };
+        let (version, later) = match (rest.strip_suffix("-only"), rest.strip_suffix("-or-later")) {
+            (Some(version), _) => (version, ""),
+            (_, Some(version)) => (version, "+"),
+            _ => continue,
+        };
+        let version = version.strip_suffix(".0").unwrap_or(version);
+        return format!("{family}-{version}{later}");
+    }
+    id.to_string()
+}
+
+/// One license as a `License:` name, such as `GPL-2+ with Classpath-2.0 exception`.
+pub fn term_name(term: &LicenseTerm) -> String {
+    let mut name = short_name(&term.id);
+    if term.or_later && !name.ends_with('+') {
+        name.push('+');
+    }
+    if let Some(exception) = &term.exception {
+        let keyword = exception.replace("-exception", "");
+        name.push_str(&format!(" with {keyword} exception"));
+    }
+    name
+}
+
+/// An expression as a `License:` field: lowercase `or` and `and`, with a
+/// comma before an `and` that joins alternatives.
+pub fn license_name(expression: &Expression) -> String {
+    match expression {
+        Expression::License(term) => term_name(term),
+        Expression::Or(terms) => terms
+            .iter()
+            .map(license_name)
+            .collect::<Vec<_>>()
+            .join(" or "),
+        Expression::And(terms) => {
+            let separator = if terms.iter().any(|t| matches!(t, Expression::Or(_))) {
+                ", and "
+            } else {
+                " and "
+            };
+            terms
+                .iter()
+                .map(license_name)
+                .collect::<Vec<_>>()
+                .join(separator)
+        }
+    }
+}
+
+/// Where Debian keeps the text of a license, if it ships it.
+pub fn common_license_path(term: &LicenseTerm) -> Option<String> {
+    let name = short_name(&term.id);
+    let name = name.trim_end_matches('+');
+    COMMON_LICENSES
+        .contains(&name)
+        .then(|| format!("/usr/share/common-licenses/{name}"))
+}
+
+/// A standalone `License:` paragraph, as `(name, text)`, for every license
+/// the `stanzas` use. See [`license_text`].
+pub async fn license_paragraphs(
+    stanzas: &[Stanza],
+    root: &Path,
+    providers: &ProviderChain,
+) -> Result<Vec<(String, String)>> {
+    let mut licenses: Vec<(String, String)> = Vec::new();
+    for stanza in stanzas {
+        for term in stanza.license.terms() {
+            let name = term_name(term);
+            if !licenses.iter().any(|(n, _)| *n == name) {
+                licenses.push((name, license_text(term, root, providers).await?));
+            }
+        }
+    }
+    Ok(licenses)
+}
+
+/// The standalone paragraph text for one license: a pointer into
+/// /usr/share/common-licenses when Debian ships it, `LICENSES/` for a
+/// `LicenseRef-`, and the template otherwise.
+pub async fn license_text(
+    term: &LicenseTerm,
+    root: &Path,
+    providers: &ProviderChain,
+) -> Result<String> {
+    let mut text = match common_license_path(term) {
+        Some(path) => {
+            let name = spdx::license_id(&term.id).map_or(term.id.as_str(), |l| l.full_name);
+            format!(
+                "On Debian systems, the complete text of the {name}\ncan be found in \"{path}\"."
+            )
+        }
+        None if term.id.starts_with("LicenseRef-") => {
+            let path = reuse::license_path(root, &term.id);
+            fs::read_to_string(&path).map_err(|e| Error::io(&path, e))?
+        }
+        None => without_title(&providers.resolve(&term.id).await?.body),
+    };
+    if let Some(exception) = &term.exception
+        && let Some(exception_text) = reuse::license_text(exception)
+    {
+        text.push_str("\n\n");
+        text.push_str(exception_text);
+    }
+    Ok(text)
+}
+
+/// A license template without the title and copyright notice at the top,
+/// which belong in the `Files:` stanzas.
+fn without_title(template: &str) -> String {
+    let lines: Vec<&str> = template.lines().collect();
+    let start = lines
+        .iter()
+        .take(5)
+        .rposition(|line| detect::is_notice(line))
+        .map(|i| i + 1)
+        .unwrap_or_else(|| match &lines[..] {
+            // A title such as `zlib License` above a blank line.
+            [title, "", ..] if title.len() < 80 && !title.ends_with(['.', ':']) => 1,
+            _ => 0,
+        });
+    let body: Vec<&str> = lines[start..]
+        .iter()
+        .copied()
+        .skip_while(|line| line.trim().is_empty())
+        .collect();
+    body.join("\n")
+}
+
+/// A DEP-5 file with a standalone `License:` paragraph for every
+/// `(name, text)` in `licenses`.
+pub fn render(upstream: &Upstream, stanzas: &[Stanza], licenses: &[(String, String)]) -> String {
+    let mut out = format!("Format: {FORMAT}\nUpstream-Name: {}\n", upstream.name);
+    if let Some(contact) = &upstream.contact {
+        out.push_str(&format!("Upstream-Contact: {contact}\n"));
+    }
+    if let Some(source) = &upstream.source {
+        out.push_str(&format!("Source: {source}\n"));
+    }
+
+    for stanza in stanzas {
+        out.push_str(&format!("\nFiles: {}\n", stanza.files.join("\n       ")));
+        let lines: Vec<String> = stanza
+            .copyright
+            .iter()
+            .map(|(holder, years)| {
+                if years.is_empty() {
+                    holder.clone()
+                } else {
+                    format!("{} {holder}", year_ranges(years))
+                }
+            })
+            .collect();
+        out.push_str(&format!("Copyright: {}\n", lines.join("\n           ")));
+        out.push_str(&format!("License: {}\n", license_name(&stanza.license)));
+    }
+
+    for (name, text) in licenses {
+        out.push_str(&format!("\nLicense: {name}\n"));
+        for line in text.trim().lines() {
+            match line.trim_end() {
+                "" => out.push_str(" .\n"),
+                line => out.push_str(&format!(" {line}\n")),
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn source(dir: &TempDir, path: &str, holder: &str, license: &str) {
+        let path = dir.path().join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(
+            path,
+            format!(
+                "// SPDX-FileCopyrightText: {holder}\n// SPDX-License-Identifier: {license}\nfn main() {{}}\n"
+            ),
+        )
+        .unwrap();
+    }
+
+    fn files(stanza: &Stanza) -> Vec<&str> {
+        stanza.files.iter().map(String::as_str).collect()
+    }
+
+    #[test]
+    fn stanzas_group_files_by_license() {
+        let dir = TempDir::new().unwrap();
+        source(&dir, "main.rs", "2024 Jane Doe", "MIT");
+        source(&dir, "vendor/a.rs", "2019-2021 Acme", "Apache-2.0");
+        source(&dir, "vendor/b.rs", "2023 Acme", "apache-2.0");
+        source(&dir, "src/lib.rs", "2025 Jane Doe", "MIT");
+        source(&dir, "src/zlib.rs", "2020 Zed", "Zlib");
+        fs::write(dir.path().join("notes.txt"), "No header here.\n").unwrap();
+
+        let mit = expression::parse("MIT").unwrap();
+        let fallback = holders(&["2026 Fallback".to_string()]);
+        let Stanzas { stanzas, invalid } = stanzas(dir.path(), Some(&mit), &fallback).unwrap();
+        assert!(invalid.is_empty());
+        assert_eq!(stanzas.len(), 3);
+
+        assert_eq!(files(&stanzas[0]), ["*"]);
+        assert_eq!(stanzas[0].license, mit);
+        let holders: Vec<&str> = stanzas[0].copyright.keys().map(String::as_str).collect();
+        assert_eq!(holders, ["Fallback", "Jane Doe"]);
+
+        assert_eq!(files(&stanzas[1]), ["src/zlib.rs"]);
+
+        // Every file in vendor/ is Apache-2.0, so the directory is collapsed.
+        assert_eq!(files(&stanzas[2]), ["vendor/*"]);
+        assert_eq!(stanzas[2].license.to_string(), "Apache-2.0");
+        assert_eq!(
+            stanzas[2].copyright["Acme"],
+            BTreeSet::from([2019, 2020, 2021, 2023])
+        );
+    }
+
+    #[test]
+    fn invalid_expressions_are_skipped_not_fatal() {
+        let dir = TempDir::new().unwrap();
+        source(&dir, "main.rs", "2024 Jane Doe", "MIT");
+        source(&dir, "quote.rs", "2024 Jane Doe", "\";");
+        source(&dir, "unknown.rs", "2024 Jane Doe", "Foo-1.0");
+
+        let mit = expression::parse("MIT").unwrap();
+        let result = stanzas(dir.path(), Some(&mit), &Holders::new()).unwrap();
+        assert_eq!(result.stanzas.len(), 1);
+        assert_eq!(
+            result.invalid,
+            [
+                ("quote.rs".to_string(), "\";".to_string()),
+                ("unknown.rs".to_string(), "Foo-1.0".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn patterns_escape_wildcards_and_spaces() {
+        assert_eq!(escape("docs/my file?.txt"), "docs/my?file\\?.txt");
+        assert_eq!(escape("a*b\\c"), "a\\*b\\\\c");
+    }
+
+    #[test]
+    fn license_names_follow_debian() {
+        let name = |expr: &str| license_name(&expression::parse(expr).unwrap().normalize());
+        assert_eq!(name("MIT OR Apache-2.0"), "Expat or Apache-2.0");
+        assert_eq!(name("GPL-2.0-or-later"), "GPL-2+");
+        assert_eq!(name("LGPL-2.1-only AND MIT"), "LGPL-2.1 and Expat");
+        assert_eq!(name("(MIT OR ISC) AND Zlib"), "Expat or ISC, and Zlib");
+        assert_eq!(
+            name("GPL-2.0-only WITH Classpath-exception-2.0"),
+            "GPL-2 with Classpath-2.0 exception"
+        );
+    }
+
+    #[test]
+    fn render_writes_stanzas_and_license_paragraphs() {
+        let upstream = Upstream {
+            name: "demo".to_string(),
+            contact: None,
+            source: Some("https://example.com/demo".to_string()),
+        };
+        let stanzas = [
+            Stanza {
+                files: vec!["*".to_string()],
+                copyright: holders(&["2019-2021, 2024 Jane Doe".to_string(), "Acme".to_string()]),
+                license: expression::parse("MIT").unwrap(),
+            },
+            Stanza {
+                files: vec!["a.c".to_string(), "b.c".to_string()],
+                copyright: Holders::new(),
+                license: expression::parse("Zlib").unwrap(),
+            },
+        ];
+        let licenses = [(
+            "Expat".to_string(),
+            "Permission is granted.\n\nNo warranty.\n".to_string(),
+        )];
+        assert_eq!(
+            render(&upstream, &stanzas, &licenses),
+            format!(
+                "Format: {FORMAT}\nUpstream-Name: demo\nSource: https://example.com/demo\n\
+                 \nFiles: *\nCopyright: Acme\n           2019-2021, 2024 Jane Doe\nLicense: Expat\n\
+                 \nFiles: a.c\n       b.c\nCopyright: \nLicense: Zlib\n\
+                 \nLicense: Expat\n Permission is granted.\n .\n No warranty.\n"
+            )
+        );
+    }
+
+    #[test]
+    fn templates_lose_their_title_and_notice() {
+        let mit =
+            "MIT License\n\nCopyright (c) [year] [fullname]\n\nPermission is hereby granted.\n";
+        assert_eq!(without_title(mit), "Permission is hereby granted.");
+        let zlib = "zlib License\n\nThis software is provided 'as-is'.\n";
+        assert_eq!(without_title(zlib), "This software is provided 'as-is'.");
+        let plain = "Redistribution is permitted.\n";
+        assert_eq!(without_title(plain), "Redistribution is permitted.");
+    }
+}
@@ -0,0 +1,668 @@
+//! Third-party dependencies and the licenses they declare, read from
+//! `Cargo.lock` (with `cargo metadata` or the local registry cache) and
+//! `package-lock.json`, without network access.
+
+use crate::expression::{self, Severity};
+use crate::{Error, Result};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+pub const LOCKFILES: &[&str] = &["Cargo.lock", "package-lock.json"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Ecosystem {
+    Cargo,
+    Npm,
+}
+
+impl Ecosystem {
+    pub fn name(self) -> &'static str {
+        match self {
+            Ecosystem::Cargo => "cargo",
+            Ecosystem::Npm => "npm",
+        }
+    }
+}
+
+/// How usable a declared license is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LicenseStatus {
+    /// A valid SPDX expression.
+    Spdx,
+    /// Free text, `SEE LICENSE IN ...`, or only a license file.
+    NonSpdx,
+    Missing,
+}
+
+impl LicenseStatus {
+    /// The name in JSON and CSV output.
+    pub fn id(self) -> &'static str {
+        match self {
+            LicenseStatus::Spdx => "spdx",
+            LicenseStatus::NonSpdx => "non-spdx",
+            LicenseStatus::Missing => "missing",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Dependency {
+    pub ecosystem: Ecosystem,
+    pub name: String,
+    pub version: String,
+    /// The declared license, normalized when it is an SPDX expression.
+    pub license: Option<String>,
+    /// The license file of packages that point to one instead of an expression.
+    pub license_file: Option<PathBuf>,
+    pub status: LicenseStatus,
+    /// The package's sources, when they are available locally.
+    pub path: Option<PathBuf>,
+    /// The shortest chain of packages, as `name version`, through which the
+    /// project depends on this one, starting with the project itself.
+    pub via: Vec<String>,
+}
+
+/// The third-party dependencies in the lockfiles in `dir`, sorted by
+/// ecosystem, name and version. `None` when `dir` has no lockfile.
+pub fn dependencies(dir: &Path) -> Result<Option<Vec<Dependency>>> {
+    let cargo = cargo_dependencies(dir, cargo_home().as_deref())?;
+    let npm = npm_dependencies(dir)?;
+    if cargo.is_none() && npm.is_none() {
+        return Ok(None);
+    }
+    let mut deps: Vec<Dependency> = cargo.into_iter().chain(npm).flatten().collect();
+    deps.sort_by(|a, b| {
+        (a.ecosystem, &a.name, &a.version).cmp(&(b.ecosystem, &b.name, &b.version))
+    });
+    deps.dedup_by(|a, b| (a.ecosystem, &a.name, &a.version) == (b.ecosystem, &b.name, &b.version));
+    Ok(Some(deps))
+}
+
+/// The license, normalized, and whether it is an SPDX expression. Cargo's
+/// old `MIT/Apache-2.0` form counts as `MIT OR Apache-2.0`.
+fn classify(license: Option<&str>, license_file: Option<&Path>) -> (Option<String>, LicenseStatus) {
+    let Some(license) = license.map(str::trim).filter(|l| !l.is_empty()) else {
+        let status = match license_file {
+            Some(_) => LicenseStatus::NonSpdx,
+            None => LicenseStatus::Missing,
+        };
+        return (None, status);
+    };
+    let spdx = |text: &str| {
+        let parsed = expression::parse(text).ok()?;
+        let valid = !parsed
+            .validate()
+            .iter()
+            .any(|issue| issue.severity == Severity::Error);
+        valid.then(|| parsed.normalize().to_string())
+    };
+    match spdx(license).or_else(|| spdx(&license.replace('/', " OR "))) {
+        Some(normalized) => (Some(normalized), LicenseStatus::Spdx),
+        None => (Some(license.to_string()), LicenseStatus::NonSpdx),
+    }
+}
+
+/// What `cargo metadata` knows about one package.
+struct CargoPackage {
+    license: Option<String>,
+    license_file: Option<PathBuf>,
+    dir: Option<PathBuf>,
+}
+
+/// Registry and git packages from `Cargo.lock`; path dependencies are part
+/// of the project. Packages `cargo metadata` does not know are looked up in
+/// the registry cache under `cargo_home`.
+fn cargo_dependencies(dir: &Path, cargo_home: Option<&Path>) -> Result<Option<Vec<Dependency>>> {
+    let path = dir.join("Cargo.lock");
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let text = fs::read_to_string(&path).map_err(|e| Error::io(&path, e))?;
+    let lock: toml::Table = toml::from_str(&text).map_err(|e| Error::Parse {
+        path: path.clone(),
+        message: e.to_string(),
+    })?;
+    let mut metadata = cargo_metadata(dir).unwrap_or_default();
+
+    let packages: Vec<&toml::Table> = lock
+        .get("package")
+        .and_then(|p| p.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|p| p.as_table())
+        .collect();
+    let field = |package: &toml::Table, key: &str| -> String {
+        package
+            .get(key)
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string()
+    };
+    let labels: Vec<String> = packages
+        .iter()
+        .map(|p| format!("{} {}", field(p, "name"), field(p, "version")))
+        .collect();
+    // `dependencies` entries are `name`, or `name version` when several versions are locked.
+    let edges: Vec<Vec<usize>> = packages
+        .iter()
+        .map(|package| {
+            let references = package.get("dependencies").and_then(|d| d.as_array());
+            references
+                .into_iter()
+                .flatten()
+                .filter_map(|reference| {
+                    let mut parts = reference.as_str()?.split_whitespace();
+                    let (name, version) = (parts.next()?, parts.next());
+                    packages.iter().position(|p| {
+                        field(p, "name") == name
+                            && version.is_none_or(|version| field(p, "version") == version)
+                    })
+                })
+                .collect()
+        })
+        .collect();
+    // Workspace members and path dependencies have no source and are the project.
+    let local: Vec<usize> = (0..packages.len())
+        .filter(|&i| !packages[i].contains_key("source"))
+        .collect();
+    let chains = chains(&edges, &local, &labels);
+
+    let mut deps = Vec::new();
+    for (i, package) in packages.iter().enumerate() {
+        if local.contains(&i) {
+            continue;
+        }
+        let (name, version) = (field(package, "name"), field(package, "version"));
+        let package = metadata
+            .remove(&(name.clone(), version.clone()))
+            .or_else(|| registry_package(cargo_home?, &name, &version))
+            .unwrap_or(CargoPackage {
+                license: None,
+                license_file: None,
+                dir: None,
+            });
+        let (license, status) =
+            classify(package.license.as_deref(), package.license_file.as_deref());
+        deps.push(Dependency {
+            ecosystem: Ecosystem::Cargo,
+            name,
+            version,
+            license,
+            license_file: package.license_file,
+            status,
+            path: package.dir,
+            via: chains[i].clone(),
+        });
+    }
+    Ok(Some(deps))
+}
+
+/// For every package, the labels of the packages on the shortest path to it
+/// from one of `roots`, starting with the root.
+fn chains(edges: &[Vec<usize>], roots: &[usize], labels: &[String]) -> Vec<Vec<String>> {
+    let mut parent: Vec<Option<usize>> = vec![None; edges.len()];
+    let mut seen: Vec<bool> = vec![false; edges.len()];
+    let mut queue: VecDeque<usize> = roots.iter().copied().collect();
+    for &root in roots {
+        seen[root] = true;
+    }
+    while let Some(node) = queue.pop_front() {
+        for &next in &edges[node] {
+            if !seen[next] {
+                seen[next] = true;
+                parent[next] = Some(node);
+                queue.push_back(next);
+            }
+        }
+    }
+    (0..edges.len())
+        .map(|node| {
+            let mut chain = Vec::new();
+            let mut current = parent[node];
+            while let Some(p) = current {
+                chain.push(labels[p].clone());
+                current = parent[p];
+            }
+            chain.reverse();
+            chain
+        })
+        .collect()
+}
+
+/// `cargo metadata --offline` for the workspace in `dir`, by name and version.
+/// `None` when cargo is missing or cannot resolve without the network.
+fn cargo_metadata(dir: &Path) -> Option<HashMap<(String, String), CargoPackage>> {
+    let cargo = env::var_os("CARGO").unwrap_or_else(|| "cargo".into());
+    let output = Command::new(cargo)
+        .args([
+            "metadata",
+            "--format-version",
+            "1",
+            "--offline",
+            "--manifest-path",
+        ])
+        .arg(dir.join("Cargo.toml"))
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let metadata: Value = serde_json::from_slice(&output.stdout).ok()?;
+    let packages = metadata["packages"].as_array()?;
+    Some(
+        packages
+            .iter()
+            .filter_map(|package| {
+                let name = package["name"].as_str()?.to_string();
+                let version = package["version"].as_str()?.to_string();
+                let dir = package["manifest_path"]
+                    .as_str()
+                    .and_then(|path| Path::new(path).parent())
+                    .map(Path::to_path_buf);
+                let license_file = package["license_file"].as_str().map(|file| {
+                    dir.as_ref()
+                        .map_or_else(|| file.into(), |dir| dir.join(file))
+                });
+                let package = CargoPackage {
+                    license: package["license"].as_str().map(str::to_string),
+                    license_file,
+                    dir,
+                };
+                Some(((name, version), package))
+            })
+            .collect(),
+    )
+}
+
+/// `$CARGO_HOME`, else `~/.cargo`.
+fn cargo_home() -> Option<PathBuf> {
+    env::var_os("CARGO_HOME")
+        .map(PathBuf::from)
+        .or_else(|| dirs::home_dir().map(|home| home.join(".cargo")))
+}
+
+/// A crate unpacked in the local registry cache, `<cargo_home>/registry/src/*/<name>-<version>`.
+fn registry_package(cargo_home: &Path, name: &str, version: &str) -> Option<CargoPackage> {
+    let dir = fs::read_dir(cargo_home.join("registry").join("src"))
+        .ok()?
+        .filter_map(|entry| Some(entry.ok()?.path().join(format!("{name}-{version}"))))
+        .find(|dir| dir.is_dir())?;
+    let manifest: toml::Table =
+        toml::from_str(&fs::read_to_string(dir.join("Cargo.toml")).ok()?).ok()?;
+    let package = manifest.get("package")?;
+    let field = |key: &str| package.get(key).and_then(|v| v.as_str());
+    Some(CargoPackage {
+        license: field("license").map(str::to_string),
+        license_file: field("license-file").map(|file| dir.join(file)),
+        dir: Some(dir),
+    })
+}
+
+/// Packages from `package-lock.json`, reading `node_modules` for licenses
+/// the lockfile does not record.
+fn npm_dependencies(dir: &Path) -> Result<Option<Vec<Dependency>>> {
+    let path = dir.join("package-lock.json");
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let text = fs::read_to_string(&path).map_err(|e| Error::io(&path, e))?;
+    let lock: Value = serde_json::from_str(&text).map_err(|source| Error::Json {
+        path: path.clone(),
+        source,
+    })?;
+
+    let mut deps = Vec::new();
+    let mut push = |location: &str, package: &Value, via: Vec<String>| {
+        let Some(name) = location
+            .rsplit("node_modules/")
+            .next()
+            .filter(|n| !n.is_empty())
+        else {
+            return;
+        };
+        let Some(version) = package["version"].as_str() else {
+            return;
+        };
+        let package_dir = dir.join(location);
+        let installed = || {
+            let text = fs::read_to_string(package_dir.join("package.json")).ok()?;
+            serde_json::from_str::<Value>(&text).ok()
+        };
+        let license =
+            npm_license(&package["license"]).or_else(|| npm_license(&installed()?["license"]));
+        let (license, status) = classify(license.as_deref(), None);
+        deps.push(Dependency {
+            ecosystem: Ecosystem::Npm,
+            name: name.to_string(),
+            version: version.to_string(),
+            license,
+            license_file: None,
+            status,
+            path: package_dir.is_dir().then_some(package_dir),
+            via,
+        });
+    };
+
+    if let Some(packages) = lock["packages"].as_object() {
+        // Lockfile version 2 and 3: `node_modules/<name>` paths, the project itself at "".
+        let locations: Vec<&String> = packages.keys().collect();
+        let labels: Vec<String> = locations
+            .iter()
+            .map(|location| {
+                let package = &packages[location.as_str()];
+                let name = match location.rsplit("node_modules/").next() {
+                    Some(name) if !location.is_empty() => name,
+                    _ => package["name"]
+                        .as_str()
+                        .or(lock["name"].as_str())
+                        .unwrap_or("project"),
+                };
+                match package["version"].as_str() {
+                    Some(version) => format!("{name} {version}"),
+                    None => name.to_string(),
+                }
+            })
+            .collect();
+        let edges: Vec<Vec<usize>> = locations
+            .iter()
+            .map(|location| {
+                let package = &packages[location.as_str()];
+                [
+                    "dependencies",
+                    "devDependencies",
+                    "optionalDependencies",
+                    "peerDependencies",
+                ]
+                .iter()
+                .filter_map(|key| package[*key].as_object())
+                .flat_map(|names| names.keys())
+                .filter_map(|name| {
+                    let resolved = resolve_npm(location, name, packages)?;
+                    locations.iter().position(|l| **l == resolved)
+                })
+                .collect()
+            })
+            .collect();
+        let roots: Vec<usize> = locations
+            .iter()
+            .position(|l| l.is_empty())
+            .into_iter()
+            .collect();
+        let chains = chains(&edges, &roots, &labels);
+        for (i, location) in locations.iter().enumerate() {
+            let package = &packages[location.as_str()];
+            // Workspace packages such as `packages/local` are the project.
+            let installed = location.contains("node_modules/");
+            if installed && package["link"] != Value::Bool(true) {
+                push(location, package, chains[i].clone());
+            }
+        }
+    } else if let Some(dependencies) = lock["dependencies"].as_object() {
+        // Lockfile version 1: nested `dependencies`, without licenses.
+        let mut stack: Vec<(String, &serde_json::Map<String, Value>)> =
+            vec![(String::new(), dependencies)];
+        while let Some((prefix, dependencies)) = stack.pop() {
+            for (name, package) in dependencies {
+                let location = format!("{prefix}node_modules/{name}");
+                push(&location, package, Vec::new());
+                if let Some(nested) = package["dependencies"].as_object() {
+                    stack.push((format!("{location}/"), nested));
+                }
+            }
+        }
+    }
+    Ok(Some(deps))
+}
+
+/// Where Node finds `name` when required from the package at `location`: the
+/// closest `node_modules/<name>` up the tree.
+fn resolve_npm(
+    location: &str,
+    name: &str,
+    packages: &serde_json::Map<String, Value>,
+) -> Option<String> {
+    let mut base = location;
+    loop {
+        let candidate = match base {
+            "" => format!("node_modules/{name}"),
+            base => format!("{base}/node_modules/{name}"),
+        };
+        if packages.contains_key(&candidate) {
+            return Some(candidate);
+        }
+        if base.is_empty() {
+            return None;
+        }
+        base = base
+            .rfind("node_modules/")
+            .map_or("", |i| base[..i].trim_end_matches('/'));
+    }
+}
+
+/// `"MIT"`, or the legacy `{ "type": "MIT" }` and `[{ "type": "MIT" }, ...]` forms.
+fn npm_license(value: &Value) -> Option<String> {
+    match value {
+        Value::String(license) => Some(license.clone()),
+        Value::Object(license) => license.get("type")?.as_str().map(str::to_string),
+        Value::Array(licenses) => {
+            let ids: Vec<String> = licenses.iter().filter_map(npm_license).collect();
+            (!ids.is_empty()).then(|| ids.join(" OR "))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/deps")
+            .join(name)
+    }
+
+    /// `(name version, license, status, via)` for each dependency.
+    fn summary(deps: &[Dependency]) -> Vec<(String, Option<&str>, LicenseStatus, String)> {
+        let mut summary: Vec<_> = deps
+            .iter()
+            .map(|dep| {
+                (
+                    format!("{} {}", dep.name, dep.version),
+                    dep.license.as_deref(),
+                    dep.status,
+                    dep.via.join(" > "),
+                )
+            })
+            .collect();
+        summary.sort_by(|a, b| a.0.cmp(&b.0));
+        summary
+    }
+
+    #[test]
+    fn classification() {
+        let file = Path::new("LICENSE.txt");
+        for (license, license_file, expected, status) in [
+            (Some("MIT"), None, Some("MIT"), LicenseStatus::Spdx),
+            (
+                Some("mit or apache-2.0"),
+                None,
+                Some("MIT OR Apache-2.0"),
+                LicenseStatus::Spdx,
+            ),
+            (
+                Some("MIT/Apache-2.0"),
+                None,
+                Some("MIT OR Apache-2.0"),
+                LicenseStatus::Spdx,
+            ),
+            (
+                Some("GPL-2.0+"),
+                None,
+                Some("GPL-2.0-or-later"),
+                LicenseStatus::Spdx,
+            ),
+            (
+                Some("LicenseRef-Acme"),
+                None,
+                Some("LicenseRef-Acme"),
+                LicenseStatus::Spdx,
+            ),
+            (
+                Some("Foo-1.0"),
+                None,
+                Some("Foo-1.0"),
+                LicenseStatus::NonSpdx,
+            ),
+            (
+                Some("SEE LICENSE IN LICENSE.md"),
+                None,
+                Some("SEE LICENSE IN LICENSE.md"),
+                LicenseStatus::NonSpdx,
+            ),
+            (Some("  "), Some(file), None, LicenseStatus::NonSpdx),
+            (None, Some(file), None, LicenseStatus::NonSpdx),
+            (None, None, None, LicenseStatus::Missing),
+            (Some(""), None, None, LicenseStatus::Missing),
+        ] {
+            let (normalized, found) = classify(license, license_file);
+            assert_eq!(
+                (normalized.as_deref(), found),
+                (expected, status),
+                "{license:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn cargo_lock_with_the_registry_cache() {
+        let dir = tempfile::TempDir::new().unwrap();
+        fs::copy(fixture("cargo/lock.toml"), dir.path().join("Cargo.lock")).unwrap();
+        let cargo_home = fixture("cargo-home");
+        let deps = cargo_dependencies(dir.path(), Some(&cargo_home))
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            summary(&deps),
+            [
+                (
+                    "fixture-custom 2.0.0".to_string(),
+                    Some("Acme Proprietary"),
+                    LicenseStatus::NonSpdx,
+                    "app 0.1.0 > fixture-old 0.1.0".to_string()
+                ),
+                (
+                    "fixture-dual 1.0.0".to_string(),
+                    Some("MIT OR Apache-2.0"),
+                    LicenseStatus::Spdx,
+                    "app 0.1.0".to_string()
+                ),
+                (
+                    "fixture-file 0.3.1".to_string(),
+                    None,
+                    LicenseStatus::NonSpdx,
+                    "app 0.1.0 > fixture-dual 1.0.0".to_string()
+                ),
+                (
+                    "fixture-old 0.1.0".to_string(),
+                    Some("GPL-2.0-or-later"),
+                    LicenseStatus::Spdx,
+                    "app 0.1.0".to_string()
+                ),
+                (
+                    "fixture-old 0.2.0".to_string(),
+                    None,
+                    LicenseStatus::Missing,
+                    "helper 0.1.0".to_string()
+                ),
+            ]
+        );
+        let file = deps.iter().find(|d| d.name == "fixture-file").unwrap();
+        let sources = file.path.as_ref().unwrap();
+        assert!(sources.ends_with("fixture-file-0.3.1"));
+        assert_eq!(file.license_file, Some(sources.join("LICENSE.txt")));
+
+        assert!(cargo_dependencies(&fixture("npm"), None).unwrap().is_none());
+    }
+
+    #[test]
+    fn package_lock_with_node_modules() {
+        let deps = npm_dependencies(&fixture("npm")).unwrap().unwrap();
+        assert_eq!(
+            summary(&deps),
+            [
+                (
+                    "left-pad 1.3.0".to_string(),
+                    Some("WTFPL"),
+                    LicenseStatus::Spdx,
+                    "web 1.0.0".to_string()
+                ),
+                (
+                    "left-pad 2.0.0".to_string(),
+                    Some("MIT OR ISC"),
+                    LicenseStatus::Spdx,
+                    "web 1.0.0 > legacy 1.0.0".to_string()
+                ),
+                (
+                    "legacy 1.0.0".to_string(),
+                    Some("MIT OR Apache-2.0"),
+                    LicenseStatus::Spdx,
+                    "web 1.0.0".to_string()
+                ),
+                (
+                    "orphan 0.0.1".to_string(),
+                    None,
+                    LicenseStatus::Missing,
+                    String::new()
+                ),
+                (
+                    "tool 2.0.0".to_string(),
+                    Some("SEE LICENSE IN LICENSE.md"),
+                    LicenseStatus::NonSpdx,
+                    "web 1.0.0".to_string()
+                ),
+            ]
+        );
+        let legacy = deps.iter().find(|d| d.name == "legacy").unwrap();
+        assert_eq!(
+            legacy.path.as_deref(),
+            Some(fixture("npm").join("node_modules/legacy").as_path())
+        );
+    }
+
+    #[test]
+    fn package_lock_version_1() {
+        let dir = tempfile::TempDir::new().unwrap();
+        fs::write(
+            dir.path().join("package-lock.json"),
+            r#"{
+                "name": "old",
+                "lockfileVersion": 1,
+                "dependencies": {
+                    "a": { "version": "1.0.0", "dependencies": { "b": { "version": "2.0.0" } } }
+                }
+            }"#,
+        )
+        .unwrap();
+        let deps = dependencies(dir.path()).unwrap().unwrap();
+        let names: Vec<(&str, &str, LicenseStatus)> = deps
+            .iter()
+            .map(|d| (d.name.as_str(), d.version.as_str(), d.status))
+            .collect();
+        assert_eq!(
+            names,
+            [
+                ("a", "1.0.0", LicenseStatus::Missing),
+                ("b", "2.0.0", LicenseStatus::Missing)
+            ]
+        );
+        assert!(dependencies(&fixture("cargo-home")).unwrap().is_none());
+    }
+}
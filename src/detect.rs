@@ -0,0 +1,491 @@
+//! Recognising which license an existing file contains.
+//!
+//! Texts are normalised (case, punctuation, bullets, copyright notices) and
+//! compared by the Sørensen–Dice coefficient of their word bigrams against
+//! every non-deprecated license on the SPDX License List.
+
+use crate::expression::{self, Expression};
+use crate::{Result, copyright, manifest, reuse};
+use serde::Serialize;
+use spdx::identifiers::{IS_DEPRECATED, LICENSES};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// Matches below this score are reported as unrecognised.
+pub const MIN_CONFIDENCE: f64 = 0.75;
+
+/// Detections below this are not trusted for writing the id into other files.
+pub const CERTAIN_CONFIDENCE: f64 = 0.9;
+
+/// Two licenses scoring within this of each other are considered the same text.
+const TIE: f64 = 0.002;
+
+/// Licenses scoring within this of the best are close enough that the more
+/// common one is reported.
+const NEAR_TIE: f64 = 0.01;
+
+/// Where the terms of the Apache and GNU licenses end and their appendix begins.
+const END_OF_TERMS: &str = "END OF TERMS AND CONDITIONS";
+
+/// The most common licenses, most common first; they win near-ties.
+const POPULAR: &[&str] = &[
+    "MIT",
+    "Apache-2.0",
+    "BSD-3-Clause",
+    "BSD-2-Clause",
+    "ISC",
+    "GPL-3.0-only",
+    "GPL-3.0-or-later",
+    "GPL-2.0-only",
+    "GPL-2.0-or-later",
+    "LGPL-3.0-only",
+    "LGPL-2.1-only",
+    "MPL-2.0",
+    "AGPL-3.0-only",
+    "Unlicense",
+    "0BSD",
+    "Zlib",
+    "BSL-1.0",
+    "CC0-1.0",
+    "Unicode-3.0",
+];
+
+/// The license a text most resembles.
+#[derive(Debug, Clone, Serialize)]
+pub struct Detection {
+    pub spdx_id: String,
+    pub name: String,
+    /// Sørensen–Dice similarity in `0.0..=1.0`.
+    pub confidence: f64,
+    /// Licenses with the same text, such as `GPL-3.0-or-later` for `GPL-3.0-only`.
+    pub alternatives: Vec<String>,
+    /// Another license scored higher and lost the near-tie to this more common one.
+    pub contested: bool,
+    pub copyrights: Vec<CopyrightNotice>,
+}
+
+impl Detection {
+    pub fn is_confident(&self) -> bool {
+        self.confidence >= MIN_CONFIDENCE
+    }
+
+    /// Confident enough to write the id into headers and manifests: a close
+    /// match that no other license scored as high as.
+    pub fn is_certain(&self) -> bool {
+        self.confidence >= CERTAIN_CONFIDENCE && !self.contested
+    }
+}
+
+/// A `Copyright ...` line found in a license file.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct CopyrightNotice {
+    /// The years as written, e.g. `2019-2024`.
+    pub years: String,
+    pub holder: String,
+}
+
+/// Compares `text` against the SPDX License List. Returns `None` only for
+/// text without any words; check [`Detection::is_confident`] for the verdict.
+pub fn detect(text: &str) -> Option<Detection> {
+    let text_words = normalize(text);
+    let bigrams = Bigrams::new(&text_words);
+    if bigrams.total == 0 {
+        return None;
+    }
+
+    // Compare the licenses closest in length first, so that the bound below
+    // rules out most of the list without building its bigrams.
+    let mut candidates: Vec<(usize, &str, &str, &str)> = LICENSES
+        .iter()
+        .filter(|(_, _, flags)| flags & IS_DEPRECATED == 0)
+        .filter_map(|(id, name, _)| Some((*id, *name, spdx::license_id(id)?.text())))
+        .flat_map(|(id, name, template)| {
+            variants(template).map(move |variant| {
+                let total = words(variant).count().saturating_sub(1);
+                (total, id, name, variant)
+            })
+        })
+        .collect();
+    candidates.sort_by_key(|(total, ..)| total.abs_diff(bigrams.total));
+
+    let mut scored: Vec<(f64, &str, &str, &str)> = Vec::new();
+    let mut best = 0.0;
+    for (other_total, id, name, template) in candidates {
+        // Dice can be no higher than this for texts of these lengths.
+        let bound = 2.0 * bigrams.total.min(other_total) as f64
+            / (bigrams.total + other_total).max(1) as f64;
+        if bound + NEAR_TIE < best {
+            continue;
+        }
+        let score = bigrams.dice(&Bigrams::new(&normalize(template)));
+        if score > best {
+            best = score;
+        }
+        match scored.iter_mut().find(|(_, other, ..)| *other == id) {
+            Some(entry) if entry.0 < score => *entry = (score, id, name, template),
+            Some(_) => {}
+            None => scored.push((score, id, name, template)),
+        }
+    }
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+    // Among near-ties, such as Apache-2.0 and its derivative Pixar, the more
+    // common license wins.
+    let &(top, ..) = scored.first()?;
+    let &(confidence, spdx_id, name, template) = scored
+        .iter()
+        .take_while(|(score, ..)| top - score <= NEAR_TIE)
+        .reduce(|best, entry| {
+            if (preference(entry.1), entry.0) > (preference(best.1), best.0) {
+                entry
+            } else {
+                best
+            }
+        })?;
+    let alternatives = scored
+        .iter()
+        .filter(|(score, id, ..)| *id != spdx_id && (confidence - score).abs() <= TIE)
+        .map(|(_, id, ..)| id.to_string())
+        .collect();
+    let contested = top - confidence > TIE;
+    // Notices that are part of the license text itself, like the FSF's in the GPL.
+    let template_lines: Vec<String> = template
+        .lines()
+        .filter(|line| is_notice(line))
+        .map(line_words)
+        .collect();
+    let copyrights = copyright_notices(text)
+        .into_iter()
+        .filter(|(line, _)| !template_lines.contains(&line_words(line)))
+        .map(|(_, notice)| notice)
+        .collect();
+
+    Some(Detection {
+        spdx_id: spdx_id.to_string(),
+        name: name.to_string(),
+        confidence,
+        alternatives,
+        contested,
+        copyrights,
+    })
+}
+
+/// The licenses recognized in `dir`'s license files, as alternatives. When a
+/// license file is not recognized with certainty, the license its manifest
+/// declares is used instead, or nothing when there is none. Without license
+/// files, the manifest or the REUSE `LICENSES/` directory is used.
+pub fn project_expression(dir: &Path) -> Option<Expression> {
+    let mut ids: Vec<String> = Vec::new();
+    let mut uncertain = false;
+    for path in crate::find_license_files(dir) {
+        let Ok(text) = fs::read_to_string(&path) else {
+            continue;
+        };
+        match detect(&text) {
+            Some(detection) if detection.is_certain() && !ids.contains(&detection.spdx_id) => {
+                ids.push(detection.spdx_id);
+            }
+            Some(detection) if !detection.is_certain() => uncertain = true,
+            _ => {}
+        }
+    }
+    if uncertain || ids.is_empty() {
+        if let Ok(Some(declared)) = manifest::declared_license(dir) {
+            return Some(declared);
+        }
+        if uncertain {
+            return None;
+        }
+        ids = reuse::stored_licenses(dir);
+    }
+    expression::parse(&ids.join(" OR "))
+        .ok()
+        .map(|e| e.normalize())
+}
+
+/// The license the project in `dir` declares in its manifest, or else the one
+/// recognized in its license files, see [`project_expression`].
+pub fn project_license(dir: &Path) -> Result<Option<Expression>> {
+    if let Some(declared) = manifest::declared_license(dir)? {
+        return Ok(Some(declared));
+    }
+    Ok(project_expression(dir))
+}
+
+/// The forms a license text is commonly distributed in: the full SPDX text,
+/// and the terms alone without the "how to apply" appendix, as in the
+/// `LICENSE-APACHE` most Rust crates ship.
+fn variants(template: &str) -> impl Iterator<Item = &str> {
+    let terms = template
+        .find(END_OF_TERMS)
+        .map(|end| &template[..end + END_OF_TERMS.len()])
+        .filter(|terms| terms.len() < template.trim_end().len());
+    std::iter::once(template).chain(terms)
+}
+
+/// How strongly a near-tie should go to `id`: widely used licenses first,
+/// then OSI-approved ones.
+fn preference(id: &str) -> (usize, bool) {
+    let popular = POPULAR
+        .iter()
+        .position(|p| *p == id)
+        .map_or(0, |rank| POPULAR.len() - rank);
+    let osi = spdx::license_id(id).is_some_and(|l| l.is_osi_approved());
+    (popular, osi)
+}
+
+/// How close `text` is to the SPDX License List text of `spdx_id`, as in
+/// [`Detection::confidence`]; much faster than [`detect`] when the license is expected.
+pub fn similarity(text: &str, spdx_id: &str) -> Option<f64> {
+    let template = spdx::license_id(spdx_id)?.text();
+    let words = normalize(text);
+    let bigrams = Bigrams::new(&words);
+    variants(template)
+        .map(|variant| bigrams.dice(&Bigrams::new(&normalize(variant))))
+        .max_by(f64::total_cmp)
+}
+
+/// The `Copyright <years> <holder>` notices in `text`, with the line each came from.
+pub fn copyright_notices(text: &str) -> Vec<(&str, CopyrightNotice)> {
+    text.lines()
+        .filter(|line| is_notice(line))
+        .filter_map(|line| {
+            let years = copyright::years_in(line);
+            let (&(first, _, _), &(_, last, _)) = (years.first()?, years.last()?);
+            let after = line[last..].trim_start_matches([',', '-', '–', ' ']);
+            // `Copyright Jane Doe 2024` puts the holder before the years.
+            let holder = if after.trim().is_empty() {
+                strip_notice_prefix(&line[..first])
+            } else {
+                after
+            };
+            let holder = holder.trim_end_matches(|c: char| c == '.' || c.is_whitespace());
+            let holder = holder
+                .strip_suffix("All rights reserved")
+                .unwrap_or(holder)
+                .trim_end_matches([' ', ',', '.']);
+            (!holder.is_empty()).then(|| {
+                let notice = CopyrightNotice {
+                    years: line[first..last].to_string(),
+                    holder: holder.to_string(),
+                };
+                (line, notice)
+            })
+        })
+        .collect()
+}
+
+/// A line that starts with `Copyright`, `(c)` or `©` once bullets and comment
+/// markers are stripped. License prose that wraps onto a line starting with
+/// the word, like Apache's "copyright owner or entity ...", is not a notice:
+/// `Copyright` must be followed by a symbol, placeholder or year.
+pub fn is_notice(line: &str) -> bool {
+    let line = strip_bullet(line).to_lowercase();
+    if line.starts_with("(c)") || line.starts_with('©') {
+        return true;
+    }
+    let Some(rest) = line.strip_prefix("copyright") else {
+        return false;
+    };
+    let rest = rest.trim_start();
+    !rest.starts_with(char::is_alphabetic) || !copyright::years_in(rest).is_empty()
+}
+
+/// The text after the leading `Copyright`, `(c)` and `©` markers.
+fn strip_notice_prefix(line: &str) -> &str {
+    let mut rest = strip_bullet(line).trim_start();
+    while let Some(after) = ["copyright", "(c)", "©"].iter().find_map(|marker| {
+        rest.get(..marker.len())
+            .filter(|prefix| prefix.eq_ignore_ascii_case(marker))
+            .map(|_| rest[marker.len()..].trim_start())
+    }) {
+        rest = after;
+    }
+    rest
+}
+
+fn strip_bullet(line: &str) -> &str {
+    line.trim_start_matches(|c: char| {
+        c.is_whitespace() || matches!(c, '*' | '-' | '#' | '/' | ';' | '•' | '>')
+    })
+}
+
+/// The words of `text` outside copyright notices, without punctuation,
+/// bullets or list numbering.
+fn words(text: &str) -> impl Iterator<Item = &str> {
+    text.lines()
+        .filter(|line| !is_notice(line))
+        .flat_map(|line| strip_bullet(line).split(|c: char| !c.is_alphanumeric()))
+        .filter(|word| !word.is_empty())
+}
+
+/// The lower-cased [`words`], with British spellings folded into American ones.
+fn normalize(text: &str) -> Vec<String> {
+    words(text)
+        .map(|word| match word.to_lowercase().as_str() {
+            "licence" => "license".to_string(),
+            "licences" => "licenses".to_string(),
+            "licenced" => "licensed".to_string(),
+            word => word.to_string(),
+        })
+        .collect()
+}
+
+/// The words of a notice after the `Copyright (c)` markers, so that
+/// `Copyright (C) 2007 ...` and `Copyright © 2007 ...` compare equal.
+fn line_words(line: &str) -> String {
+    strip_notice_prefix(line)
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// The multiset of adjacent word pairs in a text.
+struct Bigrams<'a> {
+    counts: HashMap<(&'a str, &'a str), usize>,
+    total: usize,
+}
+
+impl<'a> Bigrams<'a> {
+    fn new(words: &'a [String]) -> Self {
+        let mut counts = HashMap::new();
+        for pair in words.windows(2) {
+            *counts
+                .entry((pair[0].as_str(), pair[1].as_str()))
+                .or_insert(0) += 1;
+        }
+        Self {
+            counts,
+            total: words.len().saturating_sub(1),
+        }
+    }
+
+    /// `2 |A ∩ B| / (|A| + |B|)` over the bigram multisets.
+    fn dice(&self, other: &Bigrams) -> f64 {
+        if self.total + other.total == 0 {
+            return 0.0;
+        }
+        let shared: usize = self
+            .counts
+            .iter()
+            .map(|(pair, count)| (*count).min(other.counts.get(pair).copied().unwrap_or(0)))
+            .sum();
+        2.0 * shared as f64 / (self.total + other.total) as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::Path;
+
+    fn fixture(name: &str) -> String {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/licenses")
+            .join(name);
+        fs::read_to_string(path).unwrap()
+    }
+
+    fn detected(text: &str) -> Detection {
+        let detection = detect(text).unwrap();
+        assert!(
+            detection.is_certain(),
+            "{} at {} (contested: {})",
+            detection.spdx_id,
+            detection.confidence,
+            detection.contested
+        );
+        detection
+    }
+
+    #[test]
+    fn project_expression_from_license_files_manifest_or_reuse() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path();
+        fs::write(path.join("LICENSE-MIT"), fixture("rust-LICENSE-MIT")).unwrap();
+        fs::write(path.join("LICENSE-APACHE"), fixture("rust-LICENSE-APACHE")).unwrap();
+        let expression = project_expression(path).unwrap();
+        assert_eq!(expression.to_string(), "Apache-2.0 OR MIT");
+
+        // A license file that is not recognized leaves it to the manifest.
+        fs::write(path.join("LICENSE"), "All rights reserved by Acme.\n").unwrap();
+        assert_eq!(project_expression(path), None);
+        fs::write(
+            path.join("Cargo.toml"),
+            "[package]\nname = \"demo\"\nlicense = \"MIT\"\n",
+        )
+        .unwrap();
+        assert_eq!(project_expression(path).unwrap().to_string(), "MIT");
+
+        let reuse = tempfile::TempDir::new().unwrap();
+        fs::create_dir(reuse.path().join("LICENSES")).unwrap();
+        for name in ["MIT.txt", "CC0-1.0.txt", "Foo.txt"] {
+            fs::write(reuse.path().join("LICENSES").join(name), "text").unwrap();
+        }
+        let expression = project_expression(reuse.path()).unwrap();
+        assert_eq!(expression.to_string(), "CC0-1.0 OR MIT");
+    }
+
+    #[test]
+    fn rust_apache_without_appendix_is_apache_not_pixar() {
+        let detection = detected(&fixture("rust-LICENSE-APACHE"));
+        assert_eq!(detection.spdx_id, "Apache-2.0");
+        assert!(!detection.alternatives.contains(&"Pixar".to_string()));
+    }
+
+    #[test]
+    fn pixar_is_still_pixar() {
+        let text = spdx::license_id("Pixar").unwrap().text();
+        assert_eq!(detect(text).unwrap().spdx_id, "Pixar");
+    }
+
+    #[test]
+    fn rust_mit() {
+        assert_eq!(detected(&fixture("rust-LICENSE-MIT")).spdx_id, "MIT");
+    }
+
+    #[test]
+    fn go_bsd_3_clause() {
+        let detection = detected(&fixture("go-LICENSE"));
+        assert_eq!(detection.spdx_id, "BSD-3-Clause");
+        assert_eq!(detection.copyrights.len(), 1);
+        assert_eq!(detection.copyrights[0].holder, "The Go Authors");
+    }
+
+    #[test]
+    fn node_mit_and_isc() {
+        let mit = detected(&fixture("node-LICENSE"));
+        assert_eq!(mit.spdx_id, "MIT");
+        assert_eq!(mit.copyrights.len(), 3);
+        assert_eq!(detected(&fixture("node-ISC-LICENSE")).spdx_id, "ISC");
+    }
+
+    #[test]
+    fn gpl_versions_are_alternatives() {
+        let text = spdx::license_id("GPL-3.0-only").unwrap().text();
+        let detection = detected(text);
+        assert!(detection.spdx_id.starts_with("GPL-3.0"));
+        assert!(
+            detection
+                .alternatives
+                .iter()
+                .any(|a| a.starts_with("GPL-3.0"))
+        );
+    }
+
+    #[test]
+    fn notices_are_not_license_prose() {
+        assert!(is_notice("Copyright (c) 2024 Jane Doe"));
+        assert!(is_notice("Copyright 2009 The Go Authors."));
+        assert!(is_notice("// © Acme"));
+        assert!(is_notice("Copyright [yyyy] [name of copyright owner]"));
+        assert!(!is_notice(
+            "copyright notice, this list of conditions and the following disclaimer."
+        ));
+        assert!(!is_notice("copyright owner or entity authorized by"));
+    }
+}
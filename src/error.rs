@@ -0,0 +1,113 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error(transparent)]
+    Http(#[from] reqwest::Error),
+
+    #[error(transparent)]
+    Walk(#[from] ignore::Error),
+
+    /// The API refused the request because of rate limiting; the message says when to retry.
+    #[error("{0}")]
+    RateLimited(String),
+
+    #[error(
+        "GitHub rejected the token (401 Unauthorized). Check GITHUB_TOKEN, GH_TOKEN or --token."
+    )]
+    Unauthorized,
+
+    #[error("Failed to access {}: {source}", path.display())]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+
+    #[error("Invalid JSON in {}: {source}", path.display())]
+    Json {
+        path: PathBuf,
+        #[source]
+        source: serde_json::Error,
+    },
+
+    #[error("Invalid config file {}: {source}", path.display())]
+    Config {
+        path: PathBuf,
+        #[source]
+        source: toml::de::Error,
+    },
+
+    #[error("Failed to parse {}: {message}", path.display())]
+    Parse { path: PathBuf, message: String },
+
+    #[error("Unknown license '{key}'.{}", did_you_mean(suggestions))]
+    UnknownLicense {
+        key: String,
+        suggestions: Vec<String>,
+    },
+
+    #[error("'{key}' matches several licenses: {}. Pick one of these keys.", candidates.join(", "))]
+    AmbiguousLicense {
+        key: String,
+        candidates: Vec<String>,
+    },
+
+    #[error("Invalid license expression '{expr}': {message} (at position {offset}).")]
+    InvalidExpression {
+        expr: String,
+        offset: usize,
+        message: String,
+    },
+
+    #[error("The local source needs --templates-dir or LIC_TEMPLATES_DIR.")]
+    MissingTemplatesDir,
+
+    /// License files that writing would replace.
+    #[error("{}", already_exist(.0))]
+    LicenseFilesExist(Vec<PathBuf>),
+
+    #[error("{} must be a directory to write {count} licenses into.", path.display())]
+    NotADirectory { path: PathBuf, count: usize },
+
+    #[error("Several licenses would be written to the same file: {}", .0.join(", "))]
+    SameFileName(Vec<String>),
+}
+
+impl Error {
+    pub fn io(path: impl AsRef<Path>, source: io::Error) -> Self {
+        Error::Io {
+            path: path.as_ref().to_path_buf(),
+            source,
+        }
+    }
+
+    /// True for HTTP 404 responses, which providers treat as "license not found".
+    pub fn is_not_found(&self) -> bool {
+        matches!(self, Error::Http(e) if e.status() == Some(reqwest::StatusCode::NOT_FOUND))
+    }
+}
+
+fn did_you_mean(suggestions: &[String]) -> String {
+    if suggestions.is_empty() {
+        String::new()
+    } else {
+        format!(" Did you mean: {}?", suggestions.join(", "))
+    }
+}
+
+fn already_exist(paths: &[PathBuf]) -> String {
+    let names: Vec<String> = paths.iter().map(|p| p.display().to_string()).collect();
+    let (exists, it) = if names.len() == 1 {
+        ("exists", "it")
+    } else {
+        ("exist", "them")
+    };
+    format!(
+        "{} already {exists}. Use --force to overwrite {it} or --dry-run to preview the changes.",
+        names.join(", ")
+    )
+}
@@ -0,0 +1,763 @@
+//! SPDX license expressions such as `MIT OR (Apache-2.0 AND GPL-2.0+)`.
+//!
+//! [`parse`] only checks the syntax, including that every id is made of the
+//! characters SPDX allows; [`Expression::validate`] checks every id against
+//! the SPDX license and exception lists compiled into the binary.
+
+use crate::spdx_list::SpdxLicenses;
+use crate::{Error, Result, fuzzy};
+use spdx::identifiers::{EXCEPTIONS, IS_DEPRECATED, LICENSES};
+use std::fmt;
+
+/// A parsed license expression. `AND` binds tighter than `OR`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expression {
+    License(LicenseTerm),
+    And(Vec<Expression>),
+    Or(Vec<Expression>),
+}
+
+/// A license id with its optional `+` and `WITH` exception, e.g.
+/// `GPL-2.0-or-later WITH Classpath-exception-2.0` or `LicenseRef-Acme`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LicenseTerm {
+    pub id: String,
+    pub or_later: bool,
+    pub exception: Option<String>,
+}
+
+/// A problem found by [`Expression::validate`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Issue {
+    pub severity: Severity,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// The expression is not valid SPDX.
+    Error,
+    /// Valid, but deprecated or not written in canonical form.
+    Warning,
+}
+
+/// Parses `expr`, failing with [`Error::InvalidExpression`] on syntax errors.
+pub fn parse(expr: &str) -> Result<Expression> {
+    let mut parser = Parser {
+        expr,
+        tokens: tokenize(expr),
+        pos: 0,
+    };
+    let expression = parser.parse_or()?;
+    if let Some(&(offset, token)) = parser.tokens.get(parser.pos) {
+        let message = match token {
+            Token::Close => "unbalanced ')'".to_string(),
+            _ => format!("expected AND or OR before {token}"),
+        };
+        return Err(parser.error(offset, message));
+    }
+    Ok(expression)
+}
+
+/// The expression for licenses written with `spdx_ids`: `expr`, the
+/// `--license` value they were picked with, with its keys replaced by the ids
+/// in order, or without one the ids as alternatives.
+pub fn for_licenses(expr: Option<&str>, spdx_ids: &[&str]) -> Result<Expression> {
+    Ok(match expr {
+        Some(expr) => {
+            let parsed = parse(expr)?;
+            let keys = parsed.license_ids();
+            parsed.map_ids(&|key| {
+                keys.iter()
+                    .position(|k| k == key)
+                    .and_then(|i| spdx_ids.get(i))
+                    .map_or_else(|| key.to_string(), |id| id.to_string())
+            })
+        }
+        None => parse(&spdx_ids.join(" OR "))?,
+    })
+}
+
+impl Expression {
+    /// Joins `terms` with AND, merging nested ANDs.
+    pub(crate) fn and(terms: Vec<Expression>) -> Self {
+        if terms.len() == 1 {
+            return terms.into_iter().next().unwrap();
+        }
+        Expression::And(
+            terms
+                .into_iter()
+                .flat_map(|term| match term {
+                    Expression::And(inner) => inner,
+                    term => vec![term],
+                })
+                .collect(),
+        )
+    }
+
+    /// Joins `terms` with OR, merging nested ORs.
+    fn or(terms: Vec<Expression>) -> Self {
+        if terms.len() == 1 {
+            return terms.into_iter().next().unwrap();
+        }
+        Expression::Or(
+            terms
+                .into_iter()
+                .flat_map(|term| match term {
+                    Expression::Or(inner) => inner,
+                    term => vec![term],
+                })
+                .collect(),
+        )
+    }
+
+    /// Every license term, left to right.
+    pub fn terms(&self) -> Vec<&LicenseTerm> {
+        match self {
+            Expression::License(term) => vec![term],
+            Expression::And(terms) | Expression::Or(terms) => {
+                terms.iter().flat_map(Expression::terms).collect()
+            }
+        }
+    }
+
+    /// The license ids in the expression, in order and without duplicates.
+    pub fn license_ids(&self) -> Vec<String> {
+        let mut ids: Vec<String> = Vec::new();
+        for term in self.terms() {
+            if !ids.contains(&term.id) {
+                ids.push(term.id.clone());
+            }
+        }
+        ids
+    }
+
+    /// The expression with every license id replaced by `f(id)`.
+    pub fn map_ids(&self, f: &impl Fn(&str) -> String) -> Expression {
+        match self {
+            Expression::License(term) => Expression::License(LicenseTerm {
+                id: f(&term.id),
+                ..term.clone()
+            }),
+            Expression::And(terms) => Expression::And(terms.iter().map(|t| t.map_ids(f)).collect()),
+            Expression::Or(terms) => Expression::Or(terms.iter().map(|t| t.map_ids(f)).collect()),
+        }
+    }
+
+    /// Checks every license and exception id against the SPDX lists.
+    pub fn validate(&self) -> Vec<Issue> {
+        self.terms().into_iter().flat_map(validate_term).collect()
+    }
+
+    /// The canonical form: SPDX casing, and the deprecated GNU ids replaced by
+    /// their `-only`/`-or-later` versions (`GPL-2.0+` becomes `GPL-2.0-or-later`).
+    pub fn normalize(&self) -> Expression {
+        match self {
+            Expression::License(term) => Expression::License(normalize_term(term)),
+            Expression::And(terms) => Expression::and(terms.iter().map(Self::normalize).collect()),
+            Expression::Or(terms) => Expression::or(terms.iter().map(Self::normalize).collect()),
+        }
+    }
+
+    /// Whether both expressions normalize to the same licenses, ignoring the
+    /// order of `AND` and `OR` operands (`MIT OR Apache-2.0` is `Apache-2.0 OR MIT`).
+    pub fn equivalent(&self, other: &Expression) -> bool {
+        self.normalize().sorted() == other.normalize().sorted()
+    }
+
+    /// The expression with the operands of every `AND` and `OR` sorted.
+    fn sorted(&self) -> Expression {
+        let sort = |terms: &[Expression]| {
+            let mut terms: Vec<Expression> = terms.iter().map(Self::sorted).collect();
+            terms.sort_by_cached_key(|term| term.to_string());
+            terms
+        };
+        match self {
+            Expression::License(_) => self.clone(),
+            Expression::And(terms) => Expression::And(sort(terms)),
+            Expression::Or(terms) => Expression::Or(sort(terms)),
+        }
+    }
+
+    /// A plain-language outline of what the expression allows, one term per line.
+    pub fn explain(&self) -> String {
+        let mut out = String::new();
+        self.explain_into(&mut out, 0);
+        out
+    }
+
+    fn explain_into(&self, out: &mut String, depth: usize) {
+        let indent = "  ".repeat(depth);
+        let (heading, terms) = match self {
+            Expression::License(term) => {
+                out.push_str(&format!("{indent}{term}: {}\n", describe(term)));
+                return;
+            }
+            Expression::And(terms) => ("All of", terms),
+            Expression::Or(terms) => ("Any one of", terms),
+        };
+        out.push_str(&format!("{indent}{heading}:\n"));
+        for term in terms {
+            term.explain_into(out, depth + 1);
+        }
+    }
+}
+
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (op, terms) = match self {
+            Expression::License(term) => return write!(f, "{term}"),
+            Expression::And(terms) => ("AND", terms),
+            Expression::Or(terms) => ("OR", terms),
+        };
+        for (i, term) in terms.iter().enumerate() {
+            if i > 0 {
+                write!(f, " {op} ")?;
+            }
+            match (self, term) {
+                (Expression::And(_), Expression::Or(_)) => write!(f, "({term})")?,
+                _ => write!(f, "{term}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for LicenseTerm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.id)?;
+        if self.or_later {
+            write!(f, "+")?;
+        }
+        if let Some(exception) = &self.exception {
+            write!(f, " WITH {exception}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The SPDX license list entry for `id`, ignoring case.
+fn find_license(id: &str) -> Option<&'static (&'static str, &'static str, u8)> {
+    LICENSES
+        .iter()
+        .find(|(name, _, _)| name.eq_ignore_ascii_case(id))
+}
+
+fn find_exception(id: &str) -> Option<&'static (&'static str, u8)> {
+    EXCEPTIONS
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(id))
+}
+
+/// `LicenseRef-...` or `DocumentRef-...:LicenseRef-...`, which need not be listed.
+fn is_license_ref(id: &str) -> bool {
+    let id = match id.split_once(':') {
+        Some((document, license)) if document.starts_with("DocumentRef-") => license,
+        _ => id,
+    };
+    id.strip_prefix("LicenseRef-").is_some_and(is_idstring)
+}
+
+/// The first character, with its byte offset, that cannot appear in an id:
+/// SPDX ids are `[A-Za-z0-9.-]+`, optionally after a `DocumentRef-...:` prefix.
+fn invalid_char(id: &str) -> Option<(usize, char)> {
+    let start = match id.split_once(':') {
+        Some((document, _)) if document.starts_with("DocumentRef-") && is_idstring(document) => {
+            document.len() + 1
+        }
+        _ => 0,
+    };
+    id[start..]
+        .char_indices()
+        .find(|&(_, c)| !(c.is_ascii_alphanumeric() || c == '-' || c == '.'))
+        .map(|(i, c)| (start + i, c))
+}
+
+fn is_idstring(s: &str) -> bool {
+    !s.is_empty()
+        && s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.')
+}
+
+/// The `-only` and `-or-later` ids replacing a deprecated GNU id such as `GPL-2.0`.
+fn gnu_replacements(id: &str) -> Option<(String, String)> {
+    let only = find_license(&format!("{id}-only"))?.0;
+    let or_later = find_license(&format!("{id}-or-later"))?.0;
+    Some((only.to_string(), or_later.to_string()))
+}
+
+fn validate_term(term: &LicenseTerm) -> Vec<Issue> {
+    let mut issues = Vec::new();
+    let error = |message: String| Issue {
+        severity: Severity::Error,
+        message,
+    };
+    let warning = |message: String| Issue {
+        severity: Severity::Warning,
+        message,
+    };
+
+    if !is_license_ref(&term.id) {
+        match find_license(&term.id) {
+            None => {
+                let listed = SpdxLicenses::new(None).licenses_list().unwrap_or_default();
+                let similar: Vec<&str> = fuzzy::suggestions(&term.id, &listed)
+                    .into_iter()
+                    .map(|l| l.spdx_id.as_str())
+                    .collect();
+                let hint = if similar.is_empty() {
+                    String::new()
+                } else {
+                    format!(" Did you mean: {}?", similar.join(", "))
+                };
+                issues.push(error(format!("Unknown license id '{}'.{hint}", term.id)));
+            }
+            Some((name, _, flags)) => {
+                if *name != term.id {
+                    issues.push(warning(format!("'{}' is written '{name}'.", term.id)));
+                }
+                if flags & IS_DEPRECATED != 0 {
+                    let hint = match gnu_replacements(name) {
+                        Some((_, or_later)) if term.or_later => format!(" Use {or_later} instead."),
+                        Some((only, or_later)) => format!(" Use {only} or {or_later} instead."),
+                        None => String::new(),
+                    };
+                    issues.push(warning(format!("'{name}' is deprecated.{hint}")));
+                } else if term.or_later && (name.ends_with("-only") || name.ends_with("-or-later"))
+                {
+                    issues.push(warning(format!(
+                        "'+' has no meaning after '{name}'; use the -or-later id instead."
+                    )));
+                }
+            }
+        }
+    }
+
+    if let Some(exception) = &term.exception
+        && !exception
+            .strip_prefix("AdditionRef-")
+            .is_some_and(is_idstring)
+    {
+        match find_exception(exception) {
+            None => issues.push(error(format!("Unknown exception id '{exception}'."))),
+            Some((name, flags)) => {
+                if name != exception {
+                    issues.push(warning(format!("'{exception}' is written '{name}'.")));
+                }
+                if flags & IS_DEPRECATED != 0 {
+                    issues.push(warning(format!("Exception '{name}' is deprecated.")));
+                }
+            }
+        }
+    }
+    issues
+}
+
+fn normalize_term(term: &LicenseTerm) -> LicenseTerm {
+    let mut normalized = term.clone();
+    if let Some((name, _, flags)) = find_license(&term.id) {
+        normalized.id = name.to_string();
+        if flags & IS_DEPRECATED != 0
+            && let Some((only, or_later)) = gnu_replacements(name)
+        {
+            normalized.id = if term.or_later { or_later } else { only };
+            normalized.or_later = false;
+        }
+    }
+    if let Some(exception) = &term.exception
+        && let Some((name, _)) = find_exception(exception)
+    {
+        normalized.exception = Some(name.to_string());
+    }
+    normalized
+}
+
+/// The current SPDX id for a single license id, in SPDX casing: the deprecated
+/// GNU ids GitHub still reports, such as `GPL-3.0`, become their `-only` ids.
+pub fn current_id(id: &str) -> String {
+    normalize_term(&LicenseTerm {
+        id: id.to_string(),
+        or_later: false,
+        exception: None,
+    })
+    .id
+}
+
+/// The full license name, e.g. `GNU General Public License v2.0 or later`.
+fn describe(term: &LicenseTerm) -> String {
+    let mut description = if is_license_ref(&term.id) {
+        "custom license".to_string()
+    } else {
+        match find_license(&term.id) {
+            Some((_, full_name, _)) => full_name.to_string(),
+            None => "unknown license".to_string(),
+        }
+    };
+    if term.or_later {
+        description.push_str(", or any later version");
+    }
+    if let Some(exception) = &term.exception {
+        description.push_str(&format!(", with {exception}"));
+    }
+    description
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Token<'a> {
+    Open,
+    Close,
+    And,
+    Or,
+    With,
+    Word(&'a str),
+}
+
+impl fmt::Display for Token<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Open => write!(f, "'('"),
+            Token::Close => write!(f, "')'"),
+            Token::And => write!(f, "AND"),
+            Token::Or => write!(f, "OR"),
+            Token::With => write!(f, "WITH"),
+            Token::Word(word) => write!(f, "'{word}'"),
+        }
+    }
+}
+
+/// Splits `expr` into tokens with their byte offsets. Operators are matched
+/// ignoring case; anything else between whitespace and parentheses is a word.
+fn tokenize(expr: &str) -> Vec<(usize, Token<'_>)> {
+    let mut tokens = Vec::new();
+    let mut chars = expr.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        let token = match c {
+            '(' => Token::Open,
+            ')' => Token::Close,
+            c if c.is_whitespace() => continue,
+            _ => {
+                let mut end = start + c.len_utf8();
+                while let Some(&(i, c)) = chars.peek() {
+                    if c.is_whitespace() || c == '(' || c == ')' {
+                        break;
+                    }
+                    end = i + c.len_utf8();
+                    chars.next();
+                }
+                let word = &expr[start..end];
+                match word.to_ascii_uppercase().as_str() {
+                    "AND" => Token::And,
+                    "OR" => Token::Or,
+                    "WITH" => Token::With,
+                    _ => Token::Word(word),
+                }
+            }
+        };
+        tokens.push((start, token));
+    }
+    tokens
+}
+
+/// Recursive descent over `or := and (OR and)*`, `and := primary (AND primary)*`,
+/// `primary := '(' or ')' | id['+'] [WITH exception]`.
+struct Parser<'a> {
+    expr: &'a str,
+    tokens: Vec<(usize, Token<'a>)>,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, offset: usize, message: impl Into<String>) -> Error {
+        Error::InvalidExpression {
+            expr: self.expr.to_string(),
+            offset,
+            message: message.into(),
+        }
+    }
+
+    fn next(&mut self) -> Option<(usize, Token<'a>)> {
+        let token = self.tokens.get(self.pos).copied();
+        self.pos += 1;
+        token
+    }
+
+    fn eat(&mut self, token: Token) -> bool {
+        let found = self.tokens.get(self.pos).is_some_and(|&(_, t)| t == token);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn parse_or(&mut self) -> Result<Expression> {
+        let mut terms = vec![self.parse_and()?];
+        while self.eat(Token::Or) {
+            terms.push(self.parse_and()?);
+        }
+        Ok(Expression::or(terms))
+    }
+
+    fn parse_and(&mut self) -> Result<Expression> {
+        let mut terms = vec![self.parse_primary()?];
+        while self.eat(Token::And) {
+            terms.push(self.parse_primary()?);
+        }
+        Ok(Expression::and(terms))
+    }
+
+    fn parse_primary(&mut self) -> Result<Expression> {
+        let end = self.expr.len();
+        match self.next() {
+            None => Err(self.error(end, "expected a license id")),
+            Some((_, Token::Open)) => {
+                let inner = self.parse_or()?;
+                match self.next() {
+                    Some((_, Token::Close)) => Ok(inner),
+                    Some((offset, token)) => {
+                        Err(self.error(offset, format!("expected ')' before {token}")))
+                    }
+                    None => Err(self.error(end, "missing ')'")),
+                }
+            }
+            Some((offset, Token::Word(word))) => {
+                let (id, or_later) = match word.strip_suffix('+') {
+                    Some(id) => (id, true),
+                    None => (word, false),
+                };
+                if id.is_empty() {
+                    return Err(self.error(offset, format!("invalid license id '{word}'")));
+                }
+                if let Some((i, c)) = invalid_char(id) {
+                    return Err(self.error(
+                        offset + i,
+                        format!("invalid character '{c}' in license id '{word}'"),
+                    ));
+                }
+                let exception = if self.eat(Token::With) {
+                    match self.next() {
+                        Some((offset, Token::Word(exception))) => {
+                            if let Some((i, c)) = invalid_char(exception) {
+                                return Err(self.error(
+                                    offset + i,
+                                    format!(
+                                        "invalid character '{c}' in exception id '{exception}'"
+                                    ),
+                                ));
+                            }
+                            Some(exception.to_string())
+                        }
+                        Some((offset, token)) => {
+                            return Err(self.error(
+                                offset,
+                                format!("expected an exception id after WITH, found {token}"),
+                            ));
+                        }
+                        None => return Err(self.error(end, "expected an exception id after WITH")),
+                    }
+                } else {
+                    None
+                };
+                Ok(Expression::License(LicenseTerm {
+                    id: id.to_string(),
+                    or_later,
+                    exception,
+                }))
+            }
+            Some((offset, token)) => {
+                Err(self.error(offset, format!("expected a license id, found {token}")))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn term(id: &str) -> Expression {
+        Expression::License(LicenseTerm {
+            id: id.to_string(),
+            or_later: false,
+            exception: None,
+        })
+    }
+
+    fn error_at(expr: &str) -> (usize, String) {
+        match parse(expr) {
+            Err(Error::InvalidExpression {
+                offset, message, ..
+            }) => (offset, message),
+            other => panic!("{expr}: expected a syntax error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        assert_eq!(
+            parse("MIT OR Apache-2.0 AND ISC").unwrap(),
+            Expression::Or(vec![
+                term("MIT"),
+                Expression::And(vec![term("Apache-2.0"), term("ISC")]),
+            ])
+        );
+        assert_eq!(
+            parse("(MIT OR Apache-2.0) AND ISC").unwrap(),
+            Expression::And(vec![
+                Expression::Or(vec![term("MIT"), term("Apache-2.0")]),
+                term("ISC"),
+            ])
+        );
+    }
+
+    #[test]
+    fn nested_operators_are_flattened() {
+        assert_eq!(
+            parse("MIT OR (ISC OR Zlib)").unwrap(),
+            Expression::Or(vec![term("MIT"), term("ISC"), term("Zlib")])
+        );
+        assert_eq!(parse("((MIT))").unwrap(), term("MIT"));
+    }
+
+    #[test]
+    fn terms_with_plus_and_exceptions() {
+        let parsed = parse("gpl-2.0+ with classpath-exception-2.0 or mit").unwrap();
+        let terms = parsed.terms();
+        assert_eq!(terms.len(), 2);
+        assert_eq!(terms[0].id, "gpl-2.0");
+        assert!(terms[0].or_later);
+        assert_eq!(
+            terms[0].exception.as_deref(),
+            Some("classpath-exception-2.0")
+        );
+        assert_eq!(parsed.license_ids(), ["gpl-2.0", "mit"]);
+    }
+
+    #[test]
+    fn expression_for_written_licenses() {
+        let ids = ["MIT", "Apache-2.0"];
+        assert_eq!(
+            for_licenses(None, &ids).unwrap().to_string(),
+            "MIT OR Apache-2.0"
+        );
+        assert_eq!(
+            for_licenses(Some("mit and apache"), &ids)
+                .unwrap()
+                .to_string(),
+            "MIT AND Apache-2.0"
+        );
+    }
+
+    #[test]
+    fn ids_must_be_idstrings() {
+        for (expr, offset, c) in [
+            ("\";", 0, '"'),
+            ("MIT,", 3, ','),
+            ("MIT/Apache-2.0", 3, '/'),
+            ("MIT OR Apache_2.0", 13, '_'),
+            ("a:LicenseRef-x", 1, ':'),
+            ("GPL-2.0 WITH Classpath/x", 22, '/'),
+        ] {
+            let (at, message) = error_at(expr);
+            assert_eq!(at, offset, "{expr}");
+            assert!(
+                message.starts_with(&format!("invalid character '{c}'")),
+                "{message}"
+            );
+        }
+        for expr in [
+            "DocumentRef-spdx-tool-1.2:LicenseRef-MIT-Style-2",
+            "LicenseRef-Acme.1 OR GPL-2.0+",
+            "GPL-2.0-or-later WITH AdditionRef-x",
+        ] {
+            assert!(parse(expr).is_ok(), "{expr}");
+        }
+    }
+
+    #[test]
+    fn malformed_expressions_report_where() {
+        assert_eq!(error_at(""), (0, "expected a license id".to_string()));
+        assert_eq!(error_at("MIT OR"), (6, "expected a license id".to_string()));
+        assert_eq!(error_at("(MIT"), (4, "missing ')'".to_string()));
+        assert_eq!(error_at("MIT)"), (3, "unbalanced ')'".to_string()));
+        assert_eq!(
+            error_at("MIT Apache-2.0"),
+            (4, "expected AND or OR before 'Apache-2.0'".to_string())
+        );
+        assert_eq!(
+            error_at("MIT AND OR ISC"),
+            (8, "expected a license id, found OR".to_string())
+        );
+        assert_eq!(
+            error_at("GPL-2.0++"),
+            (
+                7,
+                "invalid character '+' in license id 'GPL-2.0++'".to_string()
+            )
+        );
+        assert_eq!(
+            error_at("GPL-2.0 WITH (x)"),
+            (
+                13,
+                "expected an exception id after WITH, found '('".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn normalize_fixes_case_and_deprecated_gnu_ids() {
+        let normalized = parse("mit or gpl-2.0+ and lgpl-2.1").unwrap().normalize();
+        assert_eq!(
+            normalized.to_string(),
+            "MIT OR GPL-2.0-or-later AND LGPL-2.1-only"
+        );
+        assert_eq!(
+            parse("Apache-2.0 WITH llvm-exception")
+                .unwrap()
+                .normalize()
+                .to_string(),
+            "Apache-2.0 WITH LLVM-exception"
+        );
+    }
+
+    #[test]
+    fn display_round_trips() {
+        for expr in [
+            "MIT",
+            "MIT OR Apache-2.0",
+            "(MIT OR Apache-2.0) AND ISC",
+            "GPL-2.0-or-later WITH Classpath-exception-2.0 OR MIT AND Zlib",
+            "LicenseRef-Acme OR GPL-2.0+",
+        ] {
+            let parsed = parse(expr).unwrap();
+            assert_eq!(parsed.to_string(), expr);
+            assert_eq!(parse(&parsed.to_string()).unwrap(), parsed);
+            let normalized = parsed.normalize();
+            assert_eq!(normalized.normalize(), normalized, "{expr}");
+        }
+    }
+
+    #[test]
+    fn validate_reports_unknown_and_noncanonical_ids() {
+        let issues = parse("mit OR Foo-1.0 OR LicenseRef-Acme")
+            .unwrap()
+            .validate();
+        let errors: Vec<&str> = issues
+            .iter()
+            .filter(|i| i.severity == Severity::Error)
+            .map(|i| i.message.as_str())
+            .collect();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("Unknown license id 'Foo-1.0'."));
+        assert!(
+            issues
+                .iter()
+                .any(|i| i.message == "'mit' is written 'MIT'.")
+        );
+
+        let deprecated = parse("GPL-2.0").unwrap().validate();
+        assert_eq!(
+            deprecated[0].message,
+            "'GPL-2.0' is deprecated. Use GPL-2.0-only or GPL-2.0-or-later instead."
+        );
+    }
+}
@@ -0,0 +1,276 @@
+//! Matching loosely typed license names such as `Apache2`, `apache` or `gpl3`.
+
+use crate::LicenseMeta;
+
+/// Common names that do not follow from the key or SPDX id, after [`normalize`].
+const ALIASES: &[(&str, &str)] = &[
+    ("apache", "Apache-2.0"),
+    ("asl", "Apache-2.0"),
+    ("asl2", "Apache-2.0"),
+    ("agpl", "AGPL-3.0-only"),
+    ("mpl", "MPL-2.0"),
+    ("epl", "EPL-2.0"),
+    ("bsd2", "BSD-2-Clause"),
+    ("simplifiedbsd", "BSD-2-Clause"),
+    ("bsd3", "BSD-3-Clause"),
+    ("newbsd", "BSD-3-Clause"),
+    ("boost", "BSL-1.0"),
+    ("cc0", "CC0-1.0"),
+    ("expat", "MIT"),
+];
+
+/// What user input refers to among the listed licenses.
+#[derive(Debug)]
+pub enum Match<'a> {
+    One(&'a LicenseMeta),
+    Ambiguous(Vec<&'a LicenseMeta>),
+    None,
+}
+
+/// Finds the license `input` refers to, trying in turn: the exact key, SPDX id
+/// or name ignoring case, an alias, the key or SPDX id ignoring punctuation and
+/// a trailing `.0` (`Apache2`, `gpl3`). Input that is the prefix of several
+/// keys or SPDX ids (`gpl`, `bsd`) is ambiguous.
+pub fn find<'a>(input: &str, licenses: &'a [LicenseMeta]) -> Match<'a> {
+    let input = input.trim();
+    if let Some(license) = licenses.iter().find(|l| {
+        l.key.eq_ignore_ascii_case(input)
+            || l.spdx_id.eq_ignore_ascii_case(input)
+            || l.name.eq_ignore_ascii_case(input)
+    }) {
+        return Match::One(license);
+    }
+
+    let wanted = normalize(input);
+    if wanted.is_empty() {
+        return Match::None;
+    }
+    if let Some((_, spdx_id)) = ALIASES.iter().find(|(alias, _)| *alias == wanted)
+        && let Some(license) = licenses
+            .iter()
+            .find(|l| l.spdx_id.eq_ignore_ascii_case(spdx_id))
+    {
+        return Match::One(license);
+    }
+
+    let loose = |id: &str| {
+        let id = normalize(id);
+        id == wanted || id.strip_suffix('0') == Some(wanted.as_str())
+    };
+    let matches: Vec<&LicenseMeta> = licenses
+        .iter()
+        .filter(|l| loose(&l.key) || loose(&l.spdx_id))
+        .collect();
+    if !matches.is_empty() {
+        return one_or_ambiguous(matches);
+    }
+
+    // A lone prefix match is too weak to act on (`mitt` would pick
+    // `MIT-testregon`); it still shows up in the suggestions.
+    let matches: Vec<&LicenseMeta> = licenses
+        .iter()
+        .filter(|l| {
+            normalize(&l.key).starts_with(&wanted) || normalize(&l.spdx_id).starts_with(&wanted)
+        })
+        .collect();
+    if matches.len() < 2 {
+        return Match::None;
+    }
+    Match::Ambiguous(matches)
+}
+
+/// Up to five licenses close to `input`: those containing it first, then the
+/// ones within a small edit distance of their key or SPDX id, with or without
+/// the version (so `apahce` suggests `apache-2.0`).
+pub fn suggestions<'a>(input: &str, licenses: &'a [LicenseMeta]) -> Vec<&'a LicenseMeta> {
+    let lower = input.trim().to_lowercase();
+    let wanted = normalize(input);
+    if wanted.is_empty() {
+        return Vec::new();
+    }
+    let max_distance = (wanted.len() / 3).max(1);
+    let mut scored: Vec<(usize, &LicenseMeta)> = licenses
+        .iter()
+        .filter_map(|l| {
+            if l.key.to_lowercase().contains(&lower)
+                || l.spdx_id.to_lowercase().contains(&lower)
+                || l.name.to_lowercase().contains(&lower)
+            {
+                return Some((0, l));
+            }
+            let distance = [&l.key, &l.spdx_id]
+                .into_iter()
+                .flat_map(|id| {
+                    let id = normalize(id);
+                    let stem = id
+                        .trim_end_matches(|c: char| c.is_ascii_digit())
+                        .to_string();
+                    [id, stem]
+                })
+                .map(|id| strsim::damerau_levenshtein(&wanted, &id))
+                .min()?;
+            (distance <= max_distance).then_some((distance, l))
+        })
+        .collect();
+    scored.sort_by_key(|(distance, _)| *distance);
+    scored.into_iter().take(5).map(|(_, l)| l).collect()
+}
+
+fn one_or_ambiguous(matches: Vec<&LicenseMeta>) -> Match<'_> {
+    match matches.as_slice() {
+        [] => Match::None,
+        [license] => Match::One(license),
+        _ => Match::Ambiguous(matches),
+    }
+}
+
+/// Lower-cased letters and digits only, dropping a `v` before a version number,
+/// so `GPLv3`, `gpl-3` and `GPL 3` all become `gpl3`.
+fn normalize(s: &str) -> String {
+    let chars: Vec<char> = s
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+    chars
+        .iter()
+        .enumerate()
+        .filter(|&(i, &c)| {
+            !(c == 'v'
+                && i > 0
+                && chars[i - 1].is_ascii_alphabetic()
+                && chars.get(i + 1).is_some_and(char::is_ascii_digit))
+        })
+        .map(|(_, &c)| c)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The keys and SPDX ids of the bundled GitHub license templates.
+    fn github() -> Vec<LicenseMeta> {
+        [
+            (
+                "agpl-3.0",
+                "AGPL-3.0-only",
+                "GNU Affero General Public License v3.0",
+            ),
+            ("apache-2.0", "Apache-2.0", "Apache License 2.0"),
+            (
+                "bsd-2-clause",
+                "BSD-2-Clause",
+                "BSD 2-Clause \"Simplified\" License",
+            ),
+            (
+                "bsd-3-clause",
+                "BSD-3-Clause",
+                "BSD 3-Clause \"New\" or \"Revised\" License",
+            ),
+            ("bsl-1.0", "BSL-1.0", "Boost Software License 1.0"),
+            ("gpl-2.0", "GPL-2.0-only", "GNU General Public License v2.0"),
+            ("gpl-3.0", "GPL-3.0-only", "GNU General Public License v3.0"),
+            (
+                "lgpl-2.1",
+                "LGPL-2.1-only",
+                "GNU Lesser General Public License v2.1",
+            ),
+            ("mit", "MIT", "MIT License"),
+            ("mpl-2.0", "MPL-2.0", "Mozilla Public License 2.0"),
+            ("unlicense", "Unlicense", "The Unlicense"),
+        ]
+        .into_iter()
+        .map(|(key, spdx_id, name)| LicenseMeta {
+            key: key.to_string(),
+            spdx_id: spdx_id.to_string(),
+            name: name.to_string(),
+        })
+        .collect()
+    }
+
+    fn key(input: &str) -> Option<String> {
+        match find(input, &github()) {
+            Match::One(license) => Some(license.key.clone()),
+            Match::Ambiguous(candidates) => panic!("{input} is ambiguous: {candidates:?}"),
+            Match::None => None,
+        }
+    }
+
+    fn ambiguous(input: &str) -> Vec<String> {
+        match find(input, &github()) {
+            Match::Ambiguous(candidates) => candidates.iter().map(|l| l.key.clone()).collect(),
+            other => panic!("{input} is not ambiguous: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn exact_keys_ids_and_names() {
+        assert_eq!(key("MIT").as_deref(), Some("mit"));
+        assert_eq!(key(" apache-2.0 ").as_deref(), Some("apache-2.0"));
+        assert_eq!(
+            key("mozilla public license 2.0").as_deref(),
+            Some("mpl-2.0")
+        );
+    }
+
+    #[test]
+    fn loose_spellings() {
+        for (input, expected) in [
+            ("Apache2", "apache-2.0"),
+            ("apache 2", "apache-2.0"),
+            ("GPLv3", "gpl-3.0"),
+            ("gpl3", "gpl-3.0"),
+            ("gpl-2", "gpl-2.0"),
+            ("lgpl21", "lgpl-2.1"),
+            ("BSD3", "bsd-3-clause"),
+        ] {
+            assert_eq!(key(input).as_deref(), Some(expected), "{input}");
+        }
+    }
+
+    #[test]
+    fn aliases() {
+        assert_eq!(key("apache").as_deref(), Some("apache-2.0"));
+        assert_eq!(key("Expat").as_deref(), Some("mit"));
+        assert_eq!(key("boost").as_deref(), Some("bsl-1.0"));
+        assert_eq!(key("New BSD").as_deref(), Some("bsd-3-clause"));
+    }
+
+    #[test]
+    fn family_prefixes_are_ambiguous() {
+        assert_eq!(ambiguous("gpl"), ["gpl-2.0", "gpl-3.0"]);
+        assert_eq!(ambiguous("bsd"), ["bsd-2-clause", "bsd-3-clause"]);
+    }
+
+    #[test]
+    fn single_prefix_matches_are_not_guesses() {
+        assert_eq!(key("mitt"), None);
+        assert_eq!(key("unlic"), None);
+        assert_eq!(key("--"), None);
+    }
+
+    #[test]
+    fn suggestions_for_typos() {
+        let licenses = github();
+        let keys = |input| -> Vec<&str> {
+            suggestions(input, &licenses)
+                .into_iter()
+                .map(|l| l.key.as_str())
+                .collect()
+        };
+        assert_eq!(keys("apahce"), ["apache-2.0"]);
+        assert_eq!(keys("mti"), ["mit"]);
+        assert!(keys("unlic").contains(&"unlicense"));
+        assert!(keys("zzzzzzzz").is_empty());
+    }
+
+    #[test]
+    fn normalize_drops_punctuation_and_version_v() {
+        assert_eq!(normalize("GPLv3"), "gpl3");
+        assert_eq!(normalize("GPL 3"), "gpl3");
+        assert_eq!(normalize("gpl-3"), "gpl3");
+        assert_eq!(normalize("v2"), "v2");
+        assert_eq!(normalize("Unlicense"), "unlicense");
+    }
+}
@@ -0,0 +1,379 @@
+use crate::cache::{self, Cache};
+use crate::provider::LicenseProvider;
+use crate::{Error, LicenseDetail, LicenseMeta, Result, expression};
+use async_trait::async_trait;
+use chrono::{DateTime, Local, Utc};
+use reqwest::header::{HeaderMap, RETRY_AFTER, USER_AGENT};
+use reqwest::{Client, Response, StatusCode};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+pub const DEFAULT_API_URL: &str = "https://api.github.com";
+
+/// Environment variables holding a token for api.github.com, in order of preference.
+pub const TOKEN_VARS: [&str; 2] = ["GITHUB_TOKEN", "GH_TOKEN"];
+
+/// The token to send to `api_url`: the explicit one when given, otherwise
+/// the first non-empty [`TOKEN_VARS`] read with `env`, but only for
+/// api.github.com so that a github.com token never leaks to another host.
+pub fn token_for(
+    api_url: &str,
+    explicit: Option<String>,
+    env: impl Fn(&str) -> Option<String>,
+) -> Option<String> {
+    let explicit = explicit.filter(|t| !t.is_empty());
+    if explicit.is_some() || api_url.trim_end_matches('/') != DEFAULT_API_URL {
+        return explicit;
+    }
+    TOKEN_VARS
+        .iter()
+        .find_map(|name| env(name).filter(|t| !t.is_empty()))
+}
+
+/// Client for a GitHub-compatible `/licenses` API, such as api.github.com,
+/// a GitHub Enterprise instance or an internal mirror serving the same JSON.
+pub struct GitHubApi {
+    client: Client,
+    cache: Cache,
+    base_url: String,
+    token: Option<String>,
+}
+
+impl GitHubApi {
+    pub fn new(client: Client, cache: Cache, base_url: &str, token: Option<String>) -> Self {
+        Self {
+            client,
+            cache,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            token,
+        }
+    }
+
+    /// How to raise the rate limit, for error messages.
+    fn token_hint(&self) -> &'static str {
+        if self.token.is_some() {
+            ""
+        } else if self.base_url == DEFAULT_API_URL {
+            " Set GITHUB_TOKEN or GH_TOKEN, or pass --token, to raise the limit."
+        } else {
+            " Pass --token to raise the limit."
+        }
+    }
+
+    pub fn cache(&self) -> &Cache {
+        &self.cache
+    }
+
+    /// Cache namespace for this endpoint, so responses from different mirrors never mix.
+    fn cache_prefix(&self) -> String {
+        let host = self
+            .base_url
+            .split_once("://")
+            .map_or(self.base_url.as_str(), |(_, rest)| rest);
+        host.chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '.' || c == '-' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect()
+    }
+
+    /// The listed licenses, with deprecated SPDX ids such as `GPL-3.0` replaced
+    /// by their current ones, see [`expression::current_id`].
+    pub async fn licenses_list(&self, force: bool) -> Result<Vec<LicenseMeta>> {
+        let url = format!("{}/licenses", self.base_url);
+        let name = format!("{}/licenses", self.cache_prefix());
+        let licenses: Vec<LicenseMeta> = self.get_json(&url, &name, force).await?;
+        Ok(licenses
+            .into_iter()
+            .map(|license| LicenseMeta {
+                spdx_id: expression::current_id(&license.spdx_id),
+                ..license
+            })
+            .collect())
+    }
+
+    pub async fn license_body(&self, key: &str, force: bool) -> Result<LicenseDetail> {
+        if !is_license_key(key) {
+            return Err(Error::UnknownLicense {
+                key: key.to_string(),
+                suggestions: Vec::new(),
+            });
+        }
+        let url = format!("{}/licenses/{}", self.base_url, key);
+        let name = format!("{}/licenses/{}", self.cache_prefix(), key.to_lowercase());
+        self.get_json(&url, &name, force).await
+    }
+
+    /// Fetches `url` as JSON through the cache entry `name`, see [`cache::fetch_json`].
+    async fn get_json<T>(&self, url: &str, name: &str, force: bool) -> Result<T>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        let mut request = self
+            .client
+            .get(url)
+            .header(USER_AGENT, "git-license-cli-rust");
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+        cache::fetch_json(&self.cache, request, name, force, |response| {
+            check_status(response, self.token_hint())
+        })
+        .await
+    }
+}
+
+#[async_trait]
+impl LicenseProvider for GitHubApi {
+    fn name(&self) -> &str {
+        "github"
+    }
+
+    async fn list(&self) -> Result<Vec<LicenseMeta>> {
+        self.licenses_list(false).await
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<LicenseDetail>> {
+        if !is_license_key(key) {
+            return Ok(None);
+        }
+        match self.license_body(key, false).await {
+            Ok(detail) => Ok(Some(detail)),
+            Err(e) if e.is_not_found() => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// Whether `key` can be a license key such as `apache-2.0` or `gpl-2.0+`. Keys
+/// end up in URLs and cache paths, so anything else (`../x`, `a/b`) is refused.
+fn is_license_key(key: &str) -> bool {
+    !key.is_empty()
+        && !key.starts_with('.')
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '+' | '-'))
+}
+
+/// Turns error statuses into errors, explaining rate limiting in plain words.
+/// `hint` says how to raise the limit.
+fn check_status(response: Response, hint: &str) -> Result<Response> {
+    let status = response.status();
+    if (status == StatusCode::FORBIDDEN || status == StatusCode::TOO_MANY_REQUESTS)
+        && let Some(message) = rate_limit_message(response.headers(), hint)
+    {
+        return Err(Error::RateLimited(message));
+    }
+    if status == StatusCode::UNAUTHORIZED {
+        return Err(Error::Unauthorized);
+    }
+    Ok(response.error_for_status()?)
+}
+
+fn rate_limit_message(headers: &HeaderMap, hint: &str) -> Option<String> {
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+
+    if let Some(secs) = header(RETRY_AFTER.as_str()).and_then(|v| v.parse::<u64>().ok()) {
+        return Some(format!(
+            "GitHub API secondary rate limit hit; retry in {secs} seconds.{hint}"
+        ));
+    }
+
+    if header("x-ratelimit-remaining")? != "0" {
+        return None;
+    }
+    let limit = header("x-ratelimit-limit").unwrap_or("?");
+    let reset = header("x-ratelimit-reset")
+        .and_then(|v| v.parse::<i64>().ok())
+        .and_then(|ts| DateTime::from_timestamp(ts, 0));
+    let when = match reset {
+        Some(reset) => {
+            let minutes = (reset - Utc::now()).num_minutes().max(0);
+            format!(
+                "resets at {} (in {minutes} min)",
+                reset.with_timezone(&Local).format("%H:%M:%S")
+            )
+        }
+        None => "reset time unknown".to_string(),
+    };
+    Some(format!(
+        "GitHub API rate limit of {limit} requests/hour exceeded; {when}.{hint}"
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env(vars: &'static [(&'static str, &'static str)]) -> impl Fn(&str) -> Option<String> {
+        |name| {
+            vars.iter()
+                .find(|(var, _)| *var == name)
+                .map(|(_, value)| value.to_string())
+        }
+    }
+
+    #[test]
+    fn license_keys_cannot_escape_the_cache() {
+        for key in ["mit", "Apache-2.0", "gpl-2.0+", "bsd-3-clause-clear"] {
+            assert!(is_license_key(key), "{key}");
+        }
+        for key in [
+            "",
+            "..",
+            "../../etc/passwd",
+            "a/b",
+            "a\\b",
+            ".hidden",
+            "mit?x=1",
+        ] {
+            assert!(!is_license_key(key), "{key}");
+        }
+    }
+
+    #[test]
+    fn environment_tokens_only_go_to_github_com() {
+        let vars = env(&[("GITHUB_TOKEN", "gh-secret")]);
+        assert_eq!(
+            token_for("https://api.github.com/", None, &vars).as_deref(),
+            Some("gh-secret")
+        );
+        assert_eq!(
+            token_for("https://ghe.example.com/api/v3", None, &vars),
+            None
+        );
+        assert_eq!(
+            token_for("https://api.github.com.evil.test", None, &vars),
+            None
+        );
+    }
+
+    #[test]
+    fn explicit_token_goes_to_any_host() {
+        let vars = env(&[("GITHUB_TOKEN", "gh-secret")]);
+        let token = token_for(
+            "https://ghe.example.com/api/v3",
+            Some("ghe-secret".to_string()),
+            &vars,
+        );
+        assert_eq!(token.as_deref(), Some("ghe-secret"));
+    }
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.parse().unwrap(), value.parse().unwrap()))
+            .collect()
+    }
+
+    fn response(status: u16, pairs: &[(&'static str, &str)]) -> Response {
+        let mut response = http::Response::new("");
+        *response.status_mut() = StatusCode::from_u16(status).unwrap();
+        *response.headers_mut() = headers(pairs);
+        Response::from(response)
+    }
+
+    #[test]
+    fn primary_rate_limits_say_when_they_reset() {
+        let reset = (Utc::now() + chrono::Duration::minutes(30)).timestamp();
+        let message = rate_limit_message(
+            &headers(&[
+                ("x-ratelimit-limit", "60"),
+                ("x-ratelimit-remaining", "0"),
+                ("x-ratelimit-reset", &reset.to_string()),
+            ]),
+            " Pass --token to raise the limit.",
+        )
+        .unwrap();
+        assert!(
+            message.starts_with("GitHub API rate limit of 60 requests/hour exceeded; resets at ")
+        );
+        assert!(
+            message.contains("(in 29 min)") || message.contains("(in 30 min)"),
+            "{message}"
+        );
+        assert!(
+            message.ends_with(". Pass --token to raise the limit."),
+            "{message}"
+        );
+    }
+
+    #[test]
+    fn rate_limits_without_details_are_still_explained() {
+        let message = rate_limit_message(&headers(&[("x-ratelimit-remaining", "0")]), "").unwrap();
+        assert_eq!(
+            message,
+            "GitHub API rate limit of ? requests/hour exceeded; reset time unknown."
+        );
+        let message = rate_limit_message(&headers(&[("retry-after", "45")]), "").unwrap();
+        assert_eq!(
+            message,
+            "GitHub API secondary rate limit hit; retry in 45 seconds."
+        );
+    }
+
+    #[test]
+    fn other_refusals_are_not_rate_limits() {
+        assert_eq!(rate_limit_message(&HeaderMap::new(), ""), None);
+        assert_eq!(
+            rate_limit_message(&headers(&[("x-ratelimit-remaining", "12")]), ""),
+            None
+        );
+        assert_eq!(
+            rate_limit_message(
+                &headers(&[("retry-after", "Wed, 21 Oct 2015 07:28:00 GMT")]),
+                ""
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn statuses_map_to_errors() {
+        let limited = [("x-ratelimit-remaining", "0")];
+        assert!(check_status(response(200, &[]), "").is_ok());
+        assert!(check_status(response(304, &[]), "").is_ok());
+        assert!(matches!(
+            check_status(response(403, &limited), ""),
+            Err(Error::RateLimited(_))
+        ));
+        assert!(matches!(
+            check_status(response(429, &[("retry-after", "5")]), ""),
+            Err(Error::RateLimited(_))
+        ));
+        assert!(matches!(
+            check_status(response(401, &[]), ""),
+            Err(Error::Unauthorized)
+        ));
+        assert!(matches!(
+            check_status(response(403, &[]), ""),
+            Err(Error::Http(e)) if e.status() == Some(StatusCode::FORBIDDEN)
+        ));
+        assert!(
+            check_status(response(404, &[]), "")
+                .unwrap_err()
+                .is_not_found()
+        );
+        assert!(matches!(
+            check_status(response(502, &limited), ""),
+            Err(Error::Http(_))
+        ));
+    }
+
+    #[test]
+    fn empty_tokens_fall_through() {
+        let vars = env(&[("GITHUB_TOKEN", ""), ("GH_TOKEN", "fallback")]);
+        assert_eq!(
+            token_for(DEFAULT_API_URL, Some(String::new()), &vars).as_deref(),
+            Some("fallback")
+        );
+        assert_eq!(
+            token_for(DEFAULT_API_URL, None, env(&[("GH_TOKEN", "")])),
+            None
+        );
+    }
+}
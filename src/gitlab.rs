@@ -0,0 +1,90 @@
+use crate::provider::LicenseProvider;
+use crate::{LicenseDetail, LicenseMeta, Result, bundled, expression};
+use async_trait::async_trait;
+use reqwest::Client;
+use reqwest::header::USER_AGENT;
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+
+pub const DEFAULT_API_URL: &str = "https://gitlab.com/api/v4";
+
+/// Client for GitLab's `/templates/licenses` API.
+pub struct GitLabApi {
+    client: Client,
+    base_url: String,
+}
+
+#[derive(Deserialize)]
+struct GitLabTemplate {
+    key: String,
+    name: String,
+    #[serde(default)]
+    content: String,
+}
+
+impl GitLabApi {
+    pub fn new(client: Client, base_url: &str) -> Self {
+        Self {
+            client,
+            base_url: base_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    async fn get_json<T: DeserializeOwned>(&self, url: &str) -> Result<T> {
+        let response = self
+            .client
+            .get(url)
+            .header(USER_AGENT, "git-license-cli-rust")
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(response.json().await?)
+    }
+}
+
+/// GitLab only reports licensee keys, so map them to current SPDX ids where known.
+fn spdx_id_for(key: &str) -> String {
+    spdx::identifiers::LICENSES
+        .iter()
+        .find(|(id, _, _)| id.eq_ignore_ascii_case(key))
+        .map(|(id, _, _)| expression::current_id(id))
+        .or_else(|| {
+            bundled::licenses_list()
+                .into_iter()
+                .find(|l| l.key == key)
+                .map(|l| l.spdx_id)
+        })
+        .unwrap_or_else(|| key.to_string())
+}
+
+#[async_trait]
+impl LicenseProvider for GitLabApi {
+    fn name(&self) -> &str {
+        "gitlab"
+    }
+
+    async fn list(&self) -> Result<Vec<LicenseMeta>> {
+        let url = format!("{}/templates/licenses?per_page=100", self.base_url);
+        let templates: Vec<GitLabTemplate> = self.get_json(&url).await?;
+        Ok(templates
+            .into_iter()
+            .map(|t| LicenseMeta {
+                spdx_id: spdx_id_for(&t.key),
+                key: t.key,
+                name: t.name,
+            })
+            .collect())
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<LicenseDetail>> {
+        let url = format!("{}/templates/licenses/{}", self.base_url, key);
+        match self.get_json::<GitLabTemplate>(&url).await {
+            Ok(t) => Ok(Some(LicenseDetail {
+                name: t.name,
+                body: t.content,
+            })),
+            Err(e) if e.is_not_found() => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
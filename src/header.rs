@@ -0,0 +1,591 @@
+//! SPDX license headers at the top of source files.
+
+use crate::Result;
+use crate::expression::{self, Expression};
+use ignore::WalkBuilder;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// How a language writes comments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommentStyle {
+    /// A line comment prefix such as `//` or `#`.
+    Line(&'static str),
+    /// Block delimiters for languages without line comments, such as CSS and HTML.
+    Block(&'static str, &'static str),
+}
+
+impl CommentStyle {
+    /// The comment syntax for `path`, by file name or extension.
+    pub fn for_path(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_str()?;
+        match name {
+            "Dockerfile" | "Containerfile" | "Makefile" | "GNUmakefile" | "CMakeLists.txt"
+            | "Gemfile" | "Rakefile" | "Vagrantfile" | "BUILD" | "WORKSPACE" => {
+                return Some(CommentStyle::Line("#"));
+            }
+            _ => {}
+        }
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        Some(match extension.as_str() {
+            "rs" | "c" | "h" | "cc" | "cpp" | "cxx" | "hh" | "hpp" | "hxx" | "cs" | "java"
+            | "kt" | "kts" | "scala" | "go" | "swift" | "js" | "mjs" | "cjs" | "jsx" | "ts"
+            | "mts" | "cts" | "tsx" | "dart" | "php" | "groovy" | "gradle" | "proto" | "zig"
+            | "sol" | "scss" | "less" | "fs" | "fsx" => CommentStyle::Line("//"),
+            "py" | "pyi" | "rb" | "sh" | "bash" | "zsh" | "fish" | "pl" | "pm" | "r" | "yaml"
+            | "yml" | "toml" | "ps1" | "nix" | "tf" | "cmake" | "mk" | "ex" | "exs" | "jl"
+            | "cr" | "nim" | "coffee" | "bzl" | "star" => CommentStyle::Line("#"),
+            "sql" | "lua" | "hs" | "elm" | "adb" | "ads" => CommentStyle::Line("--"),
+            "lisp" | "el" | "clj" | "cljs" | "scm" => CommentStyle::Line(";;"),
+            "tex" | "erl" | "hrl" => CommentStyle::Line("%"),
+            "vim" => CommentStyle::Line("\""),
+            "css" => CommentStyle::Block("/*", "*/"),
+            "html" | "htm" | "xhtml" | "xml" | "svg" | "vue" => CommentStyle::Block("<!--", "-->"),
+            _ => return None,
+        })
+    }
+
+    /// `line` as a comment.
+    pub fn comment(self, line: &str) -> String {
+        match self {
+            CommentStyle::Line(prefix) => format!("{prefix} {line}"),
+            CommentStyle::Block(open, close) => format!("{open} {line} {close}"),
+        }
+    }
+}
+
+/// The `SPDX-FileCopyrightText` and `SPDX-License-Identifier` header lines.
+pub fn header_lines(year: &str, author: &str, expression: &str) -> [String; 2] {
+    [
+        format!("{COPYRIGHT_TAG} {year} {author}"),
+        format!("{LICENSE_TAG} {expression}"),
+    ]
+}
+
+/// Headers are only looked for this far into a file.
+const HEADER_SEARCH_LINES: usize = 30;
+
+/// Whether `text` already has an SPDX license identifier in its header.
+pub fn has_header(text: &str) -> bool {
+    text[..header_len(text)]
+        .lines()
+        .any(|line| tag_value(line, LICENSE_TAG).is_some())
+}
+
+/// Prefixes of line comments, and of lines inside block comments.
+const LINE_COMMENTS: &[&str] = &[
+    "//", "--", ";", "%", "\"", "*", "..", "REM ", "rem ", "dnl ",
+];
+/// Block comment delimiters, including docstrings.
+const BLOCK_COMMENTS: &[(&str, &str)] = &[
+    ("/*", "*/"),
+    ("<!--", "-->"),
+    ("\"\"\"", "\"\"\""),
+    ("'''", "'''"),
+    ("{-", "-}"),
+    ("(*", "*)"),
+];
+
+/// The byte length of the header region: the preamble, then comments and
+/// blank lines up to the first line of code, within [`HEADER_SEARCH_LINES`].
+/// Only tags in this region belong to the header.
+fn header_len(text: &str) -> usize {
+    let mut len = preamble_len(text);
+    let mut closing: Option<&str> = None;
+    for line in text[len..].split_inclusive('\n').take(HEADER_SEARCH_LINES) {
+        let trimmed = line.trim();
+        if let Some(close) = closing {
+            if trimmed.contains(close) {
+                closing = None;
+            }
+        } else if let Some((open, close)) = BLOCK_COMMENTS
+            .iter()
+            .find(|(open, _)| trimmed.starts_with(open))
+        {
+            if !trimmed[open.len()..].contains(close) {
+                closing = Some(close);
+            }
+        } else if !(trimmed.is_empty()
+            || is_hash_comment(trimmed)
+            || LINE_COMMENTS
+                .iter()
+                .any(|prefix| trimmed.starts_with(prefix)))
+        {
+            break;
+        }
+        len += line.len();
+    }
+    len
+}
+
+/// A `#` comment, unlike a Rust attribute or a C preprocessor directive.
+fn is_hash_comment(line: &str) -> bool {
+    line.strip_prefix('#')
+        .is_some_and(|rest| !rest.starts_with(|c: char| c == '[' || c == '!' || c.is_alphabetic()))
+}
+
+/// `text` with `lines` prepended as comments, after any shebang, XML or PHP
+/// declaration, doctype and encoding line. `None` when a header is already present.
+pub fn add_header(text: &str, style: CommentStyle, lines: &[String]) -> Option<String> {
+    if has_header(text) {
+        return None;
+    }
+    let split = preamble_len(text);
+    let (preamble, rest) = text.split_at(split);
+
+    let mut result = preamble.to_string();
+    if !result.is_empty() && !result.ends_with('\n') {
+        result.push('\n');
+    }
+    for line in lines {
+        result.push_str(&style.comment(line));
+        result.push('\n');
+    }
+    if !rest.is_empty() && !rest.starts_with(['\n', '\r']) {
+        result.push('\n');
+    }
+    result.push_str(rest);
+    Some(result)
+}
+
+/// Whether `line` is a `#!` interpreter line, unlike a Rust inner attribute
+/// such as `#![allow(dead_code)]`.
+fn is_shebang(line: &str) -> bool {
+    line.strip_prefix("#!")
+        .is_some_and(|rest| !rest.trim_start().starts_with('['))
+}
+
+/// The byte length of the lines that must stay first in the file.
+fn preamble_len(text: &str) -> usize {
+    let mut len = 0;
+    for (i, line) in text.split_inclusive('\n').enumerate().take(2) {
+        let trimmed = line.trim_start_matches('\u{feff}');
+        let keep = if i == 0 {
+            is_shebang(trimmed)
+                || trimmed.starts_with("<?xml")
+                || trimmed.starts_with("<?php")
+                || trimmed.to_ascii_lowercase().starts_with("<!doctype")
+                || is_encoding_line(trimmed)
+        } else {
+            len > 0 && is_encoding_line(trimmed)
+        };
+        if !keep {
+            break;
+        }
+        len += line.len();
+    }
+    len
+}
+
+/// An Emacs/Vim style encoding declaration such as `# -*- coding: utf-8 -*-`,
+/// which Python and Ruby only honour on the first two lines.
+fn is_encoding_line(line: &str) -> bool {
+    let line = line.trim_start();
+    (line.starts_with('#') || line.starts_with("--") || line.starts_with("//"))
+        && (line.contains("coding:") || line.contains("coding="))
+}
+
+/// Every file under `root` that has a known comment style, skipping hidden
+/// files and anything excluded by `.gitignore` or `.licignore` (same syntax).
+pub fn source_files(root: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let walker = WalkBuilder::new(root)
+        .require_git(false)
+        .add_custom_ignore_filename(".licignore")
+        .build();
+    for entry in walker {
+        let entry = entry?;
+        let path = entry.path();
+        if entry.file_type().is_some_and(|t| t.is_file()) && CommentStyle::for_path(path).is_some()
+        {
+            files.push(path.to_path_buf());
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// A source file that `lic header` works on.
+#[derive(Debug, Clone)]
+pub struct SourceFile {
+    pub path: PathBuf,
+    pub style: CommentStyle,
+    pub text: String,
+}
+
+/// The source files under `paths`, minus ignored and generated ones and, with
+/// a warning, those that have no known comment syntax or are not UTF-8 text.
+pub fn sources(paths: &[PathBuf]) -> Result<Vec<SourceFile>> {
+    let mut files = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            files.extend(source_files(path)?);
+        } else {
+            files.push(path.clone());
+        }
+    }
+
+    let mut sources = Vec::new();
+    for path in files {
+        let Some(style) = CommentStyle::for_path(&path) else {
+            eprintln!(
+                "warning: skipping {}: unknown comment syntax",
+                path.display()
+            );
+            continue;
+        };
+        let Ok(text) = fs::read_to_string(&path) else {
+            eprintln!(
+                "warning: skipping {}: not a UTF-8 text file",
+                path.display()
+            );
+            continue;
+        };
+        if !is_generated(&text) {
+            sources.push(SourceFile { path, style, text });
+        }
+    }
+    Ok(sources)
+}
+
+/// What is wrong with the header of `text` when `expected` is the license it
+/// should declare, `None` when nothing is.
+pub fn problem(text: &str, expected: &Expression) -> Option<String> {
+    let header = read_header(text).unwrap_or_default();
+    match &header.expression {
+        None => Some("missing SPDX-License-Identifier".to_string()),
+        Some(found) if !expression::parse(found).is_ok_and(|found| found.equivalent(expected)) => {
+            Some(format!("{found}, expected {expected}"))
+        }
+        Some(_) if header.copyright.is_empty() => {
+            Some("missing SPDX-FileCopyrightText".to_string())
+        }
+        Some(_) => None,
+    }
+}
+
+/// Whether `text` is marked as generated (`@generated` or `DO NOT EDIT`) near the top.
+pub fn is_generated(text: &str) -> bool {
+    text.lines()
+        .take(HEADER_SEARCH_LINES)
+        .any(|line| line.contains("@generated") || line.contains("DO NOT EDIT"))
+}
+
+/// The SPDX tags found in a file's header.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Header {
+    /// `SPDX-FileCopyrightText` values, e.g. `2024 Jane Doe`.
+    pub copyright: Vec<String>,
+    pub expression: Option<String>,
+}
+
+/// The SPDX tags in the comments at the top of `text`, before any code;
+/// `None` when there are none.
+pub fn read_header(text: &str) -> Option<Header> {
+    let mut header = Header::default();
+    for line in text[..header_len(text)].lines() {
+        if let Some(value) = tag_value(line, COPYRIGHT_TAG) {
+            header.copyright.push(value.to_string());
+        } else if let Some(value) = tag_value(line, LICENSE_TAG) {
+            header.expression = Some(value.to_string());
+        }
+    }
+    (header != Header::default()).then_some(header)
+}
+
+/// `text` with the SPDX header lines, and the blank line after them, removed.
+/// Tags after the header, such as in code, stay. `None` when there is no header.
+pub fn remove_header(text: &str) -> Option<String> {
+    let header_len = header_len(text);
+    let mut removed = false;
+    let mut skip_blank = false;
+    let mut result = String::with_capacity(text.len());
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        let in_header = offset < header_len;
+        offset += line.len();
+        if in_header && is_tag_line(line) {
+            removed = true;
+            skip_blank = true;
+            continue;
+        }
+        if skip_blank && line.trim().is_empty() {
+            skip_blank = false;
+            continue;
+        }
+        skip_blank = false;
+        result.push_str(line);
+    }
+    removed.then_some(result)
+}
+
+/// A change of copyright holder for [`update_header`].
+#[derive(Debug, Clone, Copy)]
+pub struct HolderChange<'a> {
+    /// The holders to replace, compared ignoring case and any `<email>`.
+    pub from: &'a [String],
+    pub to: &'a str,
+}
+
+impl HolderChange<'_> {
+    fn applies_to(&self, holder: &str) -> bool {
+        let name = |holder: &str| {
+            let name = holder.split_once('<').map_or(holder, |(name, _)| name);
+            name.trim().to_lowercase()
+        };
+        self.from.iter().any(|from| name(from) == name(holder))
+    }
+}
+
+/// `text` with the license identifier and/or a copyright holder in its header
+/// replaced, keeping the years. Other holders and anything after the header
+/// stay. `None` when nothing changes.
+pub fn update_header(
+    text: &str,
+    expression: Option<&str>,
+    holder: Option<HolderChange>,
+) -> Option<String> {
+    let header_len = header_len(text);
+    let mut result = String::with_capacity(text.len());
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        let in_header = offset < header_len;
+        offset += line.len();
+        let updated = if !in_header {
+            None
+        } else if let (Some(value), Some(expression)) = (tag_value(line, LICENSE_TAG), expression) {
+            Some(replace_value(line, LICENSE_TAG, value, expression))
+        } else if let (Some(value), Some(holder)) = (tag_value(line, COPYRIGHT_TAG), holder) {
+            let split = value
+                .find(|c: char| !(c.is_ascii_digit() || "-–, ".contains(c)))
+                .unwrap_or(value.len());
+            let years = value[..split].trim_end_matches([',', ' ']);
+            let replacement = if years.is_empty() {
+                holder.to.to_string()
+            } else {
+                format!("{years} {}", holder.to)
+            };
+            holder
+                .applies_to(&value[split..])
+                .then(|| replace_value(line, COPYRIGHT_TAG, value, &replacement))
+        } else {
+            None
+        };
+        result.push_str(updated.as_deref().unwrap_or(line));
+    }
+    (result != text).then_some(result)
+}
+
+pub(crate) const COPYRIGHT_TAG: &str = "SPDX-FileCopyrightText:";
+pub(crate) const LICENSE_TAG: &str = "SPDX-License-Identifier:";
+
+/// The value after `tag` on `line`, without a trailing block comment close.
+/// `None` unless the tag starts a comment, so that tags in code or string
+/// literals do not count.
+pub(crate) fn tag_value<'a>(line: &'a str, tag: &str) -> Option<&'a str> {
+    let (before, value) = line.split_once(tag)?;
+    let value = value.trim();
+    let value = value
+        .strip_suffix("-->")
+        .or_else(|| value.strip_suffix("*/"))
+        .unwrap_or(value)
+        .trim();
+    is_comment_start(before.trim(), value).then_some(value)
+}
+
+/// Whether `before` is nothing but comment syntax such as `//`, `#`, `<!--`,
+/// `REM` or Vim's `"`, and not the start of a string literal that `value` closes.
+fn is_comment_start(before: &str, value: &str) -> bool {
+    if matches!(before, "REM" | "rem" | "@REM" | "@rem" | "dnl") {
+        return true;
+    }
+    !before.chars().any(char::is_alphanumeric)
+        && !['"', '\'', '`']
+            .iter()
+            .any(|&quote| before.contains(quote) && value.contains(quote))
+}
+
+fn replace_value(line: &str, tag: &str, value: &str, replacement: &str) -> String {
+    let (before, after) = line.split_once(tag).unwrap_or((line, ""));
+    format!("{before}{tag}{}", after.replacen(value, replacement, 1))
+}
+
+/// A line holding nothing but a comment with an SPDX header tag.
+fn is_tag_line(line: &str) -> bool {
+    tag_value(line, COPYRIGHT_TAG).is_some() || tag_value(line, LICENSE_TAG).is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn with_header(text: &str, style: CommentStyle) -> String {
+        add_header(text, style, &header_lines("2026", "Jane Doe", "MIT")).unwrap()
+    }
+
+    #[test]
+    fn header_goes_after_shebang() {
+        let text = with_header("#!/usr/bin/env python3\nprint()\n", CommentStyle::Line("#"));
+        assert!(
+            text.starts_with("#!/usr/bin/env python3\n# SPDX-FileCopyrightText: 2026 Jane Doe\n")
+        );
+    }
+
+    #[test]
+    fn header_goes_before_rust_inner_attributes() {
+        for source in ["#![allow(dead_code)]\nfn main() {}\n", "#! [no_std]\n"] {
+            let text = with_header(source, CommentStyle::Line("//"));
+            assert!(text.starts_with("// SPDX-FileCopyrightText"), "{text}");
+            assert!(text.ends_with(source));
+        }
+    }
+
+    #[test]
+    fn rust_script_shebang_is_kept() {
+        let text = with_header(
+            "#!/usr/bin/env -S cargo +nightly -Zscript\n",
+            CommentStyle::Line("//"),
+        );
+        assert!(text.starts_with("#!/usr/bin/env -S cargo"));
+    }
+
+    #[test]
+    fn problems_compare_expressions_not_strings() {
+        let expected = expression::parse("MIT OR Apache-2.0").unwrap();
+        let header = |expr: &str| {
+            format!(
+                "// SPDX-FileCopyrightText: 2026 Jane Doe\n// SPDX-License-Identifier: {expr}\n"
+            )
+        };
+        assert_eq!(problem(&header("Apache-2.0 OR MIT"), &expected), None);
+        assert_eq!(
+            problem(&header("MIT"), &expected).as_deref(),
+            Some("MIT, expected MIT OR Apache-2.0")
+        );
+        assert_eq!(
+            problem("fn main() {}\n", &expected).as_deref(),
+            Some("missing SPDX-License-Identifier")
+        );
+        assert_eq!(
+            problem("// SPDX-License-Identifier: MIT OR Apache-2.0\n", &expected).as_deref(),
+            Some("missing SPDX-FileCopyrightText")
+        );
+    }
+
+    /// A Rust file whose header is followed by code holding tags of its own.
+    const SOURCE: &str = "\
+// SPDX-FileCopyrightText: 2019-2021 Jane Doe <jane@example.com>
+// SPDX-FileCopyrightText: 2024 Acme Corp
+// SPDX-License-Identifier: MIT
+
+const TEMPLATE: &str = \"
+// SPDX-License-Identifier: {license}
+\";
+// SPDX-License-Identifier: Apache-2.0
+";
+
+    #[test]
+    fn read_header_stops_at_code() {
+        assert_eq!(
+            read_header(SOURCE),
+            Some(Header {
+                copyright: vec![
+                    "2019-2021 Jane Doe <jane@example.com>".to_string(),
+                    "2024 Acme Corp".to_string(),
+                ],
+                expression: Some("MIT".to_string()),
+            })
+        );
+
+        let block = "<?php\n/*\n * SPDX-FileCopyrightText: 2026 Jane Doe\n\n   SPDX-License-Identifier: MIT\n */\necho 1;\n";
+        let header = read_header(block).unwrap();
+        assert_eq!(header.copyright, ["2026 Jane Doe"]);
+        assert_eq!(header.expression.as_deref(), Some("MIT"));
+
+        assert_eq!(
+            read_header("fn main() {}\n// SPDX-License-Identifier: MIT\n"),
+            None
+        );
+        let attribute = "#[cfg(test)]\n# SPDX-License-Identifier: MIT\n";
+        assert_eq!(read_header(attribute), None);
+        assert!(!has_header(attribute));
+    }
+
+    #[test]
+    fn update_header_replaces_only_the_given_holder() {
+        let from = ["jane doe".to_string()];
+        let holder = HolderChange {
+            from: &from,
+            to: "John Roe",
+        };
+        let updated = update_header(SOURCE, Some("MIT OR Apache-2.0"), Some(holder)).unwrap();
+        assert_eq!(
+            updated,
+            SOURCE
+                .replacen(
+                    "2019-2021 Jane Doe <jane@example.com>",
+                    "2019-2021 John Roe",
+                    1
+                )
+                .replacen("Identifier: MIT\n", "Identifier: MIT OR Apache-2.0\n", 1)
+        );
+
+        let others = ["Someone Else".to_string()];
+        let holder = HolderChange {
+            from: &others,
+            to: "John Roe",
+        };
+        assert_eq!(update_header(SOURCE, None, Some(holder)), None);
+        assert_eq!(update_header(SOURCE, Some("MIT"), None), None);
+    }
+
+    #[test]
+    fn remove_header_keeps_tags_in_code() {
+        let removed = remove_header(SOURCE).unwrap();
+        assert_eq!(removed, SOURCE.split_once("\n\n").unwrap().1);
+        assert_eq!(remove_header(&removed), None);
+
+        let python = "#!/usr/bin/env python3\n# SPDX-License-Identifier: MIT\n\nprint()\n";
+        assert_eq!(
+            remove_header(python).unwrap(),
+            "#!/usr/bin/env python3\nprint()\n"
+        );
+    }
+
+    #[test]
+    fn generated_files_are_marked_near_the_top() {
+        assert!(is_generated("// @generated by protoc\nfn main() {}\n"));
+        assert!(is_generated(
+            "# Code generated by go generate. DO NOT EDIT.\n"
+        ));
+        let late = format!("{}// DO NOT EDIT\n", "x\n".repeat(HEADER_SEARCH_LINES));
+        assert!(!is_generated(&late));
+    }
+
+    #[test]
+    fn source_files_respect_licignore() {
+        let dir = tempfile::TempDir::new().unwrap();
+        for path in [
+            "src/main.rs",
+            "src/gen/api.rs",
+            "README.md",
+            "build.py",
+            "vendor/x.c",
+        ] {
+            let path = dir.path().join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "x\n").unwrap();
+        }
+        fs::write(dir.path().join(".licignore"), "vendor/\nsrc/gen/\n").unwrap();
+
+        let files: Vec<PathBuf> = source_files(dir.path())
+            .unwrap()
+            .into_iter()
+            .map(|path| path.strip_prefix(dir.path()).unwrap().to_path_buf())
+            .collect();
+        assert_eq!(
+            files,
+            [PathBuf::from("build.py"), PathBuf::from("src/main.rs")]
+        );
+    }
+}
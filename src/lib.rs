@@ -0,0 +1,292 @@
+//! Initialize LICENSE files from bundled, SPDX, GitHub, GitLab or local templates.
+//!
+//! The public API covers the three steps the `lic` binary performs:
+//!
+//! - listing licenses through a [`LicenseProvider`], usually a [`ProviderChain`],
+//! - rendering a template with [`replace_placeholders`], or several at once
+//!   with [`render_licenses`],
+//! - writing the result with [`write_license`], or [`write_licenses`] to
+//!   refuse replacing existing license files.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+pub mod bundled;
+pub mod cache;
+pub mod check;
+pub mod compat;
+pub mod config;
+pub mod copyright;
+pub mod debian;
+pub mod deps;
+pub mod detect;
+mod error;
+pub mod expression;
+pub mod fuzzy;
+pub mod github;
+pub mod gitlab;
+pub mod header;
+pub mod local;
+pub mod manifest;
+pub mod naming;
+pub mod notices;
+pub mod policy;
+pub mod provider;
+pub mod reuse;
+pub mod spdx_list;
+
+pub use error::{Error, Result};
+pub use provider::{LicenseProvider, ProviderChain};
+
+/// A license as listed by a provider.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LicenseMeta {
+    pub key: String,
+    pub name: String,
+    pub spdx_id: String,
+}
+
+/// A license template, with placeholders such as `[year]` still in place.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LicenseDetail {
+    pub name: String,
+    pub body: String,
+}
+
+/// A license template with the year and copyright holder filled in.
+#[derive(Debug, Clone)]
+pub struct RenderedLicense {
+    pub name: String,
+    pub spdx_id: String,
+    pub content: String,
+}
+
+impl RenderedLicense {
+    /// Replaces the rendered template with the verbatim SPDX license text, as
+    /// REUSE requires in `LICENSES/`. `LicenseRef-`s keep their content.
+    pub fn use_spdx_text(&mut self) {
+        if let Some(text) = reuse::license_text(&self.spdx_id) {
+            self.content = text.to_string();
+        }
+    }
+}
+
+/// Resolves and renders every license in `keys` with the same year and
+/// copyright holder.
+pub async fn render_licenses(
+    providers: &ProviderChain,
+    keys: &[String],
+    year: &str,
+    author: &str,
+) -> Result<Vec<RenderedLicense>> {
+    let mut licenses = Vec::new();
+    for key in keys {
+        let detail = providers.resolve(key).await?;
+        licenses.push(RenderedLicense {
+            spdx_id: providers.spdx_id(key).await?,
+            content: replace_placeholders(&detail.body, year, author),
+            name: detail.name,
+        });
+    }
+    Ok(licenses)
+}
+
+/// Returns `git config user.name`, if set.
+pub fn git_user_name() -> Option<String> {
+    let output = Command::new("git")
+        .args(["config", "user.name"])
+        .output()
+        .ok()?;
+
+    if output.status.success() {
+        String::from_utf8(output.stdout)
+            .ok()
+            .map(|s| s.trim().to_string())
+    } else {
+        None
+    }
+}
+
+/// Returns the root of the git repository containing the current directory.
+pub fn git_root() -> Option<PathBuf> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--show-toplevel"])
+        .output()
+        .ok()?;
+
+    if output.status.success() {
+        String::from_utf8(output.stdout)
+            .ok()
+            .map(|s| PathBuf::from(s.trim()))
+    } else {
+        None
+    }
+}
+
+const YEAR_PLACEHOLDERS: &[&str] = &["<YEAR>", "[year]", "[yyyy]", "<year>", "YEAR"];
+
+const AUTHOR_PLACEHOLDERS: &[&str] = &[
+    "[fullname]",
+    "[name of copyright owner]",
+    "<copyright holders>",
+    "<name of author>",
+    "<copyright holder>",
+    "<COPYRIGHT HOLDERS>",
+    "<COPYRIGHT HOLDER>",
+    "[name of copyright holder]",
+    "<owner>",
+];
+
+/// File names recognised as a project's license file, in order of preference.
+pub const LICENSE_FILE_NAMES: &[&str] = &[
+    "LICENSE",
+    "LICENSE.md",
+    "LICENSE.txt",
+    "LICENCE",
+    "LICENCE.md",
+    "LICENCE.txt",
+    "COPYING",
+    "COPYING.md",
+    "COPYING.txt",
+    "COPYING.LESSER",
+    "UNLICENSE",
+];
+
+/// Fills in the year and copyright holder placeholders used by the known templates.
+pub fn replace_placeholders(template: &str, year: &str, author: &str) -> String {
+    let mut result = template.to_string();
+    for placeholder in YEAR_PLACEHOLDERS {
+        result = result.replace(placeholder, year);
+    }
+    for placeholder in AUTHOR_PLACEHOLDERS {
+        result = result.replace(placeholder, author);
+    }
+    result
+}
+
+/// Placeholders that [`replace_placeholders`] would fill in but are still present in `text`.
+pub fn find_placeholders(text: &str) -> Vec<&'static str> {
+    YEAR_PLACEHOLDERS
+        .iter()
+        .chain(AUTHOR_PLACEHOLDERS)
+        .copied()
+        .filter(|p| text.contains(p))
+        .collect()
+}
+
+/// Returns the first existing license file in `dir`, see [`find_license_files`].
+pub fn find_license_file(dir: impl AsRef<Path>) -> Option<PathBuf> {
+    find_license_files(dir).into_iter().next()
+}
+
+/// Every license file in `dir`: the [`LICENSE_FILE_NAMES`] in order of
+/// preference, followed by suffixed ones such as `LICENSE-MIT`.
+pub fn find_license_files(dir: impl AsRef<Path>) -> Vec<PathBuf> {
+    let dir = dir.as_ref();
+    let mut files: Vec<PathBuf> = LICENSE_FILE_NAMES
+        .iter()
+        .map(|name| dir.join(name))
+        .filter(|path| path.is_file())
+        .collect();
+
+    let mut suffixed: Vec<PathBuf> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            (name.starts_with("LICENSE-") || name.starts_with("LICENCE-")) && path.is_file()
+        })
+        .collect();
+    suffixed.sort();
+    files.extend(suffixed);
+    files
+}
+
+/// The file writing `target` replaces: `target` itself, or another license
+/// file next to it.
+pub fn existing_license_file(target: &Path) -> Option<PathBuf> {
+    if target.is_file() {
+        return Some(target.to_path_buf());
+    }
+    let dir = target.parent().filter(|p| !p.as_os_str().is_empty());
+    find_license_file(dir.unwrap_or(Path::new(".")))
+}
+
+/// A license file next to `target` that is not one of the `targets` being written.
+pub fn other_license_file(target: &Path, targets: &[PathBuf]) -> Option<PathBuf> {
+    let dir = target.parent().filter(|p| !p.as_os_str().is_empty());
+    find_license_files(dir.unwrap_or(Path::new(".")))
+        .into_iter()
+        .find(|path| targets.iter().all(|t| t.file_name() != path.file_name()))
+}
+
+/// The project directory a license file belongs to: its own directory, or the
+/// one above for a license in `LICENSES/`.
+pub fn project_dir(license_file: &Path) -> &Path {
+    let mut dir = license_file.parent().unwrap_or(Path::new(""));
+    if dir.ends_with(reuse::LICENSES_DIR) {
+        dir = dir.parent().unwrap_or(dir);
+    }
+    if dir.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        dir
+    }
+}
+
+/// The name of the directory `dir` resolves to, such as the project name for
+/// the current directory; empty when it has none.
+pub fn project_name(dir: &Path) -> String {
+    dir.canonicalize()
+        .ok()
+        .and_then(|dir| Some(dir.file_name()?.to_string_lossy().to_string()))
+        .unwrap_or_default()
+}
+
+/// A unified diff between two versions of a file, empty when they are identical.
+pub fn unified_diff(old: &str, new: &str, old_name: &str, new_name: &str) -> String {
+    if old == new {
+        return String::new();
+    }
+    similar::TextDiff::from_lines(old, new)
+        .unified_diff()
+        .context_radius(3)
+        .header(old_name, new_name)
+        .to_string()
+}
+
+/// Writes rendered license text to `path`.
+pub fn write_license(path: impl AsRef<Path>, content: &str) -> Result<()> {
+    let path = path.as_ref();
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        fs::create_dir_all(dir).map_err(|e| Error::io(dir, e))?;
+    }
+    fs::write(path, content).map_err(|e| Error::io(path, e))
+}
+
+/// Writes every `(path, content)` in `files`, failing with
+/// [`Error::LicenseFilesExist`] before writing anything when one would replace
+/// an existing license file (see [`existing_license_file`]), unless `force` is set.
+pub fn write_licenses(files: &[(PathBuf, &str)], force: bool) -> Result<()> {
+    if !force {
+        let mut existing: Vec<PathBuf> = Vec::new();
+        for (target, _) in files {
+            if let Some(file) = existing_license_file(target)
+                && !existing.contains(&file)
+            {
+                existing.push(file);
+            }
+        }
+        if !existing.is_empty() {
+            return Err(Error::LicenseFilesExist(existing));
+        }
+    }
+    for (target, content) in files {
+        write_license(target, content)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,97 @@
+use crate::provider::LicenseProvider;
+use crate::{Error, LicenseDetail, LicenseMeta, Result};
+use async_trait::async_trait;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A directory of license templates, one file per license named after its key.
+///
+/// Files may start with a choosealicense.com-style front matter block
+/// (`---`, `title: ...`, `spdx-id: ...`, `---`); without one the key is used
+/// as SPDX id and the first line of the body as the name.
+pub struct LocalTemplates {
+    dir: PathBuf,
+}
+
+struct Template {
+    meta: LicenseMeta,
+    body: String,
+}
+
+impl LocalTemplates {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    fn templates(&self) -> Result<Vec<Template>> {
+        let read_dir = fs::read_dir(&self.dir).map_err(|e| Error::io(&self.dir, e))?;
+        let mut templates = Vec::new();
+        for item in read_dir {
+            let path = item.map_err(|e| Error::io(&self.dir, e))?.path();
+            if path.is_file() {
+                templates.push(read_template(&path)?);
+            }
+        }
+        templates.sort_by(|a, b| a.meta.key.cmp(&b.meta.key));
+        Ok(templates)
+    }
+}
+
+fn read_template(path: &Path) -> Result<Template> {
+    let raw = fs::read_to_string(path).map_err(|e| Error::io(path, e))?;
+    let key = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    let (front_matter, body) = match raw.strip_prefix("---\n") {
+        Some(rest) => match rest.split_once("\n---\n") {
+            Some((front_matter, body)) => (front_matter, body.trim_start_matches('\n')),
+            None => ("", raw.as_str()),
+        },
+        None => ("", raw.as_str()),
+    };
+    let field = |name: &str| {
+        front_matter.lines().find_map(|line| {
+            let (k, v) = line.split_once(':')?;
+            (k.trim() == name).then(|| v.trim().trim_matches('"').to_string())
+        })
+    };
+
+    let name = field("title")
+        .or_else(|| {
+            body.lines()
+                .find(|l| !l.trim().is_empty())
+                .map(|l| l.trim().to_string())
+        })
+        .unwrap_or_else(|| key.clone());
+    let spdx_id = field("spdx-id").unwrap_or_else(|| key.clone());
+    Ok(Template {
+        meta: LicenseMeta { key, name, spdx_id },
+        body: body.to_string(),
+    })
+}
+
+#[async_trait]
+impl LicenseProvider for LocalTemplates {
+    fn name(&self) -> &str {
+        "local"
+    }
+
+    async fn list(&self) -> Result<Vec<LicenseMeta>> {
+        Ok(self.templates()?.into_iter().map(|t| t.meta).collect())
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<LicenseDetail>> {
+        Ok(self
+            .templates()?
+            .into_iter()
+            .find(|t| {
+                t.meta.key.eq_ignore_ascii_case(key) || t.meta.spdx_id.eq_ignore_ascii_case(key)
+            })
+            .map(|t| LicenseDetail {
+                name: t.meta.name,
+                body: t.body,
+            }))
+    }
+}
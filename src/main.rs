@@ -1,30 +1,53 @@
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, bail};
 use chrono::{Datelike, Local};
-use clap::Parser;
-use cliclack::{input, intro, outro, select};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use cliclack::{confirm, input, intro, log, multiselect, note, outro};
+use lic::cache::Cache;
+use lic::check::{self, CheckOptions};
+use lic::compat::{self, Compatibility};
+use lic::config::Config;
+use lic::deps::{self, Dependency, LicenseStatus as DepStatus};
+use lic::expression::{Expression, LicenseTerm, Severity};
+use lic::github::{self, GitHubApi};
+use lic::gitlab::{self, GitLabApi};
+use lic::header::{self, HolderChange, SourceFile};
+use lic::local::LocalTemplates;
+use lic::manifest::{self, LicenseField};
+use lic::naming::{self, FileNaming};
+use lic::policy::{self, POLICY_FILE, Policy, Project};
+use lic::spdx_list::SpdxLicenses;
+use lic::{
+    LicenseProvider, ProviderChain, bundled, copyright, debian, detect, expression, notices, reuse,
+};
+use lic::{
+    RenderedLicense, find_license_file, find_license_files, git_root, git_user_name, unified_diff,
+    write_license,
+};
 use reqwest::Client;
-use serde::Deserialize;
 use std::fs;
-use std::process::Command;
-
-#[derive(Debug, Deserialize, Clone)]
-struct LicenseMeta {
-    key: String,
-    name: String,
-    spdx_id: String,
-}
-
-#[derive(Debug, Deserialize)]
-struct LicenseDetail {
-    name: String,
-    body: String,
-}
+use std::io::{self, IsTerminal};
+use std::path::{Path, PathBuf};
 
 #[derive(Parser, Debug)]
 #[command(name = "lic")]
 #[command(version = "0.1.0")]
-#[command(about = "Initialize a LICENSE file using GitHub licenses API (Default: CLI Mode)")]
+#[command(
+    about = "Initialize and maintain LICENSE files (runs `init` when no subcommand is given)"
+)]
+#[command(args_conflicts_with_subcommands = true)]
 struct Cli {
+    #[command(flatten)]
+    init: InitArgs,
+
+    #[command(flatten)]
+    sources: SourceArgs,
+
+    #[command(subcommand)]
+    command: Option<Commands>,
+}
+
+#[derive(Args, Debug)]
+struct InitArgs {
     /// Copyright holder name (defaults to git config user.name)
     #[arg(short, long)]
     author: Option<String>,
@@ -40,43 +63,677 @@ struct Cli {
     /// Run in interactive mode (Select license via UI)
     #[arg(short = 'i', long, default_value_t = false)]
     interactive: bool,
+
+    /// Overwrite an existing license file
+    #[arg(short, long, default_value_t = false)]
+    force: bool,
+
+    /// Print what would be written (as a diff against any existing license file) without writing
+    #[arg(long, default_value_t = false)]
+    dry_run: bool,
+
+    /// Write to this file, or into this directory, instead of the repository root
+    #[arg(short, long, conflicts_with = "stdout")]
+    output: Option<PathBuf>,
+
+    /// Print the license to stdout instead of writing a file
+    #[arg(long, default_value_t = false)]
+    stdout: bool,
+
+    /// File name convention for the license file
+    #[arg(long, value_enum, default_value_t = Naming::License)]
+    naming: Naming,
+
+    /// Leave the license field of package manifests alone
+    #[arg(long, default_value_t = false)]
+    no_manifests: bool,
+
+    /// Set the license in every manifest below the project, not only the
+    /// project's own and the Cargo workspace members inheriting it
+    #[arg(long, default_value_t = false, conflicts_with = "no_manifests")]
+    all_manifests: bool,
+
+    /// Do not create a NOTICE file when the license includes Apache-2.0
+    #[arg(long, default_value_t = false)]
+    no_notice: bool,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum Naming {
+    /// LICENSE
+    License,
+    /// LICENSE.md
+    Md,
+    /// LICENSE.txt
+    Txt,
+    /// COPYING for GPL/AGPL, COPYING.LESSER for LGPL, LICENSE otherwise
+    Gnu,
+    /// LICENSE-MIT, LICENSE-APACHE, ... for dual licensing
+    Suffixed,
+    /// LICENSES/<SPDX-ID>.txt with the verbatim SPDX text, as the REUSE specification requires
+    Reuse,
+}
+
+impl From<Naming> for FileNaming {
+    fn from(naming: Naming) -> Self {
+        match naming {
+            Naming::License => FileNaming::License,
+            Naming::Md => FileNaming::Markdown,
+            Naming::Txt => FileNaming::Text,
+            Naming::Gnu => FileNaming::Gnu,
+            Naming::Suffixed => FileNaming::Suffixed,
+            Naming::Reuse => FileNaming::Reuse,
+        }
+    }
+}
+
+#[derive(Args, Debug)]
+struct SourceArgs {
+    /// License sources to use, in lookup order [default: local (if configured),github,spdx]
+    #[arg(long, value_enum, value_delimiter = ',', global = true)]
+    source: Vec<Source>,
+
+    /// Local checkout of spdx/license-list-data to read SPDX licenses from
+    #[arg(long, env = "LIC_SPDX_DATA", global = true)]
+    spdx_data: Option<PathBuf>,
+
+    /// Directory of custom license templates used by the local source
+    #[arg(long, env = "LIC_TEMPLATES_DIR", global = true)]
+    templates_dir: Option<PathBuf>,
+
+    /// Base URL of the GitLab API used by the gitlab source
+    #[arg(long, env = "LIC_GITLAB_URL", global = true)]
+    gitlab_url: Option<String>,
+
+    /// Refresh templates from the GitHub licenses API instead of the bundled copies
+    #[arg(long, default_value_t = false, global = true)]
+    online: bool,
+
+    /// Seconds before cached GitHub API responses are revalidated [default: 86400]
+    #[arg(long, env = "LIC_CACHE_TTL", global = true)]
+    cache_ttl: Option<u64>,
+
+    /// Base URL of a GitHub-compatible licenses API (GitHub Enterprise or a mirror); implies --online
+    #[arg(long, env = "LIC_API_URL", global = true)]
+    api_url: Option<String>,
+
+    /// Token for authenticated API requests (defaults to $GITHUB_TOKEN or $GH_TOKEN for api.github.com only)
+    #[arg(long, global = true)]
+    token: Option<String>,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum Source {
+    /// GitHub's license templates (bundled, or from the API with --online)
+    Github,
+    /// The full SPDX License List
+    Spdx,
+    /// A directory of custom templates (--templates-dir)
+    Local,
+    /// GitLab's license templates API
+    Gitlab,
+}
+
+#[derive(Subcommand, Debug)]
+enum Commands {
+    /// Create a LICENSE file (the default when no subcommand is given)
+    Init(InitArgs),
+    /// List available licenses as a table of key, SPDX id and name
+    List {
+        /// Only show licenses whose key, SPDX id or name contains this
+        query: Option<String>,
+    },
+    /// Print a license template
+    Show {
+        /// License key or SPDX id
+        key: String,
+
+        /// Print the key, name and SPDX id instead of the template body
+        #[arg(long, default_value_t = false)]
+        meta: bool,
+    },
+    /// Check that the project has a license file with every placeholder filled in
+    Check(CheckArgs),
+    /// Identify the license in a license file and the copyright notices in it
+    Detect {
+        /// License file, or a directory to scan for license files (defaults to the git repository root)
+        path: Option<PathBuf>,
+    },
+    /// Print SPDX header comment lines, or add them to source files
+    Header(HeaderCommand),
+    /// Extend the copyright years in an existing license file to the current year
+    Update {
+        /// Copyright holder whose notices are updated (defaults to git config user.name)
+        #[arg(short, long)]
+        author: Option<String>,
+
+        /// Year to extend the notices to (defaults to current year)
+        #[arg(short, long)]
+        year: Option<u32>,
+
+        /// Print the diff without writing
+        #[arg(long, default_value_t = false)]
+        dry_run: bool,
+
+        /// Project directory (defaults to the git repository root)
+        dir: Option<PathBuf>,
+    },
+    /// Parse, check and rewrite SPDX license expressions such as `MIT OR Apache-2.0`
+    Expr {
+        #[command(subcommand)]
+        action: ExprAction,
+    },
+    /// REUSE compliance: license texts in LICENSES/, annotations and linting
+    Reuse {
+        #[command(subcommand)]
+        action: ReuseAction,
+    },
+    /// Write a machine-readable debian/copyright (DEP-5) file
+    Debian(DebianArgs),
+    /// List dependencies and their licenses from Cargo.lock and package-lock.json
+    Deps(DepsArgs),
+    /// Bundle the license and NOTICE files of every dependency into THIRD_PARTY_LICENSES
+    Notices(NoticesArgs),
+    /// Write and check the Apache-2.0 NOTICE file
+    Notice {
+        #[command(subcommand)]
+        action: NoticeAction,
+    },
+    /// Check license compatibility and manage the lic-policy.toml dependency policy
+    Policy {
+        #[command(subcommand)]
+        action: PolicyAction,
+    },
+    /// Manage the on-disk cache of GitHub license API responses
+    Cache {
+        #[command(subcommand)]
+        action: CacheAction,
+    },
+}
+
+#[derive(Args, Debug)]
+struct CheckArgs {
+    /// SPDX expression the license files must match, e.g. "MIT OR Apache-2.0"
+    #[arg(long)]
+    expect: Option<String>,
+
+    /// Year the copyright notices must reach (defaults to current year)
+    #[arg(short, long)]
+    year: Option<u32>,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = ReportFormat::Text)]
+    format: ReportFormat,
+
+    /// Project directory (defaults to the git repository root)
+    dir: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+struct DebianArgs {
+    /// License of files without licensing information (defaults to the detected license)
+    #[arg(short, long)]
+    license: Option<String>,
+
+    /// Upstream-Name (defaults to the project directory name)
+    #[arg(long)]
+    upstream_name: Option<String>,
+
+    /// Upstream-Contact, e.g. "Jane Doe <jane@example.com>"
+    #[arg(long)]
+    contact: Option<String>,
+
+    /// Source: URL (defaults to the origin remote)
+    #[arg(long)]
+    source_url: Option<String>,
+
+    /// Where to write the file (defaults to debian/copyright in the project)
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    /// Print the file instead of writing it
+    #[arg(long, conflicts_with = "dry_run")]
+    stdout: bool,
+
+    /// Overwrite an existing file
+    #[arg(short, long)]
+    force: bool,
+
+    /// Print the diff without writing
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Project directory (defaults to the git repository root)
+    dir: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+struct DepsArgs {
+    /// Output format
+    #[arg(long, value_enum, default_value_t = DepsFormat::Table)]
+    format: DepsFormat,
+
+    /// Fail when a license is denied, not allowed, or incompatible with the project license
+    #[arg(long)]
+    check: bool,
+
+    /// Policy file (defaults to lic-policy.toml in the project)
+    #[arg(long, requires = "check")]
+    policy: Option<PathBuf>,
+
+    /// Project license (defaults to `project` in the policy, else the detected license)
+    #[arg(long, requires = "check")]
+    project: Option<String>,
+
+    /// Project directory (defaults to the git repository root)
+    dir: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+struct NoticesArgs {
+    /// Output format
+    #[arg(long, value_enum, default_value_t = NoticesFormat::Text)]
+    format: NoticesFormat,
+
+    /// Template to render instead of the built-in one, in a Handlebars subset: no helpers or partials, and missing values are empty (see the README)
+    #[arg(long)]
+    template: Option<PathBuf>,
+
+    /// Where to write the bundle (defaults to THIRD_PARTY_LICENSES in the project, with .md or .html)
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    /// Print the bundle instead of writing it
+    #[arg(long)]
+    stdout: bool,
+
+    /// Overwrite an existing file
+    #[arg(short, long)]
+    force: bool,
+
+    /// Project directory (defaults to the git repository root)
+    dir: Option<PathBuf>,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum NoticesFormat {
+    Text,
+    Markdown,
+    Html,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum DepsFormat {
+    Table,
+    Json,
+    Csv,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum ReportFormat {
+    Text,
+    Json,
+    /// SARIF 2.1.0, for code scanning
+    Sarif,
+}
+
+#[derive(Args, Debug)]
+struct HeaderArgs {
+    /// Copyright holder name (defaults to git config user.name)
+    #[arg(short, long)]
+    author: Option<String>,
+
+    /// Copyright year (defaults to current year)
+    #[arg(short, long)]
+    year: Option<String>,
+
+    /// License key or SPDX expression (defaults to the license detected in the project, else mit)
+    #[arg(short, long)]
+    license: Option<String>,
+}
+
+#[derive(Args, Debug)]
+#[command(args_conflicts_with_subcommands = true)]
+struct HeaderCommand {
+    #[command(flatten)]
+    args: HeaderArgs,
+
+    /// Line comment prefix to put in front of each header line
+    #[arg(long, default_value = "//")]
+    comment: String,
+
+    #[command(subcommand)]
+    action: Option<HeaderAction>,
+}
+
+#[derive(Subcommand, Debug)]
+enum HeaderAction {
+    /// Prepend SPDX headers to every source file that lacks one
+    Add {
+        #[command(flatten)]
+        args: HeaderArgs,
+
+        /// List the files that would change without writing
+        #[arg(long, default_value_t = false)]
+        dry_run: bool,
+
+        /// Files or directories to process (defaults to the git repository root)
+        paths: Vec<PathBuf>,
+    },
+    /// Report source files whose SPDX header is missing or names another license
+    Check {
+        /// License key or SPDX expression the headers must name (defaults to the license detected in the project, else mit)
+        #[arg(short, long)]
+        license: Option<String>,
+
+        /// Files or directories to check (defaults to the git repository root)
+        paths: Vec<PathBuf>,
+    },
+    /// Rewrite the license identifier or copyright holder in existing headers
+    Update {
+        /// New copyright holder; the years are kept
+        #[arg(short, long)]
+        author: Option<String>,
+
+        /// Copyright holder that --author replaces, repeatable (defaults to the holder in the project's license file, else the git user name)
+        #[arg(long, requires = "author")]
+        from: Vec<String>,
+
+        /// New license key or SPDX expression
+        #[arg(short, long)]
+        license: Option<String>,
+
+        /// List the files that would change without writing
+        #[arg(long, default_value_t = false)]
+        dry_run: bool,
+
+        /// Files or directories to process (defaults to the git repository root)
+        paths: Vec<PathBuf>,
+    },
+    /// Remove SPDX headers from source files
+    Remove {
+        /// List the files that would change without writing
+        #[arg(long, default_value_t = false)]
+        dry_run: bool,
+
+        /// Files or directories to process (defaults to the git repository root)
+        paths: Vec<PathBuf>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum ExprAction {
+    /// Check every license and exception id, failing on unknown ones
+    Validate {
+        /// License expression (quoting is optional)
+        #[arg(required = true)]
+        expr: Vec<String>,
+    },
+    /// Describe in plain words what the expression allows
+    Explain {
+        /// License expression (quoting is optional)
+        #[arg(required = true)]
+        expr: Vec<String>,
+    },
+    /// Print the canonical form, e.g. `gpl-2.0+ or mit` as `GPL-2.0-or-later OR MIT`
+    Normalize {
+        /// License expression (quoting is optional)
+        #[arg(required = true)]
+        expr: Vec<String>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum NoticeAction {
+    /// Write NOTICE with the project copyright and the NOTICE files of Apache-2.0 dependencies
+    Update {
+        /// Copyright holder (defaults to git config user.name)
+        #[arg(short, long)]
+        author: Option<String>,
+
+        /// Year the project copyright notice should reach (defaults to current year)
+        #[arg(short, long)]
+        year: Option<u32>,
+
+        /// Print the diff without writing
+        #[arg(long, default_value_t = false)]
+        dry_run: bool,
+
+        /// Project directory (defaults to the git repository root)
+        dir: Option<PathBuf>,
+    },
+    /// Fail unless NOTICE exists and is up to date, when the project or a dependency uses Apache-2.0
+    Check {
+        /// Year the copyright notice must reach (defaults to current year)
+        #[arg(short, long)]
+        year: Option<u32>,
+
+        /// Project directory (defaults to the git repository root)
+        dir: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum PolicyAction {
+    /// Write a starter lic-policy.toml
+    Init {
+        /// Overwrite an existing policy file
+        #[arg(short, long)]
+        force: bool,
+
+        /// Project directory (defaults to the git repository root)
+        dir: Option<PathBuf>,
+    },
+    /// Show the category of each license and whether a project can include it
+    Explain {
+        /// License expression of the dependency (quoting is optional)
+        #[arg(required = true)]
+        expr: Vec<String>,
+
+        /// Project license (defaults to the detected license)
+        #[arg(long)]
+        project: Option<String>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum ReuseAction {
+    /// Write license texts to LICENSES/<SPDX-ID>.txt
+    Download {
+        /// License keys or SPDX expressions (defaults to every license the project uses that LICENSES/ lacks)
+        licenses: Vec<String>,
+
+        /// Overwrite existing files in LICENSES/
+        #[arg(long, default_value_t = false)]
+        force: bool,
+    },
+    /// Give every file copyright and licensing information: a header where the
+    /// file has comments, a .license sidecar for binaries, a REUSE.toml annotation otherwise
+    Annotate {
+        #[command(flatten)]
+        args: HeaderArgs,
+
+        /// Annotate in the legacy .reuse/dep5 file instead of REUSE.toml
+        #[arg(long, default_value_t = false)]
+        dep5: bool,
+
+        /// Use .license sidecar files instead of REUSE.toml for text files without comment syntax
+        #[arg(long, default_value_t = false, conflicts_with = "dep5")]
+        sidecar: bool,
+
+        /// List the files that would change without writing
+        #[arg(long, default_value_t = false)]
+        dry_run: bool,
+
+        /// Files or directories to annotate (defaults to the git repository root)
+        paths: Vec<PathBuf>,
+    },
+    /// Report files without copyright or licensing information, and missing or unused licenses
+    Lint {
+        /// Print the report as JSON
+        #[arg(long, default_value_t = false)]
+        json: bool,
+
+        /// Project directory (defaults to the git repository root)
+        dir: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum CacheAction {
+    /// List cached entries with their age and ETag
+    List,
+    /// Re-download the license list and every license body
+    Refresh,
+    /// Remove all cached entries
+    Clear,
+    /// Print the cache directory
+    Path,
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
-    let client = Client::new();
+    let config = Config::load()?;
+    let sources = &cli.sources;
 
-    // 根据是否传入 -i 决定执行模式
-    if cli.interactive {
-        handle_interactive(&cli, &client).await?;
-    } else {
-        handle_cli(&cli, &client).await?;
+    // 不带子命令时保持原有的 init 行为
+    match cli.command.unwrap_or(Commands::Init(cli.init)) {
+        Commands::Cache { action } => handle_cache(&action, &github_api(sources, &config)).await,
+        Commands::Expr { action } => handle_expr(&action),
+        Commands::Detect { path } => handle_detect(&path.unwrap_or_else(project_root)),
+        Commands::Check(args) => handle_check(&args),
+        Commands::Deps(args) => handle_deps(&args),
+        Commands::Notices(args) => handle_notices(&args),
+        Commands::Notice { action } => handle_notice(&action),
+        Commands::Policy { action } => handle_policy(&action),
+        Commands::Update {
+            author,
+            year,
+            dry_run,
+            dir,
+        } => handle_update(author, year, dry_run, &dir.unwrap_or_else(project_root)),
+        command => {
+            let providers = build_providers(sources, &config)?;
+            match command {
+                // 根据是否传入 -i 决定执行模式
+                Commands::Init(args) if args.interactive => {
+                    handle_interactive(&args, &providers).await
+                }
+                Commands::Init(args) => handle_cli(&args, &providers).await,
+                Commands::List { query } => handle_list(query.as_deref(), &providers).await,
+                Commands::Show { key, meta } => handle_show(&key, meta, &providers).await,
+                Commands::Header(header) => match header.action {
+                    Some(HeaderAction::Add {
+                        args,
+                        dry_run,
+                        paths,
+                    }) => handle_header_add(&args, dry_run, paths, &providers).await,
+                    Some(HeaderAction::Check { license, paths }) => {
+                        handle_header_check(license.as_deref(), paths, &providers).await
+                    }
+                    Some(HeaderAction::Update {
+                        author,
+                        from,
+                        license,
+                        dry_run,
+                        paths,
+                    }) => {
+                        handle_header_update(author, from, license, dry_run, paths, &providers)
+                            .await
+                    }
+                    Some(HeaderAction::Remove { dry_run, paths }) => {
+                        handle_header_remove(dry_run, paths)
+                    }
+                    None => handle_header(&header.args, &header.comment, &providers).await,
+                },
+                Commands::Reuse { action } => handle_reuse(action, &providers).await,
+                Commands::Debian(args) => handle_debian(&args, &providers).await,
+                _ => unreachable!("handled above"),
+            }
+        }
     }
+}
 
-    Ok(())
+fn github_api(cli: &SourceArgs, config: &Config) -> GitHubApi {
+    let cache_ttl = cli.cache_ttl.or(config.cache_ttl).unwrap_or(86400);
+    let api_url = cli
+        .api_url
+        .as_deref()
+        .or(config.api_url.as_deref())
+        .unwrap_or(github::DEFAULT_API_URL);
+    let token = github::token_for(api_url, cli.token.clone(), |name| std::env::var(name).ok());
+    GitHubApi::new(Client::new(), Cache::new(cache_ttl), api_url, token)
 }
 
-async fn handle_interactive(cli: &Cli, client: &Client) -> Result<()> {
+/// Builds the ordered provider chain from `--source`, falling back to the
+/// local templates (when configured), GitHub and SPDX.
+fn build_providers(cli: &SourceArgs, config: &Config) -> Result<ProviderChain> {
+    let templates_dir = cli.templates_dir.clone().or(config.templates_dir.clone());
+    let sources = if !cli.source.is_empty() {
+        cli.source.clone()
+    } else if templates_dir.is_some() {
+        vec![Source::Local, Source::Github, Source::Spdx]
+    } else {
+        vec![Source::Github, Source::Spdx]
+    };
+
+    let mut providers: Vec<Box<dyn LicenseProvider>> = Vec::new();
+    for source in sources {
+        match source {
+            Source::Github => {
+                // A configured endpoint is only useful online, so it implies --online.
+                if cli.online || cli.api_url.is_some() || config.api_url.is_some() {
+                    providers.push(Box::new(github_api(cli, config)));
+                }
+                providers.push(Box::new(bundled::BundledProvider));
+            }
+            Source::Spdx => {
+                let data_dir = cli.spdx_data.clone().or(config.spdx_data.clone());
+                providers.push(Box::new(SpdxLicenses::new(data_dir)));
+            }
+            Source::Local => {
+                let dir = templates_dir
+                    .clone()
+                    .ok_or(lic::Error::MissingTemplatesDir)?;
+                providers.push(Box::new(LocalTemplates::new(dir)));
+            }
+            Source::Gitlab => {
+                let url = cli
+                    .gitlab_url
+                    .as_deref()
+                    .or(config.gitlab_url.as_deref())
+                    .unwrap_or(gitlab::DEFAULT_API_URL);
+                providers.push(Box::new(GitLabApi::new(Client::new(), url)));
+            }
+        }
+    }
+    Ok(ProviderChain::new(providers))
+}
+
+async fn handle_interactive(cli: &InitArgs, providers: &ProviderChain) -> Result<()> {
     intro(" 📜 Initialize License")?;
 
-    let license_key = if let Some(key) = &cli.license {
-        key.clone()
+    let license_keys = if let Some(expr) = &cli.license {
+        license_keys(expr)?
     } else {
-        let licenses_meta = fetch_licenses_list(client).await?;
+        let licenses_meta = providers.list().await?;
 
         let items: Vec<(String, String, String)> = licenses_meta
             .iter()
             .map(|l| (l.key.clone(), l.name.clone(), l.spdx_id.clone()))
             .collect();
 
-        select("Pick a license template").items(&items).interact()?
+        multiselect("Pick one or more license templates (space to toggle)")
+            .items(&items)
+            .required(true)
+            .interact()?
     };
 
     let author = if let Some(a) = &cli.author {
         a.clone()
     } else {
-        let default_author = get_git_user_name().unwrap_or_else(|| "Your Name".to_string());
+        let default_author = git_user_name().unwrap_or_else(|| "Your Name".to_string());
         input("Copyright holder name")
             .default_input(&default_author)
             .placeholder("Who owns the copyright?")
@@ -86,105 +743,1305 @@ async fn handle_interactive(cli: &Cli, client: &Client) -> Result<()> {
     let year = if let Some(y) = &cli.year {
         y.clone()
     } else {
-        let current_year = Local::now().year().to_string();
+        let default_year = current_year().to_string();
         input("Copyright year")
-            .default_input(&current_year)
+            .default_input(&default_year)
             .placeholder("Defaults to current year")
             .interact()?
     };
 
-    let license_detail = fetch_license_body(client, &license_key).await?;
-    let final_content = replace_placeholders(&license_detail.body, &year, &author);
-    fs::write("LICENSE", final_content)?;
+    let licenses = render_licenses(cli, providers, &license_keys, &year, &author).await?;
+    let names = licenses
+        .iter()
+        .map(|l| l.name.as_str())
+        .collect::<Vec<_>>()
+        .join(" / ");
+
+    if cli.stdout {
+        outro(format!("{names} for {author}:"))?;
+        print_licenses(&licenses);
+        return Ok(());
+    }
+
+    let targets = target_paths(cli, &licenses)?;
+    let mut written = Vec::new();
+    for (license, target) in licenses.iter().zip(&targets) {
+        if target.is_file() {
+            let old = fs::read_to_string(target)
+                .with_context(|| format!("Failed to read {}", target.display()))?;
+            let path = display(target);
+            let diff = unified_diff(&old, &license.content, &path, &path);
+            if diff.is_empty() {
+                log::info(format!("{path} is already up to date."))?;
+                continue;
+            }
+            note(format!("Changes to {path}"), diff)?;
+            if cli.dry_run {
+                continue;
+            }
+            if !cli.force
+                && !confirm(format!("Overwrite {path}?"))
+                    .initial_value(false)
+                    .interact()?
+            {
+                log::remark(format!("Left {path} unchanged."))?;
+                continue;
+            }
+        } else if cli.dry_run {
+            note(display(target), &license.content)?;
+            continue;
+        } else if let Some(other) = lic::other_license_file(target, &targets)
+            && !cli.force
+            && !confirm(format!(
+                "{} already exists. Write {} as well?",
+                other.display(),
+                target.display()
+            ))
+            .initial_value(false)
+            .interact()?
+        {
+            log::remark(format!("Did not write {}.", target.display()))?;
+            continue;
+        }
+        write_license(target, &license.content)?;
+        written.push(display(target));
+    }
+
+    let expression = expression::for_licenses(cli.license.as_deref(), &spdx_ids(&licenses))?;
+    if !cli.no_notice
+        && let Some(path) = init_notice(&expression, &targets, &author, &year, cli.dry_run)?
+        && !cli.dry_run
+    {
+        written.push(display(&path));
+    }
+
+    if !cli.no_manifests
+        && let Some(target) = targets.first()
+    {
+        let license = LicenseField::new(&expression, target);
+        for update in manifest::updates(lic::project_dir(target), &license, cli.all_manifests)? {
+            if cli.dry_run {
+                let path = display(&update.path);
+                note(
+                    format!("Changes to {path}"),
+                    unified_diff(&update.old, &update.new, &path, &path),
+                )?;
+            } else {
+                update.write()?;
+                log::info(format!("Set the license in {}.", update.path.display()))?;
+            }
+        }
+    }
 
-    outro(format!(
-        "✅ {} license created for {}!",
-        license_detail.name, author
-    ))?;
+    if cli.dry_run {
+        outro("Dry run, nothing written.")?;
+    } else if written.is_empty() {
+        outro("Nothing written.")?;
+    } else {
+        outro(format!(
+            "✅ {names} created for {author} in {}!",
+            written.join(", ")
+        ))?;
+    }
 
     Ok(())
 }
 
-async fn handle_cli(cli: &Cli, client: &Client) -> Result<()> {
-    let license_key = cli.license.as_deref().unwrap_or("mit");
+async fn handle_cli(cli: &InitArgs, providers: &ProviderChain) -> Result<()> {
+    let license_keys = license_keys(cli.license.as_deref().unwrap_or("mit"))?;
+    let author = resolve_author(cli.author.as_deref())?;
+    let year = resolve_year(cli.year.as_deref());
 
-    let author = if let Some(a) = &cli.author {
-        a.clone()
-    } else {
-        get_git_user_name()
-            .context("Author name not found. Please provide via --author or configure git.")?
+    let licenses = render_licenses(cli, providers, &license_keys, &year, &author).await?;
+    if cli.stdout {
+        print_licenses(&licenses);
+        return Ok(());
+    }
+
+    let targets = target_paths(cli, &licenses)?;
+    if cli.force {
+        for other in other_license_files(&targets) {
+            eprintln!(
+                "warning: {} is not replaced and stays in place; remove it if it no longer applies",
+                other.display()
+            );
+        }
+    }
+    let files: Vec<(PathBuf, &str)> = targets
+        .into_iter()
+        .zip(licenses.iter().map(|l| l.content.as_str()))
+        .collect();
+    if write_checked(&files, cli.force, cli.dry_run)? {
+        for (license, (target, _)) in licenses.iter().zip(&files) {
+            println!(
+                "Created {} license for {} ({}) in {}.",
+                license.name,
+                author,
+                year,
+                target.display()
+            );
+        }
+    }
+    let targets: Vec<PathBuf> = files.into_iter().map(|(target, _)| target).collect();
+    let expression = expression::for_licenses(cli.license.as_deref(), &spdx_ids(&licenses))?;
+    if !cli.no_notice
+        && let Some(path) = init_notice(&expression, &targets, &author, &year, cli.dry_run)?
+        && !cli.dry_run
+    {
+        println!("Created {} for {author}.", path.display());
+    }
+    let Some(target) = targets.first().filter(|_| !cli.no_manifests) else {
+        return Ok(());
     };
 
-    let year = if let Some(y) = &cli.year {
-        y.clone()
+    let license = LicenseField::new(&expression, target);
+    for update in manifest::updates(lic::project_dir(target), &license, cli.all_manifests)? {
+        if cli.dry_run {
+            print!(
+                "{}",
+                unified_diff(
+                    &update.old,
+                    &update.new,
+                    &display(&update.path),
+                    &display(&update.path)
+                )
+            );
+        } else {
+            update.write()?;
+            println!("Set the license in {}.", update.path.display());
+        }
+    }
+    Ok(())
+}
+
+/// License files next to the `targets` that writing them leaves alone, such
+/// as a GPL `LICENSE` when `LICENSE-MIT` and `LICENSE-APACHE` are written.
+fn other_license_files(targets: &[PathBuf]) -> Vec<PathBuf> {
+    let mut others: Vec<PathBuf> = Vec::new();
+    for target in targets {
+        if let Some(other) = lic::other_license_file(target, targets)
+            && !others.contains(&other)
+        {
+            others.push(other);
+        }
+    }
+    others
+}
+
+/// The license keys in a `--license` value such as `mit` or `MIT OR Apache-2.0`.
+fn license_keys(expr: &str) -> Result<Vec<String>> {
+    Ok(expression::parse(expr)?.license_ids())
+}
+
+fn spdx_ids(licenses: &[RenderedLicense]) -> Vec<&str> {
+    licenses.iter().map(|l| l.spdx_id.as_str()).collect()
+}
+
+/// Prints the licenses one after another, separated by a blank line.
+fn print_licenses(licenses: &[RenderedLicense]) {
+    for (i, license) in licenses.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        print!("{}", license.content);
+    }
+}
+
+/// The git repository root, or the current directory outside a repository.
+fn project_root() -> PathBuf {
+    git_root().unwrap_or_else(|| PathBuf::from("."))
+}
+
+/// The licenses `init` writes: rendered templates, or the verbatim SPDX
+/// texts in `LICENSES/` with `--naming reuse`.
+async fn render_licenses(
+    args: &InitArgs,
+    providers: &ProviderChain,
+    keys: &[String],
+    year: &str,
+    author: &str,
+) -> Result<Vec<RenderedLicense>> {
+    let mut licenses = lic::render_licenses(providers, keys, year, author).await?;
+    if matches!(args.naming, Naming::Reuse) {
+        licenses.iter_mut().for_each(RenderedLicense::use_spdx_text);
+    }
+    Ok(licenses)
+}
+
+/// Where `init` writes each license, see [`naming::target_paths`].
+fn target_paths(args: &InitArgs, licenses: &[RenderedLicense]) -> Result<Vec<PathBuf>> {
+    Ok(naming::target_paths(
+        args.naming.into(),
+        args.output.as_deref(),
+        &project_root(),
+        &spdx_ids(licenses),
+    )?)
+}
+
+fn display(path: &Path) -> String {
+    path.display().to_string()
+}
+
+/// Writes license files, refusing to replace existing ones unless `force` is set.
+///
+/// With `dry_run` the new text (or a diff against the license file it would
+/// replace) is printed instead. Returns whether the files were written.
+fn write_checked(files: &[(PathBuf, &str)], force: bool, dry_run: bool) -> Result<bool> {
+    if !dry_run {
+        lic::write_licenses(files, force)?;
+        return Ok(true);
+    }
+    for (target, content) in files {
+        let Some(existing) = lic::existing_license_file(target) else {
+            println!("Would write {}:\n", target.display());
+            print!("{content}");
+            continue;
+        };
+        let old = fs::read_to_string(&existing)
+            .with_context(|| format!("Failed to read {}", existing.display()))?;
+        let diff = unified_diff(&old, content, &display(&existing), &display(target));
+        if diff.is_empty() {
+            println!("{} is already up to date.", existing.display());
+        } else {
+            print!("{diff}");
+        }
+    }
+    Ok(false)
+}
+
+fn resolve_author(author: Option<&str>) -> Result<String> {
+    match author {
+        Some(a) => Ok(a.to_string()),
+        None => git_user_name()
+            .context("Author name not found. Please provide via --author or configure git."),
+    }
+}
+
+fn resolve_year(year: Option<&str>) -> String {
+    year.map_or_else(|| current_year().to_string(), str::to_string)
+}
+
+fn current_year() -> u32 {
+    Local::now().year().unsigned_abs()
+}
+
+async fn handle_list(query: Option<&str>, providers: &ProviderChain) -> Result<()> {
+    let licenses = match query {
+        Some(query) => providers.search(query).await?,
+        None => providers.list().await?,
+    };
+    let key_width = licenses
+        .iter()
+        .map(|l| l.key.len())
+        .max()
+        .unwrap_or(0)
+        .max(3);
+    let spdx_width = licenses
+        .iter()
+        .map(|l| l.spdx_id.len())
+        .max()
+        .unwrap_or(0)
+        .max(7);
+
+    println!("{:<key_width$}  {:<spdx_width$}  NAME", "KEY", "SPDX ID");
+    for license in &licenses {
+        println!(
+            "{:<key_width$}  {:<spdx_width$}  {}",
+            license.key, license.spdx_id, license.name
+        );
+    }
+    Ok(())
+}
+
+async fn handle_show(key: &str, meta: bool, providers: &ProviderChain) -> Result<()> {
+    if meta {
+        let license = providers
+            .lookup(key)
+            .await?
+            .with_context(|| format!("Unknown license '{key}'."))?;
+        println!("key:     {}", license.key);
+        println!("name:    {}", license.name);
+        println!("spdx_id: {}", license.spdx_id);
     } else {
-        Local::now().year().to_string()
+        print!("{}", providers.resolve(key).await?.body);
+    }
+    Ok(())
+}
+
+/// Prints the check report and exits with the code of the most severe problem
+/// (3 no license file, 4 unrecognized, 5 placeholders, 6 stale year, 7 mismatch, 8 manifest mismatch).
+fn handle_check(args: &CheckArgs) -> Result<()> {
+    let dir = args.dir.clone().unwrap_or_else(project_root);
+    let expected = match &args.expect {
+        Some(expr) => Some(expression::parse(expr)?.normalize()),
+        None => None,
+    };
+    let options = CheckOptions {
+        expected,
+        year: args.year.unwrap_or_else(current_year),
+    };
+    let report = check::check(&dir, &options)?;
+
+    match args.format {
+        ReportFormat::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+        ReportFormat::Sarif => {
+            println!("{}", serde_json::to_string_pretty(&report.to_sarif(&dir))?)
+        }
+        ReportFormat::Text => {
+            for file in &report.files {
+                match &file.detection {
+                    Some(d) if d.is_confident() => println!(
+                        "{}: {} ({:.1}% match)",
+                        file.path.display(),
+                        d.spdx_id,
+                        d.confidence * 100.0
+                    ),
+                    _ => println!("{}: not recognized", file.path.display()),
+                }
+            }
+            for finding in &report.findings {
+                eprintln!("error[{}]: {}", finding.problem.id(), finding.message);
+            }
+            if report.findings.is_empty() {
+                println!("All license checks passed.");
+            }
+        }
+    }
+
+    match report.exit_code() {
+        0 => Ok(()),
+        code => std::process::exit(code),
+    }
+}
+
+/// Lists the dependencies and their licenses, warning about missing and
+/// non-SPDX ones.
+fn handle_deps(args: &DepsArgs) -> Result<()> {
+    let dir = args.dir.clone().unwrap_or_else(project_root);
+    let Some(deps) = deps::dependencies(&dir)? else {
+        bail!(
+            "No lockfile found in {} (looked for {}).",
+            dir.display(),
+            deps::LOCKFILES.join(", ")
+        );
+    };
+    if args.check {
+        return check_deps(args, &dir, &deps);
+    }
+
+    match args.format {
+        DepsFormat::Json => println!("{}", serde_json::to_string_pretty(&deps)?),
+        DepsFormat::Csv => {
+            println!("ecosystem,name,version,license,status");
+            for dep in &deps {
+                let fields = [
+                    dep.ecosystem.name(),
+                    &dep.name,
+                    &dep.version,
+                    dep.license.as_deref().unwrap_or_default(),
+                    dep.status.id(),
+                ];
+                let fields: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+                println!("{}", fields.join(","));
+            }
+        }
+        DepsFormat::Table => {
+            let license = |dep: &Dependency| match (&dep.license, &dep.license_file) {
+                (Some(license), _) => license.clone(),
+                (None, Some(file)) => format!("see {}", file.display()),
+                (None, None) => "-".to_string(),
+            };
+            let width = |f: &dyn Fn(&Dependency) -> usize, min: usize| {
+                deps.iter().map(f).max().unwrap_or(0).max(min)
+            };
+            let name_width = width(&|d| d.name.len(), 4);
+            let version_width = width(&|d| d.version.len(), 7);
+            let license_width = width(&|d| license(d).len(), 7);
+            println!(
+                "{:<9}{:<name_width$}  {:<version_width$}  {:<license_width$}  STATUS",
+                "SOURCE", "NAME", "VERSION", "LICENSE"
+            );
+            for dep in &deps {
+                let status = match dep.status {
+                    DepStatus::Spdx => "",
+                    DepStatus::NonSpdx => "not SPDX",
+                    DepStatus::Missing => "missing",
+                };
+                let line = format!(
+                    "{:<9}{:<name_width$}  {:<version_width$}  {:<license_width$}  {status}",
+                    dep.ecosystem.name(),
+                    dep.name,
+                    dep.version,
+                    license(dep)
+                );
+                println!("{}", line.trim_end());
+            }
+        }
+    }
+
+    let count = |status| deps.iter().filter(|d| d.status == status).count();
+    let (missing, non_spdx) = (count(DepStatus::Missing), count(DepStatus::NonSpdx));
+    if missing > 0 {
+        eprintln!(
+            "warning: no license declared: {missing} of {} dependencies",
+            deps.len()
+        );
+    }
+    if non_spdx > 0 {
+        eprintln!(
+            "warning: license is not an SPDX expression: {non_spdx} of {} dependencies",
+            deps.len()
+        );
+    }
+    Ok(())
+}
+
+/// Writes the license and NOTICE texts of every dependency as one bundle.
+fn handle_notices(args: &NoticesArgs) -> Result<()> {
+    let dir = args.dir.clone().unwrap_or_else(project_root);
+    let Some(deps) = deps::dependencies(&dir)? else {
+        bail!(
+            "No lockfile found in {} (looked for {}).",
+            dir.display(),
+            deps::LOCKFILES.join(", ")
+        );
     };
+    let bundle = notices::bundle(&deps);
+    if !bundle.missing.is_empty() {
+        let names: Vec<String> = bundle
+            .missing
+            .iter()
+            .map(|p| format!("{} {}", p.name, p.version))
+            .collect();
+        eprintln!(
+            "warning: no license text found for {} of {} dependencies: {}",
+            names.len(),
+            deps.len(),
+            names.join(", ")
+        );
+    }
 
-    let license_detail = fetch_license_body(client, license_key).await?;
-    let final_content = replace_placeholders(&license_detail.body, &year, &author);
-    fs::write("LICENSE", final_content)?;
+    let html = args.format == NoticesFormat::Html;
+    let text = match (&args.template, args.format) {
+        (Some(path), _) => {
+            let template = fs::read_to_string(path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            bundle
+                .render_template(&template, html)
+                .map_err(|message| lic::Error::Parse {
+                    path: path.clone(),
+                    message,
+                })?
+        }
+        (None, NoticesFormat::Html) => bundle
+            .render_template(notices::HTML_TEMPLATE, true)
+            .map_err(anyhow::Error::msg)?,
+        (None, NoticesFormat::Markdown) => bundle.render_markdown(),
+        (None, NoticesFormat::Text) => bundle.render_text(),
+    };
+    if args.stdout {
+        print!("{text}");
+        return Ok(());
+    }
 
+    let path = args.output.clone().unwrap_or_else(|| {
+        dir.join(match args.format {
+            NoticesFormat::Text => "THIRD_PARTY_LICENSES",
+            NoticesFormat::Markdown => "THIRD_PARTY_LICENSES.md",
+            NoticesFormat::Html => "THIRD_PARTY_LICENSES.html",
+        })
+    });
+    if path.exists() && !args.force {
+        bail!(
+            "{} already exists. Use --force to overwrite it.",
+            path.display()
+        );
+    }
+    write_license(&path, &text)?;
     println!(
-        "Created {} license for {} ({}).",
-        license_detail.name, author, year
+        "Wrote {} ({} texts for {} dependencies).",
+        path.display(),
+        bundle.licenses.len(),
+        deps.len()
     );
     Ok(())
 }
 
-fn get_git_user_name() -> Option<String> {
-    let output = Command::new("git")
-        .args(["config", "user.name"])
-        .output()
-        .ok()?;
+fn handle_notice(action: &NoticeAction) -> Result<()> {
+    match action {
+        NoticeAction::Update {
+            author,
+            year,
+            dry_run,
+            dir,
+        } => {
+            let dir = dir.clone().unwrap_or_else(project_root);
+            let author = resolve_author(author.as_deref())?;
+            let year = year.unwrap_or_else(current_year);
+            let deps = deps::dependencies(&dir)?.unwrap_or_default();
+            let update = notices::update_notice(&dir, &deps, &author, year);
+            let path = display(&update.path);
+            if *dry_run {
+                print!("{}", unified_diff(&update.old, &update.new, &path, &path));
+            } else if update.old == update.new {
+                println!("{path} is already up to date.");
+            } else {
+                write_license(&update.path, &update.new)?;
+                println!(
+                    "Wrote {path} with the notices of {} dependencies.",
+                    update.dependencies
+                );
+            }
+        }
+        NoticeAction::Check { year, dir } => {
+            let dir = dir.clone().unwrap_or_else(project_root);
+            let deps = deps::dependencies(&dir)?.unwrap_or_default();
+            let project_apache =
+                detect::project_license(&dir)?.is_some_and(|e| notices::requires_apache(&e));
+            let year = year.unwrap_or_else(current_year);
+            let Some(problems) = notices::check_project_notice(&dir, &deps, project_apache, year)
+            else {
+                println!(
+                    "No NOTICE needed: the project does not require Apache-2.0 and no Apache-2.0 dependency ships a NOTICE."
+                );
+                return Ok(());
+            };
+            let path = display(&dir.join(notices::NOTICE_FILE));
+            if problems.is_empty() {
+                println!("{path} is up to date.");
+                return Ok(());
+            }
+            for problem in &problems {
+                eprintln!("error: {problem}");
+            }
+            eprintln!("Run `lic notice update` to fix {path}.");
+            std::process::exit(1);
+        }
+    }
+    Ok(())
+}
 
-    if output.status.success() {
-        String::from_utf8(output.stdout)
-            .ok()
-            .map(|s| s.trim().to_string())
+/// Creates the NOTICE file Apache-2.0 expects next to the license files in
+/// `targets`, when `expression` includes Apache-2.0 and there is none yet.
+/// Returns its path when it is (or would be) written.
+fn init_notice(
+    expression: &Expression,
+    targets: &[PathBuf],
+    author: &str,
+    year: &str,
+    dry_run: bool,
+) -> Result<Option<PathBuf>> {
+    let Some(target) = targets.first() else {
+        return Ok(None);
+    };
+    if !notices::includes_apache(&expression.normalize()) {
+        return Ok(None);
+    }
+    let dir = lic::project_dir(target);
+    let path = dir.join(notices::NOTICE_FILE);
+    if path.exists() {
+        return Ok(None);
+    }
+    let deps = deps::dependencies(dir)?.unwrap_or_default();
+    let text = notices::new_notice(dir, &deps, author, year);
+    if dry_run {
+        println!("Would write {}:\n", path.display());
+        print!("{text}");
     } else {
-        None
+        write_license(&path, &text)?;
     }
+    Ok(Some(path))
 }
 
-async fn fetch_licenses_list(client: &Client) -> Result<Vec<LicenseMeta>> {
-    let url = "https://api.github.com/licenses";
-    let response = client
-        .get(url)
-        .header("User-Agent", "git-license-cli-rust")
-        .send()
-        .await?
-        .error_for_status()?;
+/// Checks the dependencies against the policy and the project license,
+/// exiting with 1 on violations.
+fn check_deps(args: &DepsArgs, dir: &Path, deps: &[Dependency]) -> Result<()> {
+    let policy = Policy::find(dir, args.policy.as_deref())?;
+    let license = match args.project.clone().or(policy.project.clone()) {
+        Some(license) => Some(license),
+        None => detect::project_expression(dir).map(|e| e.to_string()),
+    };
+    let project = Project::resolve(license.as_deref())?;
+    if let Project::Unchecked(reason) = &project {
+        eprintln!("warning: {reason}; only the policy lists are checked");
+    }
+    let report = policy::check(deps, &policy, project.license());
 
-    let licenses: Vec<LicenseMeta> = response.json().await?;
-    Ok(licenses)
+    if args.format == DepsFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        for finding in &report.violations {
+            eprintln!("error: {finding}");
+        }
+        for finding in &report.warnings {
+            eprintln!("warning: {finding}");
+        }
+        let against = match project.license() {
+            Some(project) => format!(" with {project}"),
+            None => String::new(),
+        };
+        println!(
+            "{} dependencies checked{against}: {} violations, {} warnings.",
+            deps.len(),
+            report.violations.len(),
+            report.warnings.len()
+        );
+    }
+    if !report.violations.is_empty() {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+fn handle_policy(action: &PolicyAction) -> Result<()> {
+    match action {
+        PolicyAction::Init { force, dir } => {
+            let path = dir.clone().unwrap_or_else(project_root).join(POLICY_FILE);
+            if path.exists() && !force {
+                bail!(
+                    "{} already exists. Use --force to overwrite it.",
+                    display(&path)
+                );
+            }
+            write_license(&path, policy::TEMPLATE)?;
+            println!("Wrote {}.", display(&path));
+        }
+        PolicyAction::Explain { expr, project } => {
+            let dependency = expression::parse(&expr.join(" "))?.normalize();
+            let project = match project {
+                Some(project) => expression::parse(project)?.normalize(),
+                None => detect::project_expression(&project_root())
+                    .context("No project license found. Pass --project.")?,
+            };
+            let mut terms: Vec<&LicenseTerm> = Vec::new();
+            for term in dependency.terms().into_iter().chain(project.terms()) {
+                if !terms.contains(&term) {
+                    terms.push(term);
+                }
+            }
+            for term in terms {
+                match compat::category(term) {
+                    Some(category) => println!("{term}: {category}"),
+                    None => println!("{term}: not in the compatibility model"),
+                }
+            }
+            match compat::compatibility(&dependency, &project) {
+                Compatibility::Compatible => {
+                    println!("{project} projects can include {dependency}.")
+                }
+                Compatibility::Incompatible(reason) => {
+                    println!("{project} projects cannot include {dependency}: {reason}.");
+                    std::process::exit(1);
+                }
+                Compatibility::Unknown(reason) => {
+                    println!(
+                        "Unknown whether {project} projects can include {dependency}: {reason}."
+                    )
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// `field`, quoted when it holds a comma, quote or newline.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
 }
 
-async fn fetch_license_body(client: &Client, key: &str) -> Result<LicenseDetail> {
-    let url = format!("https://api.github.com/licenses/{}", key);
-    let response = client
-        .get(&url)
-        .header("User-Agent", "git-license-cli-rust")
-        .send()
-        .await?
-        .error_for_status()?;
+fn handle_detect(path: &Path) -> Result<()> {
+    let files = if path.is_dir() {
+        find_license_files(path)
+    } else {
+        vec![path.to_path_buf()]
+    };
+    if files.is_empty() {
+        bail!("No license file found in {}.", path.display());
+    }
 
-    let detail: LicenseDetail = response.json().await?;
-    Ok(detail)
+    for file in &files {
+        let text = fs::read_to_string(file)
+            .with_context(|| format!("Failed to read {}", file.display()))?;
+        let Some(detection) = detect::detect(&text) else {
+            println!("{}: empty", file.display());
+            continue;
+        };
+        let confidence = detection.confidence * 100.0;
+        if detection.is_confident() {
+            println!(
+                "{}: {} ({}), {confidence:.1}% match",
+                file.display(),
+                detection.spdx_id,
+                detection.name
+            );
+            if !detection.alternatives.is_empty() {
+                println!("  same text as {}", detection.alternatives.join(", "));
+            }
+        } else {
+            println!(
+                "{}: not recognized (closest is {} at {confidence:.1}%)",
+                file.display(),
+                detection.spdx_id
+            );
+        }
+        for notice in &detection.copyrights {
+            println!("  Copyright {} {}", notice.years, notice.holder);
+        }
+    }
+    Ok(())
 }
 
-fn replace_placeholders(template: &str, year: &str, author: &str) -> String {
-    let mut result = template.to_string();
+async fn handle_header(args: &HeaderArgs, comment: &str, providers: &ProviderChain) -> Result<()> {
+    let expression = header_expression(args.license.as_deref(), providers).await?;
+    let author = resolve_author(args.author.as_deref())?;
+    let year = resolve_year(args.year.as_deref());
+
+    for line in header::header_lines(&year, &author, &expression) {
+        println!("{comment} {line}");
+    }
+    Ok(())
+}
 
-    result = result.replace("[year]", year);
-    result = result.replace("[yyyy]", year);
-    result = result.replace("<year>", year);
-    result = result.replace("YEAR", year);
+async fn handle_header_add(
+    args: &HeaderArgs,
+    dry_run: bool,
+    paths: Vec<PathBuf>,
+    providers: &ProviderChain,
+) -> Result<()> {
+    let expression = header_expression(args.license.as_deref(), providers).await?;
+    let author = resolve_author(args.author.as_deref())?;
+    let year = resolve_year(args.year.as_deref());
+    let lines = header::header_lines(&year, &author, &expression);
 
-    result = result.replace("[fullname]", author);
-    result = result.replace("[name of copyright owner]", author);
-    result = result.replace("<copyright holders>", author);
-    result = result.replace("<name of author>", author);
+    let (mut added, mut present) = (0, 0);
+    for source in header_sources(paths)? {
+        let Some(updated) = header::add_header(&source.text, source.style, &lines) else {
+            present += 1;
+            continue;
+        };
+        write_source(&source.path, &updated, dry_run, "add a header to")?;
+        added += 1;
+    }
 
-    result
+    if dry_run {
+        println!("{added} files would get a header, {present} already have one.");
+    } else {
+        println!("Added headers to {added} files, {present} already had one.");
+    }
+    Ok(())
+}
+
+async fn handle_header_check(
+    license: Option<&str>,
+    paths: Vec<PathBuf>,
+    providers: &ProviderChain,
+) -> Result<()> {
+    let expected = expression::parse(&header_expression(license, providers).await?)?;
+    let sources = header_sources(paths)?;
+    let mut problems = 0;
+    for source in &sources {
+        if let Some(problem) = header::problem(&source.text, &expected) {
+            println!("{}: {problem}", source.path.display());
+            problems += 1;
+        }
+    }
+    if problems > 0 {
+        bail!("{problems} files have a missing or mismatched SPDX header.");
+    }
+    println!("All {} files have {expected} headers.", sources.len());
+    Ok(())
+}
+
+async fn handle_header_update(
+    author: Option<String>,
+    from: Vec<String>,
+    license: Option<String>,
+    dry_run: bool,
+    paths: Vec<PathBuf>,
+    providers: &ProviderChain,
+) -> Result<()> {
+    if author.is_none() && license.is_none() {
+        bail!("Nothing to update. Pass --license and/or --author.");
+    }
+    let expression = match &license {
+        Some(license) => Some(header_expression(Some(license), providers).await?),
+        None => None,
+    };
+
+    let from = if author.is_some() && from.is_empty() {
+        own_holders()?
+    } else {
+        from
+    };
+    let holder = author.as_deref().map(|to| HolderChange { from: &from, to });
+
+    let mut updated = 0;
+    for source in header_sources(paths)? {
+        if let Some(text) = header::update_header(&source.text, expression.as_deref(), holder) {
+            write_source(&source.path, &text, dry_run, "update")?;
+            updated += 1;
+        }
+    }
+    if dry_run {
+        println!("{updated} headers would be updated.");
+    } else {
+        println!("Updated {updated} headers.");
+    }
+    Ok(())
+}
+
+/// The project's copyright holders: those in its license files, else the git user.
+fn own_holders() -> Result<Vec<String>> {
+    let holders: Vec<String> = debian::license_file_holders(&project_root())?
+        .into_keys()
+        .collect();
+    if !holders.is_empty() {
+        return Ok(holders);
+    }
+    git_user_name()
+        .map(|name| vec![name])
+        .context("Cannot tell whose copyright to replace. Pass the current holder with --from.")
+}
+
+fn handle_header_remove(dry_run: bool, paths: Vec<PathBuf>) -> Result<()> {
+    let mut removed = 0;
+    for source in header_sources(paths)? {
+        if let Some(text) = header::remove_header(&source.text) {
+            write_source(&source.path, &text, dry_run, "remove the header from")?;
+            removed += 1;
+        }
+    }
+    if dry_run {
+        println!("{removed} headers would be removed.");
+    } else {
+        println!("Removed {removed} headers.");
+    }
+    Ok(())
+}
+
+/// The source files under `paths`, the project root by default, see [`header::sources`].
+fn header_sources(paths: Vec<PathBuf>) -> Result<Vec<SourceFile>> {
+    let roots = if paths.is_empty() {
+        vec![project_root()]
+    } else {
+        paths
+    };
+    Ok(header::sources(&roots)?)
+}
+
+/// Writes a changed source file, or with `dry_run` says what would be done.
+fn write_source(path: &Path, text: &str, dry_run: bool, action: &str) -> Result<()> {
+    if dry_run {
+        println!("Would {action} {}", path.display());
+        return Ok(());
+    }
+    Ok(write_license(path, text)?)
+}
+
+/// The SPDX expression for `license`, which may be a key such as `apache`
+/// or an expression. Defaults to the licenses detected in the project.
+async fn header_expression(license: Option<&str>, providers: &ProviderChain) -> Result<String> {
+    let license = match license {
+        Some(license) => license.to_string(),
+        None => {
+            let root = project_root();
+            match detect::project_expression(&root) {
+                Some(expression) => expression.to_string(),
+                None if find_license_files(&root).is_empty() => "mit".to_string(),
+                None => ask_license(&root)?,
+            }
+        }
+    };
+    let parsed = expression::parse(&license)?;
+    let parsed = match &parsed {
+        Expression::License(term) if !term.or_later && term.exception.is_none() => {
+            expression::parse(&providers.spdx_id(&term.id).await?)?
+        }
+        _ => parsed,
+    };
+    if let Some(issue) = parsed
+        .validate()
+        .into_iter()
+        .find(|issue| issue.severity == Severity::Error)
+    {
+        bail!("{}", issue.message);
+    }
+    Ok(parsed.normalize().to_string())
+}
+
+/// Asks which license the project in `dir` uses, when its license files
+/// could not be recognized with certainty.
+fn ask_license(dir: &Path) -> Result<String> {
+    if !io::stdin().is_terminal() {
+        bail!(
+            "Could not tell which license the files in {} contain. Pass --license.",
+            display(dir)
+        );
+    }
+    Ok(input("Which license does the project use?")
+        .placeholder("An SPDX expression, e.g. MIT OR Apache-2.0")
+        .interact()?)
+}
+
+async fn handle_reuse(action: ReuseAction, providers: &ProviderChain) -> Result<()> {
+    match action {
+        ReuseAction::Download { licenses, force } => {
+            handle_reuse_download(&licenses, force, providers).await
+        }
+        ReuseAction::Annotate {
+            args,
+            dep5,
+            sidecar,
+            dry_run,
+            paths,
+        } => handle_reuse_annotate(&args, dep5, sidecar, dry_run, paths, providers).await,
+        ReuseAction::Lint { json, dir } => {
+            handle_reuse_lint(&dir.unwrap_or_else(project_root), json)
+        }
+    }
+}
+
+async fn handle_reuse_download(
+    licenses: &[String],
+    force: bool,
+    providers: &ProviderChain,
+) -> Result<()> {
+    let root = project_root();
+    let ids: Vec<String> = if licenses.is_empty() {
+        reuse::lint(&root)?.missing_licenses.into_keys().collect()
+    } else {
+        let mut ids = Vec::new();
+        for license in licenses {
+            let parsed = expression::parse(&header_expression(Some(license), providers).await?)?;
+            for term in parsed.terms() {
+                for id in std::iter::once(&term.id).chain(term.exception.as_ref()) {
+                    if !ids.contains(id) {
+                        ids.push(id.clone());
+                    }
+                }
+            }
+        }
+        ids
+    };
+    if ids.is_empty() {
+        println!(
+            "{} has every license the project uses.",
+            reuse::LICENSES_DIR
+        );
+        return Ok(());
+    }
+
+    for id in ids {
+        let path = reuse::license_path(&root, &id);
+        if path.exists() && !force {
+            println!(
+                "{} already exists. Use --force to overwrite it.",
+                path.display()
+            );
+            continue;
+        }
+        let Some(text) = reuse::license_text(&id) else {
+            eprintln!(
+                "warning: {id} is not on the SPDX License List; write {} yourself",
+                path.display()
+            );
+            continue;
+        };
+        write_license(&path, text)?;
+        println!("Wrote {}", path.display());
+    }
+    Ok(())
+}
+
+async fn handle_reuse_annotate(
+    args: &HeaderArgs,
+    dep5: bool,
+    sidecar: bool,
+    dry_run: bool,
+    paths: Vec<PathBuf>,
+    providers: &ProviderChain,
+) -> Result<()> {
+    let root = project_root();
+    let expression = header_expression(args.license.as_deref(), providers).await?;
+    let author = resolve_author(args.author.as_deref())?;
+    let year = resolve_year(args.year.as_deref());
+    let annotation = reuse::Annotation {
+        year: &year,
+        author: &author,
+        expression: &expression,
+        dep5,
+        sidecar,
+    };
+    let annotated = reuse::annotate(&root, &paths, &annotation)?;
+
+    for (path, text) in &annotated.headers {
+        write_source(path, text, dry_run, "add a header to")?;
+    }
+    for (path, text) in &annotated.sidecars {
+        write_source(path, text, dry_run, "write")?;
+    }
+    if let Some((path, text)) = &annotated.annotations {
+        let action = format!("annotate {} files in", annotated.annotated.len());
+        write_source(path, text, dry_run, &action)?;
+    }
+
+    let verb = if dry_run { "Would add" } else { "Added" };
+    println!(
+        "{verb} {} headers, {} .license files and {} annotations; {} files already had copyright and licensing information.",
+        annotated.headers.len(),
+        annotated.sidecars.len(),
+        annotated.annotated.len(),
+        annotated.present
+    );
+    Ok(())
+}
+
+fn handle_reuse_lint(dir: &Path, json: bool) -> Result<()> {
+    let lint = reuse::lint(dir)?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&lint)?);
+    } else {
+        print_lint(&lint);
+    }
+    if !lint.is_compliant() {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+fn print_lint(lint: &reuse::Lint) {
+    let section = |title: &str, items: &[String]| {
+        if !items.is_empty() {
+            println!("# {title}\n");
+            for item in items {
+                println!("* {item}");
+            }
+            println!();
+        }
+    };
+    section("BAD LICENSES", &lint.bad_licenses);
+    section("DEPRECATED LICENSES", &lint.deprecated_licenses);
+    section(
+        "LICENSES WITHOUT FILE EXTENSION",
+        &lint.licenses_without_extension,
+    );
+    let missing: Vec<String> = lint
+        .missing_licenses
+        .iter()
+        .map(|(id, files)| format!("{id} used in: {}", files.join(", ")))
+        .collect();
+    section("MISSING LICENSES", &missing);
+    section("UNUSED LICENSES", &lint.unused_licenses);
+    section("READ ERRORS", &lint.read_errors);
+    let invalid: Vec<String> = lint
+        .invalid_expressions
+        .iter()
+        .map(|(file, expr)| format!("{file}: '{expr}'"))
+        .collect();
+    section("INVALID SPDX LICENSE EXPRESSIONS", &invalid);
+    section("NO COPYRIGHT INFORMATION", &lint.missing_copyright);
+    section("NO LICENSING INFORMATION", &lint.missing_licensing);
+
+    println!("# SUMMARY\n");
+    println!("* Bad licenses: {}", lint.bad_licenses.len());
+    println!("* Deprecated licenses: {}", lint.deprecated_licenses.len());
+    println!(
+        "* Licenses without file extension: {}",
+        lint.licenses_without_extension.len()
+    );
+    println!("* Missing licenses: {}", lint.missing_licenses.len());
+    println!("* Unused licenses: {}", lint.unused_licenses.len());
+    println!("* Used licenses: {}", lint.used_licenses.join(", "));
+    println!("* Read errors: {}", lint.read_errors.len());
+    println!(
+        "* Invalid SPDX license expressions: {}",
+        lint.invalid_expressions.len()
+    );
+    println!(
+        "* Files with copyright information: {} / {}",
+        lint.files - lint.missing_copyright.len(),
+        lint.files
+    );
+    println!(
+        "* Files with license information: {} / {}",
+        lint.files - lint.missing_licensing.len(),
+        lint.files
+    );
+    println!();
+    if lint.is_compliant() {
+        println!("The project is compliant with version 3.3 of the REUSE Specification.");
+    } else {
+        println!("The project is not compliant with version 3.3 of the REUSE Specification.");
+    }
+}
+
+fn handle_update(
+    author: Option<String>,
+    year: Option<u32>,
+    dry_run: bool,
+    dir: &Path,
+) -> Result<()> {
+    let path = find_license_file(dir)
+        .with_context(|| format!("No license file found in {}.", dir.display()))?;
+    let author = resolve_author(author.as_deref())?;
+    let year = year.unwrap_or_else(current_year);
+
+    let text =
+        fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let (updated, changed) = copyright::bump_years(&text, &author, year);
+    if changed && dry_run {
+        print!(
+            "{}",
+            unified_diff(&text, &updated, &display(&path), &display(&path))
+        );
+    } else if changed {
+        write_license(&path, &updated)?;
+        println!(
+            "Updated copyright years for {author} in {} to {year}.",
+            path.display()
+        );
+    } else {
+        println!("{} is already up to date.", path.display());
+    }
+    Ok(())
+}
+
+/// Writes debian/copyright: files grouped by license, the project license for
+/// `Files: *`, and a standalone paragraph for every license used.
+async fn handle_debian(args: &DebianArgs, providers: &ProviderChain) -> Result<()> {
+    let root = args.dir.clone().unwrap_or_else(project_root);
+    let default = match &args.license {
+        Some(license) => Some(expression::parse(
+            &header_expression(Some(license), providers).await?,
+        )?),
+        None => detect::project_expression(&root),
+    };
+
+    let holders = debian::license_file_holders(&root)?;
+    let debian::Stanzas { stanzas, invalid } = debian::stanzas(&root, default.as_ref(), &holders)?;
+    for (file, license) in &invalid {
+        eprintln!("warning: skipping {file}: '{license}' is not a valid SPDX license expression");
+    }
+    if stanzas.is_empty() {
+        bail!(
+            "No license found in {}. Pass --license to set one.",
+            root.display()
+        );
+    }
+    for stanza in stanzas.iter().filter(|s| s.copyright.is_empty()) {
+        eprintln!(
+            "warning: no copyright found for {}; fill in its Copyright field",
+            stanza.files.join(" ")
+        );
+    }
+    let licenses = debian::license_paragraphs(&stanzas, &root, providers).await?;
+
+    let name = args
+        .upstream_name
+        .clone()
+        .unwrap_or_else(|| lic::project_name(&root));
+    let upstream = debian::Upstream {
+        name,
+        contact: args.contact.clone(),
+        source: args
+            .source_url
+            .clone()
+            .or_else(|| debian::upstream_source(&root)),
+    };
+    let text = debian::render(&upstream, &stanzas, &licenses);
+    if args.stdout {
+        print!("{text}");
+        return Ok(());
+    }
+
+    let path = args
+        .output
+        .clone()
+        .unwrap_or_else(|| root.join(debian::COPYRIGHT_PATH));
+    let existing = fs::read_to_string(&path).ok();
+    if args.dry_run {
+        let old = existing.as_deref().unwrap_or_default();
+        print!(
+            "{}",
+            unified_diff(old, &text, &display(&path), &display(&path))
+        );
+    } else if existing.is_some() && !args.force {
+        bail!(
+            "{} already exists. Use --force to overwrite it.",
+            path.display()
+        );
+    } else {
+        write_license(&path, &text)?;
+        println!("Wrote {}", path.display());
+    }
+    Ok(())
+}
+
+fn handle_expr(action: &ExprAction) -> Result<()> {
+    let (ExprAction::Validate { expr }
+    | ExprAction::Explain { expr }
+    | ExprAction::Normalize { expr }) = action;
+    let expr = expr.join(" ");
+    let parsed = expression::parse(&expr)?;
+    let issues = parsed.validate();
+    for issue in &issues {
+        match issue.severity {
+            Severity::Error => eprintln!("error: {}", issue.message),
+            Severity::Warning => eprintln!("warning: {}", issue.message),
+        }
+    }
+    let errors = issues
+        .iter()
+        .filter(|i| i.severity == Severity::Error)
+        .count();
+
+    match action {
+        ExprAction::Validate { .. } if errors == 0 => println!("{parsed} is valid."),
+        ExprAction::Validate { .. } => bail!("'{expr}' is not a valid SPDX expression."),
+        ExprAction::Explain { .. } => print!("{}", parsed.normalize().explain()),
+        ExprAction::Normalize { .. } if errors == 0 => println!("{}", parsed.normalize()),
+        ExprAction::Normalize { .. } => bail!("Cannot normalize '{expr}'."),
+    }
+    Ok(())
+}
+
+async fn handle_cache(action: &CacheAction, api: &GitHubApi) -> Result<()> {
+    let cache = api.cache();
+    match action {
+        CacheAction::List => {
+            let entries = cache.entries();
+            if entries.is_empty() {
+                println!("Cache is empty ({}).", cache.dir().display());
+            }
+            for (name, entry) in entries {
+                let state = if cache.is_fresh(&entry) {
+                    "fresh"
+                } else {
+                    "stale"
+                };
+                println!(
+                    "{name:<28} {state:<6} {}  {}",
+                    entry.fetched_at.format("%Y-%m-%d %H:%M UTC"),
+                    entry.etag.as_deref().unwrap_or("-")
+                );
+            }
+        }
+        CacheAction::Refresh => {
+            let licenses = api.licenses_list(true).await?;
+            for license in &licenses {
+                api.license_body(&license.key, true).await?;
+            }
+            println!("Refreshed {} licenses.", licenses.len());
+        }
+        CacheAction::Clear => {
+            cache.clear()?;
+            println!("Cleared {}.", cache.dir().display());
+        }
+        CacheAction::Path => println!("{}", cache.dir().display()),
+    }
+    Ok(())
 }
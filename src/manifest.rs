@@ -0,0 +1,668 @@
+//! The license field of package manifests such as `Cargo.toml`, `package.json`,
+//! `pom.xml` or a `Dockerfile`, edited in place so that formatting and
+//! comments survive.
+
+mod cargo;
+mod composer;
+mod docker;
+mod dotnet;
+mod gem;
+mod gradle;
+mod json;
+mod maven;
+mod npm;
+mod python;
+mod xml;
+
+use crate::expression::{self, Expression, Severity};
+use crate::{Error, Result};
+use ignore::WalkBuilder;
+use serde::Serialize;
+use spdx::identifiers::LICENSES;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use toml_edit::{DocumentMut, Item, TableLike};
+
+/// What a manifest's license field is set to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LicenseField {
+    /// An SPDX expression such as `MIT OR Apache-2.0`.
+    Expression(String),
+    /// A license file, for licenses that are not on the SPDX License List.
+    File(PathBuf),
+}
+
+impl LicenseField {
+    /// What manifests should declare for `expression`, written to
+    /// `license_file`: the expression, or the file when the expression is not
+    /// on the SPDX License List.
+    pub fn new(expression: &Expression, license_file: &Path) -> Self {
+        let is_spdx = !expression
+            .validate()
+            .iter()
+            .any(|issue| issue.severity == Severity::Error);
+        if is_spdx {
+            LicenseField::Expression(expression.normalize().to_string())
+        } else {
+            LicenseField::File(license_file.to_path_buf())
+        }
+    }
+}
+
+/// [`LicenseField`] as written into one manifest, with the file relative to it.
+enum Field<'a> {
+    Expression(&'a str),
+    File(&'a str),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ManifestKind {
+    /// `Cargo.toml`
+    Cargo,
+    /// `package.json`
+    Npm,
+    /// `pyproject.toml`
+    Python,
+    /// `pom.xml`
+    Maven,
+    /// `build.gradle` and `build.gradle.kts` publishing blocks
+    Gradle,
+    /// `*.csproj` and other MSBuild projects
+    DotNet,
+    /// `*.nuspec`
+    NuGet,
+    /// `composer.json`
+    Composer,
+    /// `*.gemspec`
+    Gem,
+    /// `Dockerfile` and `Containerfile` labels
+    Docker,
+}
+
+impl ManifestKind {
+    /// The kind of manifest `path` is, by file name.
+    pub fn for_path(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_str()?;
+        Some(match name {
+            "Cargo.toml" => ManifestKind::Cargo,
+            "package.json" => ManifestKind::Npm,
+            "pyproject.toml" => ManifestKind::Python,
+            "pom.xml" => ManifestKind::Maven,
+            "build.gradle" | "build.gradle.kts" => ManifestKind::Gradle,
+            "composer.json" => ManifestKind::Composer,
+            "Dockerfile" | "Containerfile" => ManifestKind::Docker,
+            _ if name.starts_with("Dockerfile.") || name.ends_with(".Dockerfile") => {
+                ManifestKind::Docker
+            }
+            _ => match path.extension()?.to_str()? {
+                "csproj" | "fsproj" | "vbproj" => ManifestKind::DotNet,
+                "nuspec" => ManifestKind::NuGet,
+                "gemspec" => ManifestKind::Gem,
+                _ => return None,
+            },
+        })
+    }
+}
+
+/// A package manifest found in a project.
+#[derive(Debug, Clone, Serialize)]
+pub struct Manifest {
+    pub kind: ManifestKind,
+    pub path: PathBuf,
+}
+
+impl Manifest {
+    pub fn text(&self) -> Result<String> {
+        fs::read_to_string(&self.path).map_err(|e| Error::io(&self.path, e))
+    }
+
+    /// The license `text` declares, following Cargo workspace inheritance
+    /// within the same file.
+    pub fn license(&self, text: &str) -> Result<Option<String>> {
+        match self.kind {
+            ManifestKind::Cargo => cargo::license(text),
+            ManifestKind::Npm => npm::license(text),
+            ManifestKind::Python => python::license(text),
+            ManifestKind::Maven => maven::license(text),
+            ManifestKind::Gradle => Ok(gradle::license(text)),
+            ManifestKind::DotNet => dotnet::project_license(text),
+            ManifestKind::NuGet => dotnet::nuspec_license(text),
+            ManifestKind::Composer => composer::license(text),
+            ManifestKind::Gem => Ok(gem::license(text)),
+            ManifestKind::Docker => Ok(docker::license(text)),
+        }
+        .map_err(|message| self.parse_error(message))
+    }
+
+    /// `text` with its license set to `license`, or `None` when nothing changes
+    /// or the manifest has no place for it.
+    pub fn with_license(&self, text: &str, license: &LicenseField) -> Result<Option<String>> {
+        let relative;
+        let field = match license {
+            LicenseField::Expression(expression) => Field::Expression(expression),
+            LicenseField::File(file) => {
+                relative = relative_to(file, self.path.parent().unwrap_or(Path::new("")));
+                Field::File(&relative)
+            }
+        };
+        let updated = match self.kind {
+            ManifestKind::Cargo => cargo::set_license(text, &field),
+            ManifestKind::Npm => npm::set_license(text, &field),
+            ManifestKind::Python => python::set_license(text, &field),
+            ManifestKind::Maven => maven::set_license(text, &field),
+            ManifestKind::Gradle => Ok(gradle::set_license(text, &field)),
+            ManifestKind::DotNet => dotnet::set_project_license(text, &field),
+            ManifestKind::NuGet => dotnet::set_nuspec_license(text, &field),
+            ManifestKind::Composer => composer::set_license(text, &field),
+            ManifestKind::Gem => Ok(gem::set_license(text, &field)),
+            ManifestKind::Docker => Ok(docker::set_license(text, &field)),
+        }
+        .map_err(|message| self.parse_error(message))?;
+        Ok((updated != text).then_some(updated))
+    }
+
+    fn parse_error(&self, message: String) -> Error {
+        Error::Parse {
+            path: self.path.clone(),
+            message,
+        }
+    }
+}
+
+/// Every manifest under `dir`, skipping hidden and `node_modules` directories
+/// and anything excluded by `.gitignore` or `.licignore`.
+pub fn find_manifests(dir: &Path) -> Result<Vec<Manifest>> {
+    let walker = WalkBuilder::new(dir)
+        .require_git(false)
+        .add_custom_ignore_filename(".licignore")
+        .filter_entry(|entry| entry.file_name() != "node_modules")
+        .build();
+    let mut manifests = Vec::new();
+    for entry in walker {
+        let entry = entry?;
+        if !entry.file_type().is_some_and(|t| t.is_file()) {
+            continue;
+        }
+        if let Some(kind) = ManifestKind::for_path(entry.path()) {
+            manifests.push(Manifest {
+                kind,
+                path: entry.into_path(),
+            });
+        }
+    }
+    manifests.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(manifests)
+}
+
+/// A manifest whose license field changes.
+#[derive(Debug)]
+pub struct Update {
+    pub path: PathBuf,
+    pub old: String,
+    pub new: String,
+}
+
+impl Update {
+    pub fn write(&self) -> Result<()> {
+        fs::write(&self.path, &self.new).map_err(|e| Error::io(&self.path, e))
+    }
+}
+
+/// The manifests under `dir` whose license field differs from `license`: the
+/// ones directly in `dir` and the Cargo workspace members inheriting their
+/// license, or with `all` every manifest [`find_manifests`] finds.
+pub fn updates(dir: &Path, license: &LicenseField, all: bool) -> Result<Vec<Update>> {
+    let mut updates = Vec::new();
+    for manifest in find_manifests(dir)? {
+        let in_root = manifest.path.parent() == Some(dir);
+        if !all && !in_root && manifest.kind != ManifestKind::Cargo {
+            continue;
+        }
+        let old = manifest.text()?;
+        if !all && !in_root && !cargo::inherits_license(&old) {
+            continue;
+        }
+        if let Some(new) = manifest.with_license(&old, license)? {
+            updates.push(Update {
+                path: manifest.path,
+                old,
+                new,
+            });
+        }
+    }
+    Ok(updates)
+}
+
+/// The SPDX expression declared by the first manifest directly in `dir` that
+/// has one, normalized. Licenses that are not SPDX expressions are skipped.
+pub fn declared_license(dir: &Path) -> Result<Option<Expression>> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Ok(None);
+    };
+    let mut manifests: Vec<Manifest> = entries
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            let kind = ManifestKind::for_path(&path)?;
+            path.is_file().then_some(Manifest { kind, path })
+        })
+        .collect();
+    manifests.sort_by(|a, b| a.path.cmp(&b.path));
+    for manifest in manifests {
+        let text = manifest.text()?;
+        let Ok(Some(license)) = manifest.license(&text) else {
+            continue;
+        };
+        if let Ok(expression) = expression::parse(&license)
+            && expression
+                .validate()
+                .iter()
+                .all(|issue| issue.severity != Severity::Error)
+        {
+            return Ok(Some(expression.normalize()));
+        }
+    }
+    Ok(None)
+}
+
+/// The SPDX id for a license name as found in manifests that predate SPDX,
+/// such as `The Apache Software License, Version 2.0` in a `pom.xml`.
+pub fn spdx_id_for_name(name: &str) -> Option<&'static str> {
+    const COMMON_NAMES: &[(&str, &str)] = &[
+        ("apachesoftwarelicense20", "Apache-2.0"),
+        ("newbsdlicense", "BSD-3-Clause"),
+        ("simplifiedbsdlicense", "BSD-2-Clause"),
+    ];
+    let name = name.trim();
+    if let Some(license) = spdx::license_id(name) {
+        return Some(license.name);
+    }
+    let words = |s: &str| -> String {
+        s.split(|c: char| !c.is_alphanumeric())
+            .map(str::to_lowercase)
+            .filter(|w| !w.is_empty() && !matches!(w.as_str(), "the" | "version" | "v"))
+            .collect()
+    };
+    let wanted = words(name);
+    COMMON_NAMES
+        .iter()
+        .find(|(common, _)| *common == wanted)
+        .map(|(_, id)| *id)
+        .or_else(|| {
+            LICENSES
+                .iter()
+                .find(|(_, full_name, _)| words(full_name) == wanted)
+                .map(|(id, _, _)| *id)
+        })
+}
+
+/// Whether `declared` is the same license as `expression`, whatever the order
+/// of its operands.
+fn is_declared(declared: Option<&str>, expression: &str) -> bool {
+    let parse = |s: &str| expression::parse(s).ok();
+    declared
+        .and_then(parse)
+        .zip(parse(expression))
+        .is_some_and(|(declared, expression)| declared.equivalent(&expression))
+}
+
+/// The ids of an expression that only offers a choice between plain licenses,
+/// such as `MIT OR Apache-2.0`; `None` for anything else.
+fn alternatives(expression: &str) -> Option<Vec<String>> {
+    let plain = |e: &Expression| match e {
+        Expression::License(term) if !term.or_later && term.exception.is_none() => {
+            Some(term.id.clone())
+        }
+        _ => None,
+    };
+    match expression::parse(expression).ok()? {
+        Expression::Or(terms) => terms.iter().map(plain).collect(),
+        single => plain(&single).map(|id| vec![id]),
+    }
+}
+
+/// `file` relative to `dir`, with `/` separators, assuming both are relative
+/// to the same root (or both absolute).
+fn relative_to(file: &Path, dir: &Path) -> String {
+    let normal = |path: &Path| -> Vec<String> {
+        path.components()
+            .filter(|c| !matches!(c, Component::CurDir))
+            .map(|c| c.as_os_str().to_string_lossy().to_string())
+            .collect()
+    };
+    let (file, dir) = (normal(file), normal(dir));
+    let common = file.iter().zip(&dir).take_while(|(a, b)| a == b).count();
+    let mut parts = vec![".."; dir.len() - common];
+    parts.extend(file[common..].iter().map(String::as_str));
+    parts.join("/")
+}
+
+fn parse_toml(text: &str) -> Result<DocumentMut, String> {
+    text.parse::<DocumentMut>().map_err(|e| e.to_string())
+}
+
+/// Sets `key` to `value`, keeping the comments around an existing value.
+fn set_toml_string(table: &mut dyn TableLike, key: &str, value: &str) {
+    match table.get_mut(key).and_then(Item::as_value_mut) {
+        Some(existing) if existing.as_str() == Some(value) => {}
+        Some(existing) => {
+            let decor = existing.decor().clone();
+            *existing = value.into();
+            *existing.decor_mut() = decor;
+        }
+        None => {
+            table.insert(key, toml_edit::value(value));
+        }
+    }
+}
+
+/// The position of the closing `quote` of a string whose contents start at `start`.
+fn closing_quote(text: &str, start: usize, quote: char) -> Option<usize> {
+    let mut escaped = false;
+    for (i, c) in text[start..].char_indices() {
+        match c {
+            '\\' if !escaped => escaped = true,
+            c if c == quote && !escaped => return Some(start + i),
+            _ => escaped = false,
+        }
+    }
+    None
+}
+
+/// The whitespace between the start of the line and `pos`, or nothing when
+/// something else precedes `pos` on its line.
+fn indent_before(text: &str, pos: usize) -> &str {
+    let line_start = text[..pos].rfind('\n').map_or(0, |i| i + 1);
+    let indent = &text[line_start..pos];
+    if indent.trim().is_empty() { indent } else { "" }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn declared_license_reads_top_level_manifests_only() {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir(dir.path().join("sub")).unwrap();
+        fs::write(
+            dir.path().join("sub/Cargo.toml"),
+            "[package]\nname = \"sub\"\nlicense = \"GPL-3.0-only\"\n",
+        )
+        .unwrap();
+        assert_eq!(declared_license(dir.path()).unwrap(), None);
+
+        fs::write(
+            dir.path().join("package.json"),
+            r#"{ "name": "demo", "license": "SEE LICENSE IN LICENSE.txt" }"#,
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("Cargo.toml"),
+            "[package]\nname = \"demo\"\nlicense = \"mit or apache-2.0\"\n",
+        )
+        .unwrap();
+        let declared = declared_license(dir.path()).unwrap().unwrap();
+        assert_eq!(declared.to_string(), "MIT OR Apache-2.0");
+    }
+
+    #[test]
+    fn license_field_falls_back_to_the_file() {
+        let file = Path::new("LICENSE");
+        let spdx = expression::parse("mit or apache-2.0").unwrap();
+        assert_eq!(
+            LicenseField::new(&spdx, file),
+            LicenseField::Expression("MIT OR Apache-2.0".to_string())
+        );
+        let custom = expression::parse("Acme-Proprietary").unwrap();
+        assert_eq!(
+            LicenseField::new(&custom, file),
+            LicenseField::File(file.to_path_buf())
+        );
+    }
+
+    #[test]
+    fn updates_only_changed_manifests() {
+        let dir = TempDir::new().unwrap();
+        fs::write(
+            dir.path().join("Cargo.toml"),
+            "[package]\nname = \"demo\"\nlicense = \"GPL-3.0-only\"\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("package.json"),
+            "{ \"name\": \"demo\", \"license\": \"MIT\" }\n",
+        )
+        .unwrap();
+
+        let license = LicenseField::Expression("MIT".to_string());
+        let changed = updates(dir.path(), &license, false).unwrap();
+        assert_eq!(changed.len(), 1);
+        let update = &changed[0];
+        assert_eq!(update.path, dir.path().join("Cargo.toml"));
+        assert!(update.new.contains("license = \"MIT\""));
+
+        update.write().unwrap();
+        assert!(updates(dir.path(), &license, false).unwrap().is_empty());
+    }
+
+    #[test]
+    fn updates_the_root_and_inheriting_members_unless_all() {
+        let dir = TempDir::new().unwrap();
+        let write = |path: &str, text: &str| {
+            let path = dir.path().join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, text).unwrap();
+        };
+        write(
+            "Cargo.toml",
+            "[workspace]\nmembers = [\"crates/*\"]\n\n[workspace.package]\nlicense = \"GPL-3.0-only\"\n",
+        );
+        write(
+            "crates/app/Cargo.toml",
+            "[package]\nname = \"app\"\nlicense.workspace = true\n",
+        );
+        write(
+            "crates/vendored/Cargo.toml",
+            "[package]\nname = \"vendored\"\nlicense = \"BSD-3-Clause\"\n",
+        );
+        write(
+            "package.json",
+            "{ \"name\": \"demo\", \"license\": \"GPL-3.0-only\" }\n",
+        );
+        write(
+            "examples/web/package.json",
+            "{ \"name\": \"example\", \"license\": \"ISC\" }\n",
+        );
+        let paths = |updates: Vec<Update>| -> Vec<String> {
+            updates
+                .iter()
+                .map(|u| {
+                    u.path
+                        .strip_prefix(dir.path())
+                        .unwrap()
+                        .display()
+                        .to_string()
+                })
+                .collect()
+        };
+
+        let license = LicenseField::Expression("MIT".to_string());
+        assert_eq!(
+            paths(updates(dir.path(), &license, false).unwrap()),
+            ["Cargo.toml", "package.json"]
+        );
+        let file = LicenseField::File(dir.path().join("LICENSE"));
+        assert_eq!(
+            paths(updates(dir.path(), &file, false).unwrap()),
+            ["Cargo.toml", "crates/app/Cargo.toml", "package.json"]
+        );
+        assert_eq!(
+            paths(updates(dir.path(), &license, true).unwrap()),
+            [
+                "Cargo.toml",
+                "crates/vendored/Cargo.toml",
+                "examples/web/package.json",
+                "package.json"
+            ]
+        );
+    }
+
+    fn set(name: &str, text: &str, license: &LicenseField) -> Option<String> {
+        let manifest = Manifest {
+            kind: ManifestKind::for_path(Path::new(name)).unwrap(),
+            path: PathBuf::from(name),
+        };
+        manifest.with_license(text, license).unwrap()
+    }
+
+    fn read(name: &str, text: &str) -> Option<String> {
+        let manifest = Manifest {
+            kind: ManifestKind::for_path(Path::new(name)).unwrap(),
+            path: PathBuf::from(name),
+        };
+        manifest.license(text).unwrap()
+    }
+
+    #[test]
+    fn updaters_round_trip() {
+        let cases = [
+            (
+                "pom.xml",
+                "<project>\n  <artifactId>demo</artifactId>\n</project>\n",
+            ),
+            (
+                "build.gradle",
+                "publishing {\n    pom {\n        licenses {\n            license {\n                name = 'GPL-3.0-only'\n            }\n        }\n    }\n}\n",
+            ),
+            (
+                "build.gradle.kts",
+                "pom {\n    licenses {\n        license {\n            name.set(\"GPL-3.0-only\")\n            url.set(\"https://www.gnu.org/licenses/gpl-3.0.txt\")\n        }\n    }\n}\n",
+            ),
+            (
+                "demo.gemspec",
+                "Gem::Specification.new do |spec|\n  spec.name = \"demo\"\n  spec.license = \"GPL-3.0-only\"\nend\n",
+            ),
+            ("Dockerfile", "FROM alpine:3\nRUN true\n"),
+            (
+                "demo.csproj",
+                "<Project>\n  <PropertyGroup>\n    <PackageId>demo</PackageId>\n  </PropertyGroup>\n</Project>\n",
+            ),
+            (
+                "demo.nuspec",
+                "<package>\n  <metadata>\n    <id>demo</id>\n  </metadata>\n</package>\n",
+            ),
+            (
+                "composer.json",
+                "{\n    \"name\": \"acme/demo\",\n    \"license\": \"GPL-3.0-only\"\n}\n",
+            ),
+            (
+                "pyproject.toml",
+                "[project]\nname = \"demo\"\nlicense = \"GPL-3.0-only\"\n",
+            ),
+        ];
+        for expression in [
+            "MIT",
+            "MIT OR Apache-2.0",
+            "GPL-2.0-or-later WITH Classpath-exception-2.0",
+        ] {
+            let license = LicenseField::Expression(expression.to_string());
+            for (name, text) in cases {
+                let new = set(name, text, &license)
+                    .unwrap_or_else(|| panic!("{name} was not updated to {expression}"));
+                let declared = read(name, &new);
+                assert!(
+                    is_declared(declared.as_deref(), expression),
+                    "{name} declares {declared:?} instead of {expression}:\n{new}"
+                );
+                assert_eq!(set(name, &new, &license), None, "{name} changed again");
+            }
+        }
+    }
+
+    #[test]
+    fn maven_keeps_other_elements_of_the_licenses_that_stay() {
+        let pom = "\
+<project>
+  <licenses>
+    <license>
+      <name>The Apache Software License, Version 2.0</name>
+      <url>https://www.apache.org/licenses/LICENSE-2.0.txt</url>
+      <distribution>repo</distribution>
+    </license>
+    <license>
+      <name>MIT</name>
+      <comments>For the vendored parts</comments>
+    </license>
+  </licenses>
+</project>
+";
+        let license = |expression: &str| LicenseField::Expression(expression.to_string());
+        assert_eq!(set("pom.xml", pom, &license("MIT OR Apache-2.0")), None);
+        assert_eq!(
+            set("pom.xml", pom, &license("Apache-2.0 OR Zlib")).unwrap(),
+            "\
+<project>
+  <licenses>
+    <license>
+      <name>The Apache Software License, Version 2.0</name>
+      <url>https://www.apache.org/licenses/LICENSE-2.0.txt</url>
+      <distribution>repo</distribution>
+    </license>
+    <license>
+      <name>Zlib</name>
+      <url>https://spdx.org/licenses/Zlib.html</url>
+    </license>
+  </licenses>
+</project>
+"
+        );
+    }
+
+    #[test]
+    fn gradle_writes_one_block_per_license() {
+        let gradle = "\
+licenses {
+    license {
+        name = 'Apache-2.0'
+        url = 'https://spdx.org/licenses/Apache-2.0.html'
+        distribution = 'repo'
+    }
+}
+";
+        let license = |expression: &str| LicenseField::Expression(expression.to_string());
+        let dual = set("build.gradle", gradle, &license("MIT OR Apache-2.0")).unwrap();
+        assert_eq!(
+            dual,
+            "\
+licenses {
+    license {
+        name = 'MIT'
+        url = 'https://spdx.org/licenses/MIT.html'
+        distribution = 'repo'
+    }
+    license {
+        name = 'Apache-2.0'
+        url = 'https://spdx.org/licenses/Apache-2.0.html'
+        distribution = 'repo'
+    }
+}
+"
+        );
+        assert_eq!(
+            set("build.gradle", &dual, &license("MIT AND Zlib")).unwrap(),
+            "\
+licenses {
+    license {
+        name = 'MIT AND Zlib'
+        distribution = 'repo'
+    }
+}
+"
+        );
+        let file = LicenseField::File(PathBuf::from("LICENSE"));
+        let custom = set("build.gradle", gradle, &file).unwrap();
+        assert!(custom.contains("name = 'SEE LICENSE IN LICENSE'\n        distribution"));
+    }
+}
@@ -0,0 +1,88 @@
+//! `Cargo.toml`: `package.license` or `package.license-file`, and
+//! `workspace.package` for crates with `license.workspace = true`.
+
+use super::{Field, parse_toml, set_toml_string};
+use toml_edit::{DocumentMut, InlineTable, Item, TableLike};
+
+pub(super) fn license(text: &str) -> Result<Option<String>, String> {
+    let doc = parse_toml(text)?;
+    let package = doc.get("package").and_then(|p| p.get("license"));
+    let license = match package {
+        Some(item) if !inherits(item) => Some(item),
+        _ => doc
+            .get("workspace")
+            .and_then(|w| w.get("package"))
+            .and_then(|p| p.get("license")),
+    };
+    Ok(license.and_then(Item::as_str).map(str::to_string))
+}
+
+/// Sets the license of the package, and of `workspace.package` when the
+/// package inherits it, it already declares one, or the workspace is virtual.
+pub(super) fn set_license(text: &str, field: &Field) -> Result<String, String> {
+    let mut doc = parse_toml(text)?;
+    let package_inherits = package_inherits(&doc);
+    if let Some(package) = doc.get_mut("package").and_then(Item::as_table_like_mut) {
+        if package_inherits {
+            // Inherit whichever of the two the workspace now declares.
+            let (key, _, other) = keys(field);
+            if !package.get(key).is_some_and(inherits) {
+                let mut inherit = InlineTable::new();
+                inherit.insert("workspace", true.into());
+                package.remove(other);
+                package.insert(key, toml_edit::value(inherit));
+            }
+        } else {
+            set_field(package, field);
+        }
+    }
+
+    let workspace_declares = doc
+        .get("workspace")
+        .and_then(|w| w.get("package"))
+        .is_some_and(|p| p.get("license").is_some() || p.get("license-file").is_some());
+    let is_virtual = doc.contains_key("workspace") && !doc.contains_key("package");
+    if (package_inherits || workspace_declares || is_virtual)
+        && let Some(workspace) = doc.get_mut("workspace").and_then(Item::as_table_like_mut)
+        && let Some(package) = workspace
+            .entry("package")
+            .or_insert(toml_edit::table())
+            .as_table_like_mut()
+    {
+        set_field(package, field);
+    }
+    Ok(doc.to_string())
+}
+
+/// Whether the package takes its license from the workspace, as members with
+/// `license.workspace = true` do. Unparsable manifests do not.
+pub(super) fn inherits_license(text: &str) -> bool {
+    parse_toml(text).is_ok_and(|doc| package_inherits(&doc))
+}
+
+fn package_inherits(doc: &DocumentMut) -> bool {
+    doc.get("package").is_some_and(|p| {
+        ["license", "license-file"]
+            .iter()
+            .any(|key| p.get(key).is_some_and(inherits))
+    })
+}
+
+/// `license.workspace = true` or `license-file.workspace = true`.
+fn inherits(item: &Item) -> bool {
+    item.get("workspace").and_then(Item::as_bool) == Some(true)
+}
+
+/// The key `field` sets, its value, and the key it replaces.
+fn keys<'a>(field: &Field<'a>) -> (&'static str, &'a str, &'static str) {
+    match field {
+        Field::Expression(expression) => ("license", expression, "license-file"),
+        Field::File(file) => ("license-file", file, "license"),
+    }
+}
+
+fn set_field(table: &mut dyn TableLike, field: &Field) {
+    let (key, value, other) = keys(field);
+    set_toml_string(table, key, value);
+    table.remove(other);
+}
@@ -0,0 +1,42 @@
+//! PHP `composer.json`: `license` as a string, or an array for a choice of
+//! licenses. Composer spells a custom license `proprietary`.
+
+use super::{Field, alternatives, json};
+use serde_json::Value;
+
+pub(super) fn license(text: &str) -> Result<Option<String>, String> {
+    Ok(match &json::parse(text)?["license"] {
+        Value::String(license) => Some(license.clone()),
+        Value::Array(licenses) => {
+            let ids: Vec<&str> = licenses.iter().filter_map(Value::as_str).collect();
+            (!ids.is_empty()).then(|| ids.join(" OR "))
+        }
+        _ => None,
+    })
+}
+
+pub(super) fn set_license(text: &str, field: &Field) -> Result<String, String> {
+    let value = match field {
+        Field::Expression(expression) => match alternatives(expression) {
+            Some(ids) if ids.len() > 1 => Value::from(ids),
+            _ => Value::from(*expression),
+        },
+        Field::File(_) => Value::from("proprietary"),
+    };
+    if json::parse(text)?["license"] == value {
+        return Ok(text.to_string());
+    }
+    let json = match &value {
+        Value::Array(ids) => {
+            let ids: Vec<String> = ids.iter().map(Value::to_string).collect();
+            format!("[{}]", ids.join(", "))
+        }
+        value => value.to_string(),
+    };
+    json::set_member(
+        text,
+        "license",
+        &json,
+        &["homepage", "keywords", "type", "description", "name"],
+    )
+}
@@ -0,0 +1,59 @@
+//! `Dockerfile`: the OCI `org.opencontainers.image.licenses` label, added
+//! after the last `FROM` when missing. It only takes SPDX expressions.
+
+use super::{Field, closing_quote};
+use std::ops::Range;
+
+const LABEL: &str = "org.opencontainers.image.licenses";
+
+pub(super) fn license(text: &str) -> Option<String> {
+    let range = value_range(text)?;
+    Some(text[range].trim_matches('"').to_string())
+}
+
+pub(super) fn set_license(text: &str, field: &Field) -> String {
+    let Field::Expression(expression) = field else {
+        return text.to_string();
+    };
+    let value = format!("\"{expression}\"");
+    if let Some(range) = value_range(text) {
+        return format!("{}{value}{}", &text[..range.start], &text[range.end..]);
+    }
+
+    let mut offset = 0;
+    let mut after_from = None;
+    for line in text.split_inclusive('\n') {
+        offset += line.len();
+        let instruction = line.trim_start().get(..5).unwrap_or_default();
+        if instruction.eq_ignore_ascii_case("FROM ") {
+            after_from = Some(offset - (line.len() - line.trim_end_matches(['\n', '\r']).len()));
+        }
+    }
+    match after_from {
+        Some(at) => format!("{}\nLABEL {LABEL}={value}{}", &text[..at], &text[at..]),
+        None => text.to_string(),
+    }
+}
+
+/// The label's value, with its quotes, outside comments.
+fn value_range(text: &str) -> Option<Range<usize>> {
+    let mut from = 0;
+    while let Some(i) = text[from..].find(LABEL) {
+        let start = from + i;
+        from = start + LABEL.len();
+        let line_start = text[..start].rfind('\n').map_or(0, |i| i + 1);
+        if text[line_start..start].trim_start().starts_with('#') || !text[from..].starts_with('=') {
+            continue;
+        }
+        let value_start = from + 1;
+        let end = if text[value_start..].starts_with('"') {
+            closing_quote(text, value_start + 1, '"')? + 1
+        } else {
+            text[value_start..]
+                .find(char::is_whitespace)
+                .map_or(text.len(), |i| value_start + i)
+        };
+        return Some(value_start..end);
+    }
+    None
+}
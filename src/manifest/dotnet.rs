@@ -0,0 +1,84 @@
+//! MSBuild projects (`PackageLicenseExpression` or `PackageLicenseFile`) and
+//! NuGet `.nuspec` files (`<license type="expression">`).
+
+use super::{Field, xml};
+
+pub(super) fn project_license(text: &str) -> Result<Option<String>, String> {
+    let doc = xml::parse(text)?;
+    Ok(
+        xml::descendant(doc.root_element(), "PackageLicenseExpression")
+            .and_then(xml::text)
+            .map(str::to_string),
+    )
+}
+
+pub(super) fn set_project_license(text: &str, field: &Field) -> Result<String, String> {
+    let (name, value, other) = match field {
+        Field::Expression(expression) => (
+            "PackageLicenseExpression",
+            *expression,
+            "PackageLicenseFile",
+        ),
+        Field::File(file) => ("PackageLicenseFile", *file, "PackageLicenseExpression"),
+    };
+    let doc = xml::parse(text)?;
+    let root = doc.root_element();
+    let element = format!("<{name}>{}</{name}>", xml::escape(value));
+    if let Some(node) = xml::descendant(root, name) {
+        if xml::text(node) == Some(value) {
+            return Ok(text.to_string());
+        }
+        return Ok(xml::replace(text, node, &element));
+    }
+    if let Some(node) = xml::descendant(root, other) {
+        return Ok(xml::replace(text, node, &element));
+    }
+    let group = root.children().find(|c| {
+        c.is_element()
+            && c.tag_name().name() == "PropertyGroup"
+            && c.attribute("Condition").is_none()
+    });
+    Ok(match group {
+        Some(group) => xml::insert(text, group, &[], &element),
+        None => text.to_string(),
+    })
+}
+
+pub(super) fn nuspec_license(text: &str) -> Result<Option<String>, String> {
+    let doc = xml::parse(text)?;
+    Ok(xml::child(doc.root_element(), "metadata")
+        .and_then(|metadata| xml::child(metadata, "license"))
+        .filter(|license| license.attribute("type") == Some("expression"))
+        .and_then(xml::text)
+        .map(str::to_string))
+}
+
+pub(super) fn set_nuspec_license(text: &str, field: &Field) -> Result<String, String> {
+    let (kind, value) = match field {
+        Field::Expression(expression) => ("expression", *expression),
+        Field::File(file) => ("file", *file),
+    };
+    let doc = xml::parse(text)?;
+    let Some(metadata) = xml::child(doc.root_element(), "metadata") else {
+        return Ok(text.to_string());
+    };
+    let element = format!(r#"<license type="{kind}">{}</license>"#, xml::escape(value));
+    // `<license>` supersedes the deprecated `<licenseUrl>`, so it takes its place.
+    let existing = xml::child(metadata, "license").or_else(|| xml::child(metadata, "licenseUrl"));
+    Ok(match existing {
+        Some(node)
+            if node.tag_name().name() == "license"
+                && node.attribute("type") == Some(kind)
+                && xml::text(node) == Some(value) =>
+        {
+            text.to_string()
+        }
+        Some(node) => xml::replace(text, node, &element),
+        None => xml::insert(
+            text,
+            metadata,
+            &["id", "version", "title", "authors", "owners"],
+            &element,
+        ),
+    })
+}
@@ -0,0 +1,104 @@
+//! Ruby `.gemspec`: `spec.license = "MIT"`, or `spec.licenses = [...]` for a
+//! choice of licenses. RubyGems spells a custom license `Nonstandard`.
+
+use super::{Field, alternatives};
+
+/// A `<var>.<attribute> = <value>` line.
+struct Assignment<'a> {
+    indent: &'a str,
+    var: &'a str,
+    attribute: &'a str,
+    /// Position of the attribute name.
+    start: usize,
+    /// End of the line, before the newline.
+    end: usize,
+    value: &'a str,
+}
+
+pub(super) fn license(text: &str) -> Option<String> {
+    let assignment = assignments(text)
+        .into_iter()
+        .find(|a| matches!(a.attribute, "license" | "licenses"))?;
+    let ids: Vec<&str> = assignment
+        .value
+        .split(['"', '\''])
+        .skip(1)
+        .step_by(2)
+        .collect();
+    (!ids.is_empty()).then(|| ids.join(" OR "))
+}
+
+pub(super) fn set_license(text: &str, field: &Field) -> String {
+    let quote = |s: &str| format!("\"{s}\"");
+    let new = match field {
+        Field::Expression(expression) => match alternatives(expression) {
+            Some(ids) if ids.len() > 1 => {
+                let ids: Vec<String> = ids.iter().map(|id| quote(id)).collect();
+                format!("licenses = [{}]", ids.join(", "))
+            }
+            _ => format!("license = {}", quote(expression)),
+        },
+        Field::File(_) => format!("license = {}", quote("Nonstandard")),
+    };
+
+    let assignments = assignments(text);
+    if let Some(existing) = assignments
+        .iter()
+        .find(|a| matches!(a.attribute, "license" | "licenses"))
+    {
+        return format!("{}{new}{}", &text[..existing.start], &text[existing.end..]);
+    }
+    let anchor = assignments.iter().rfind(|a| {
+        matches!(
+            a.attribute,
+            "name" | "version" | "summary" | "description" | "authors" | "email" | "homepage"
+        )
+    });
+    match anchor {
+        Some(anchor) => format!(
+            "{}\n{}{}.{new}{}",
+            &text[..anchor.end],
+            anchor.indent,
+            anchor.var,
+            &text[anchor.end..]
+        ),
+        None => text.to_string(),
+    }
+}
+
+fn assignments(text: &str) -> Vec<Assignment<'_>> {
+    let mut assignments = Vec::new();
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        let line_start = offset;
+        offset += line.len();
+        let content = line.trim_end_matches(['\n', '\r']);
+        let trimmed = content.trim_start();
+        let indent = &content[..content.len() - trimmed.len()];
+        let Some((var, rest)) = trimmed.split_once('.') else {
+            continue;
+        };
+        if var.is_empty() || !var.chars().all(|c| c.is_alphanumeric() || c == '_') {
+            continue;
+        }
+        let attribute_len = rest
+            .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len());
+        let (attribute, after) = rest.split_at(attribute_len);
+        let Some(value) = after.trim_start().strip_prefix('=') else {
+            continue;
+        };
+        if attribute.is_empty() || value.starts_with(['=', '~']) {
+            continue;
+        }
+        assignments.push(Assignment {
+            indent,
+            var,
+            attribute,
+            start: line_start + indent.len() + var.len() + 1,
+            end: line_start + content.len(),
+            value: value.trim(),
+        });
+    }
+    assignments
+}
@@ -0,0 +1,239 @@
+//! Gradle `build.gradle(.kts)`: the `name` and `url` of each `license { ... }`
+//! in the `licenses { ... }` block of a Maven publication, one per license as
+//! in a `pom.xml`. Build scripts without such a block are left alone.
+
+use super::{Field, alternatives, closing_quote, indent_before, is_declared, spdx_id_for_name};
+use std::ops::Range;
+
+pub(super) fn license(text: &str) -> Option<String> {
+    let names: Vec<String> = license_blocks(text, licenses_block(text)?)
+        .into_iter()
+        .filter_map(|block| value_range(text, block, "name"))
+        .map(|range| {
+            let name = &text[range];
+            spdx_id_for_name(name).map_or_else(|| name.to_string(), str::to_string)
+        })
+        .collect();
+    (!names.is_empty()).then(|| names.join(" OR "))
+}
+
+pub(super) fn set_license(text: &str, field: &Field) -> String {
+    let Some(block) = licenses_block(text) else {
+        return text.to_string();
+    };
+    let licenses: Vec<(String, Option<String>)> = match field {
+        Field::Expression(expression) if is_declared(license(text).as_deref(), expression) => {
+            return text.to_string();
+        }
+        Field::Expression(expression) => match alternatives(expression) {
+            Some(ids) => ids
+                .into_iter()
+                .map(|id| {
+                    let url = format!("https://spdx.org/licenses/{id}.html");
+                    (id, Some(url))
+                })
+                .collect(),
+            None => vec![(expression.to_string(), None)],
+        },
+        Field::File(file) => vec![(format!("SEE LICENSE IN {file}"), None)],
+    };
+    let blocks = statements(text, block);
+    let Some(first) = blocks.first().cloned() else {
+        return text.to_string();
+    };
+
+    // Existing blocks are edited in place or removed, and new ones copy the first.
+    let mut edits: Vec<(Range<usize>, String)> = Vec::new();
+    for (i, range) in blocks.iter().enumerate() {
+        let new = licenses
+            .get(i)
+            .map(|(name, url)| with_values(&text[range.clone()], name, url.as_deref()))
+            .unwrap_or_default();
+        edits.push((range.clone(), new));
+    }
+    let end = blocks.last().map_or(first.end, |range| range.end);
+    let separator = if text[..end].ends_with('\n') { "" } else { " " };
+    let added: String = licenses
+        .iter()
+        .skip(blocks.len())
+        .map(|(name, url)| {
+            let block = with_values(&text[first.clone()], name, url.as_deref());
+            format!("{separator}{block}")
+        })
+        .collect();
+    edits.push((end..end, added));
+
+    edits.sort_by_key(|(range, _)| std::cmp::Reverse(range.start));
+    let mut result = text.to_string();
+    for (range, value) in edits {
+        result.replace_range(range, &value);
+    }
+    result
+}
+
+/// `block`, the text of a `license { ... }`, with its name and url set. Without
+/// a url the existing one is removed.
+fn with_values(block: &str, name: &str, url: Option<&str>) -> String {
+    let mut edits: Vec<(Range<usize>, &str)> = Vec::new();
+    let inside = 0..block.len();
+    if let Some(range) = value_range(block, inside.clone(), "name") {
+        edits.push((range, name));
+    }
+    match url {
+        Some(url) => {
+            if let Some(range) = value_range(block, inside, "url") {
+                edits.push((range, url));
+            }
+        }
+        None => {
+            if let Some(range) = assignment_range(block, inside, "url") {
+                edits.push((range, ""));
+            }
+        }
+    }
+    edits.sort_by_key(|(range, _)| std::cmp::Reverse(range.start));
+    let mut result = block.to_string();
+    for (range, value) in edits {
+        result.replace_range(range, value);
+    }
+    result
+}
+
+/// Each `license { ... }` inside `block`, from the start of its line when
+/// nothing else precedes it there, through its newline when nothing follows.
+fn statements(text: &str, block: Range<usize>) -> Vec<Range<usize>> {
+    license_blocks(text, block)
+        .into_iter()
+        .filter_map(|inside| {
+            let keyword = text[..inside.start].rfind("license")?;
+            Some(line_range(text, keyword..inside.end + 1))
+        })
+        .collect()
+}
+
+/// The inside of each `license { ... }` directly in `block`.
+fn license_blocks(text: &str, block: Range<usize>) -> Vec<Range<usize>> {
+    let mut blocks = Vec::new();
+    let mut from = block.start;
+    while let Some(inside) = braced(text, from..block.end, "license") {
+        from = inside.end + 1;
+        blocks.push(inside);
+    }
+    blocks
+}
+
+/// The inside of the first `licenses { ... }` block.
+/// The inside of the first `licenses { ... }` block.
+fn licenses_block(text: &str) -> Option<Range<usize>> {
+    braced(text, 0..text.len(), "licenses")
+}
+
+/// The inside of the first `<keyword> { ... }` within `range`.
+fn braced(text: &str, range: Range<usize>, keyword: &str) -> Option<Range<usize>> {
+    let mut from = range.start;
+    while let Some(i) = text[from..range.end].find(keyword) {
+        let start = from + i;
+        from = start + keyword.len();
+        if !is_word_start(text, start) {
+            continue;
+        }
+        let rest = &text[from..];
+        let open = from + (rest.len() - rest.trim_start().len());
+        if text[open..].starts_with('{') {
+            let close = matching_brace(text, open)?;
+            return (close < range.end).then_some(open + 1..close);
+        }
+    }
+    None
+}
+
+/// The position of the `}` closing the `{` at `open`.
+fn matching_brace(text: &str, open: usize) -> Option<usize> {
+    let mut depth = 0;
+    let mut chars = text[open..].char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(open + i);
+                }
+            }
+            '"' | '\'' => {
+                let end = closing_quote(text, open + i + 1, c)?;
+                while chars.next().is_some_and(|(j, _)| open + j < end) {}
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// The string contents of the first `key = "..."`, `key '...'` or
+/// `key.set("...")` inside `block`.
+fn value_range(text: &str, block: Range<usize>, key: &str) -> Option<Range<usize>> {
+    key_value(text, block, key).map(|(_, value)| value)
+}
+
+/// The whole assignment whose value [`value_range`] finds, with its line when
+/// nothing else is on it.
+fn assignment_range(text: &str, block: Range<usize>, key: &str) -> Option<Range<usize>> {
+    let (start, value) = key_value(text, block, key)?;
+    let mut end = value.end + 1;
+    if text[end..].starts_with(')') {
+        end += 1;
+    }
+    Some(line_range(text, start..end))
+}
+
+/// `range` widened to its whole line, newline included, when the line holds
+/// nothing else.
+fn line_range(text: &str, range: Range<usize>) -> Range<usize> {
+    let indent = indent_before(text, range.start);
+    let line_start = range.start - indent.len();
+    let at_line_start = line_start == 0 || text[..line_start].ends_with('\n');
+    let rest = &text[range.end..];
+    let line_end = rest.find('\n').map_or(text.len(), |i| range.end + i + 1);
+    if at_line_start && text[range.end..line_end].trim().is_empty() {
+        line_start..line_end
+    } else {
+        range
+    }
+}
+
+/// The position of `key` and the string contents of its value, see [`value_range`].
+fn key_value(text: &str, block: Range<usize>, key: &str) -> Option<(usize, Range<usize>)> {
+    let mut from = block.start;
+    while let Some(i) = text[from..block.end].find(key) {
+        let start = from + i;
+        from = start + key.len();
+        if !is_word_start(text, start)
+            || text[from..]
+                .chars()
+                .next()
+                .is_some_and(|c| c.is_alphanumeric() || c == '_')
+        {
+            continue;
+        }
+        let mut rest = text[from..].trim_start();
+        for prefix in [".set(", "=", "("] {
+            if let Some(after) = rest.strip_prefix(prefix) {
+                rest = after.trim_start();
+                break;
+            }
+        }
+        let quote = rest.chars().next().filter(|c| matches!(c, '"' | '\''))?;
+        let open = text.len() - rest.len() + 1;
+        let close = closing_quote(text, open, quote)?;
+        return (close <= block.end).then_some((start, open..close));
+    }
+    None
+}
+
+fn is_word_start(text: &str, i: usize) -> bool {
+    !text[..i]
+        .chars()
+        .next_back()
+        .is_some_and(|c| c.is_alphanumeric() || c == '_' || c == '.')
+}
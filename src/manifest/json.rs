@@ -0,0 +1,155 @@
+//! Replacing or inserting one top-level member of a JSON document without
+//! reformatting the rest of it.
+
+use super::indent_before;
+use std::ops::Range;
+
+/// A top-level `"key": value` pair, by byte position.
+struct Member {
+    key: String,
+    /// From the key's opening quote.
+    start: usize,
+    value: Range<usize>,
+}
+
+pub(super) fn parse(text: &str) -> Result<serde_json::Value, String> {
+    serde_json::from_str(text).map_err(|e| e.to_string())
+}
+
+/// `text` with the top-level `key` set to `value` (already JSON). A new member
+/// goes after the first of `after` that exists, or first in the object.
+pub(super) fn set_member(
+    text: &str,
+    key: &str,
+    value: &str,
+    after: &[&str],
+) -> Result<String, String> {
+    if !parse(text)?.is_object() {
+        return Err("expected a JSON object".to_string());
+    }
+    let (open, members) = members(text);
+    let entry = format!("{}: {value}", serde_json::Value::from(key));
+    if let Some(member) = members.iter().find(|m| m.key == key) {
+        return Ok(splice(text, member.value.clone(), value));
+    }
+
+    let anchor = after
+        .iter()
+        .find_map(|name| members.iter().find(|m| m.key == *name));
+    Ok(match (anchor, members.first()) {
+        (Some(anchor), _) => {
+            let indent = indent_before(text, anchor.start);
+            let at = anchor.value.end;
+            splice(text, at..at, &format!(",\n{indent}{entry}"))
+        }
+        (None, Some(first)) => {
+            let indent = indent_before(text, first.start);
+            splice(
+                text,
+                first.start..first.start,
+                &format!("{entry},\n{indent}"),
+            )
+        }
+        (None, None) => {
+            let close = text[open..].find('}').map_or(text.len(), |i| open + i);
+            splice(text, open + 1..close, &format!("\n  {entry}\n"))
+        }
+    })
+}
+
+fn splice(text: &str, range: Range<usize>, replacement: &str) -> String {
+    format!(
+        "{}{replacement}{}",
+        &text[..range.start],
+        &text[range.end..]
+    )
+}
+
+/// The position of the top-level `{` and the members after it. Expects valid JSON.
+fn members(text: &str) -> (usize, Vec<Member>) {
+    let bytes = text.as_bytes();
+    let open = skip_ws(bytes, 0);
+    let mut members = Vec::new();
+    let mut i = open + 1;
+    loop {
+        i = skip_ws(bytes, i);
+        if bytes.get(i) != Some(&b'"') {
+            break;
+        }
+        let key_end = string_end(bytes, i);
+        let key = serde_json::from_str(&text[i..key_end]).unwrap_or_default();
+        let colon = skip_ws(bytes, key_end);
+        let value_start = skip_ws(bytes, colon + 1);
+        let value_end = value_end(bytes, value_start);
+        members.push(Member {
+            key,
+            start: i,
+            value: value_start..value_end,
+        });
+        i = skip_ws(bytes, value_end);
+        if bytes.get(i) != Some(&b',') {
+            break;
+        }
+        i += 1;
+    }
+    (open, members)
+}
+
+fn skip_ws(bytes: &[u8], mut i: usize) -> usize {
+    while bytes.get(i).is_some_and(u8::is_ascii_whitespace) {
+        i += 1;
+    }
+    i
+}
+
+/// The position after the string starting with the quote at `start`.
+fn string_end(bytes: &[u8], start: usize) -> usize {
+    let mut i = start + 1;
+    while let Some(&b) = bytes.get(i) {
+        match b {
+            b'\\' => i += 2,
+            b'"' => return i + 1,
+            _ => i += 1,
+        }
+    }
+    bytes.len()
+}
+
+/// The position after the value starting at `start`.
+fn value_end(bytes: &[u8], start: usize) -> usize {
+    match bytes.get(start) {
+        Some(b'"') => string_end(bytes, start),
+        Some(b'{' | b'[') => {
+            let mut depth = 0;
+            let mut i = start;
+            while let Some(&b) = bytes.get(i) {
+                match b {
+                    b'"' => {
+                        i = string_end(bytes, i);
+                        continue;
+                    }
+                    b'{' | b'[' => depth += 1,
+                    b'}' | b']' => {
+                        depth -= 1;
+                        if depth == 0 {
+                            return i + 1;
+                        }
+                    }
+                    _ => {}
+                }
+                i += 1;
+            }
+            bytes.len()
+        }
+        _ => {
+            let mut i = start;
+            while bytes
+                .get(i)
+                .is_some_and(|b| !b.is_ascii_whitespace() && !matches!(b, b',' | b'}' | b']'))
+            {
+                i += 1;
+            }
+            i
+        }
+    }
+}
@@ -0,0 +1,95 @@
+//! Maven `pom.xml`: one `<license>` per license in `<licenses>`. Entries for a
+//! license that stays keep their other elements, such as `<distribution>`.
+
+use super::{Field, alternatives, indent_before, is_declared, spdx_id_for_name, xml};
+use roxmltree::Node;
+
+pub(super) fn license(text: &str) -> Result<Option<String>, String> {
+    let doc = xml::parse(text)?;
+    let Some(licenses) = xml::child(doc.root_element(), "licenses") else {
+        return Ok(None);
+    };
+    let names: Vec<String> = entries(licenses).filter_map(license_id).collect();
+    Ok((!names.is_empty()).then(|| names.join(" OR ")))
+}
+
+fn entries<'a, 'input>(licenses: Node<'a, 'input>) -> impl Iterator<Item = Node<'a, 'input>> {
+    licenses
+        .children()
+        .filter(|c| c.is_element() && c.tag_name().name() == "license")
+}
+
+/// The SPDX id for the `<name>` of a `<license>`, or the name itself.
+fn license_id(license: Node) -> Option<String> {
+    let name = xml::text(xml::child(license, "name")?)?;
+    Some(spdx_id_for_name(name).map_or_else(|| name.to_string(), str::to_string))
+}
+
+pub(super) fn set_license(text: &str, field: &Field) -> Result<String, String> {
+    if let Field::Expression(expression) = field
+        && is_declared(license(text)?.as_deref(), expression)
+    {
+        return Ok(text.to_string());
+    }
+    let licenses: Vec<(String, Option<String>)> = match field {
+        Field::Expression(expression) => match alternatives(expression) {
+            Some(ids) => ids
+                .into_iter()
+                .map(|id| {
+                    let url = format!("https://spdx.org/licenses/{id}.html");
+                    (id, Some(url))
+                })
+                .collect(),
+            None => vec![(expression.to_string(), None)],
+        },
+        Field::File(file) => vec![(format!("SEE LICENSE IN {file}"), None)],
+    };
+
+    let doc = xml::parse(text)?;
+    let project = doc.root_element();
+    let existing = xml::child(project, "licenses");
+    let (indent, unit) = xml::child_indent(text, project);
+    let base = existing.map_or(indent, |node| {
+        indent_before(text, node.range().start).to_string()
+    });
+    let kept: Vec<Node> = existing.map_or_else(Vec::new, |node| entries(node).collect());
+    let mut block = "<licenses>".to_string();
+    for (name, url) in licenses {
+        let same = kept
+            .iter()
+            .find(|license| license_id(**license).as_deref() == Some(name.as_str()));
+        if let Some(license) = same {
+            block.push_str(&format!("\n{base}{unit}{}", &text[license.range()]));
+            continue;
+        }
+        block.push_str(&format!("\n{base}{unit}<license>"));
+        block.push_str(&format!(
+            "\n{base}{unit}{unit}<name>{}</name>",
+            xml::escape(&name)
+        ));
+        if let Some(url) = url {
+            block.push_str(&format!("\n{base}{unit}{unit}<url>{url}</url>"));
+        }
+        block.push_str(&format!("\n{base}{unit}</license>"));
+    }
+    block.push_str(&format!("\n{base}</licenses>"));
+
+    Ok(match existing {
+        Some(node) => xml::replace(text, node, &block),
+        None => xml::insert(
+            text,
+            project,
+            &[
+                "artifactId",
+                "version",
+                "packaging",
+                "name",
+                "description",
+                "url",
+                "inceptionYear",
+                "organization",
+            ],
+            &block,
+        ),
+    })
+}
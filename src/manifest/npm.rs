@@ -0,0 +1,24 @@
+//! `package.json`: the `license` field, or `SEE LICENSE IN <file>` for a
+//! license that is not on the SPDX License List.
+
+use super::{Field, json};
+
+pub(super) fn license(text: &str) -> Result<Option<String>, String> {
+    Ok(json::parse(text)?["license"].as_str().map(str::to_string))
+}
+
+pub(super) fn set_license(text: &str, field: &Field) -> Result<String, String> {
+    let value = match field {
+        Field::Expression(expression) => expression.to_string(),
+        Field::File(file) => format!("SEE LICENSE IN {file}"),
+    };
+    if json::parse(text)?["license"].as_str() == Some(value.as_str()) {
+        return Ok(text.to_string());
+    }
+    json::set_member(
+        text,
+        "license",
+        &serde_json::Value::from(value).to_string(),
+        &["version", "description", "name"],
+    )
+}
@@ -0,0 +1,58 @@
+//! `pyproject.toml`: PEP 639 `project.license` and `project.license-files`,
+//! or `tool.poetry.license` for Poetry projects.
+
+use super::{Field, parse_toml, set_toml_string};
+use toml_edit::{Array, Item};
+
+pub(super) fn license(text: &str) -> Result<Option<String>, String> {
+    let doc = parse_toml(text)?;
+    let license = match doc.get("project") {
+        Some(project) => project.get("license").and_then(|license| {
+            // The pre-PEP 639 table form, `license = { text = "MIT" }`.
+            license.as_str().or_else(|| license.get("text")?.as_str())
+        }),
+        None => doc
+            .get("tool")
+            .and_then(|t| t.get("poetry"))
+            .and_then(|p| p.get("license"))
+            .and_then(Item::as_str),
+    };
+    Ok(license.map(str::to_string))
+}
+
+pub(super) fn set_license(text: &str, field: &Field) -> Result<String, String> {
+    let mut doc = parse_toml(text)?;
+    if let Some(project) = doc.get_mut("project").and_then(Item::as_table_like_mut) {
+        match field {
+            Field::Expression(expression) => {
+                set_toml_string(project, "license", expression);
+                // PEP 639 forbids license classifiers next to an expression.
+                if let Some(classifiers) =
+                    project.get_mut("classifiers").and_then(Item::as_array_mut)
+                {
+                    classifiers
+                        .retain(|c| !c.as_str().is_some_and(|c| c.starts_with("License ::")));
+                }
+            }
+            Field::File(file) => {
+                project.remove("license");
+                let files = project
+                    .entry("license-files")
+                    .or_insert(toml_edit::value(Array::new()));
+                if let Some(files) = files.as_array_mut()
+                    && !files.iter().any(|f| f.as_str() == Some(file))
+                {
+                    files.push(*file);
+                }
+            }
+        }
+    } else if let Field::Expression(expression) = field
+        && let Some(poetry) = doc
+            .get_mut("tool")
+            .and_then(|t| t.get_mut("poetry"))
+            .and_then(Item::as_table_like_mut)
+    {
+        set_toml_string(poetry, "license", expression);
+    }
+    Ok(doc.to_string())
+}
@@ -0,0 +1,83 @@
+//! Editing XML manifests by byte range, so that everything else stays as written.
+
+use super::indent_before;
+use roxmltree::{Document, Node};
+
+pub(super) fn parse(text: &str) -> Result<Document<'_>, String> {
+    Document::parse(text).map_err(|e| e.to_string())
+}
+
+/// The first child element of `node` called `name`, ignoring namespaces.
+pub(super) fn child<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    node.children()
+        .find(|c| c.is_element() && c.tag_name().name() == name)
+}
+
+/// The first element under `node` called `name`, ignoring namespaces.
+pub(super) fn descendant<'a, 'input>(
+    node: Node<'a, 'input>,
+    name: &str,
+) -> Option<Node<'a, 'input>> {
+    node.descendants()
+        .find(|c| c.is_element() && c.tag_name().name() == name)
+}
+
+/// The trimmed text content of `node`.
+pub(super) fn text<'a>(node: Node<'a, '_>) -> Option<&'a str> {
+    node.text().map(str::trim).filter(|t| !t.is_empty())
+}
+
+pub(super) fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// `text` with the element `node` replaced by `new`.
+pub(super) fn replace(text: &str, node: Node, new: &str) -> String {
+    let range = node.range();
+    format!("{}{new}{}", &text[..range.start], &text[range.end..])
+}
+
+/// The indentation of `parent`'s children, and one level of indentation.
+pub(super) fn child_indent(text: &str, parent: Node) -> (String, String) {
+    let parent_indent = indent_before(text, parent.range().start);
+    let child = parent.children().find(|c| c.is_element());
+    match child.map(|c| indent_before(text, c.range().start)) {
+        Some(indent) if indent.len() > parent_indent.len() => (
+            indent.to_string(),
+            indent[parent_indent.len()..].to_string(),
+        ),
+        _ => (format!("{parent_indent}  "), "  ".to_string()),
+    }
+}
+
+/// `text` with `new` added to `parent`, after the last of its children named
+/// in `after`, or after its last child element. Lines after the first of
+/// `new` must already be indented.
+pub(super) fn insert(text: &str, parent: Node, after: &[&str], new: &str) -> String {
+    let (indent, _) = child_indent(text, parent);
+    let elements = || parent.children().filter(|c| c.is_element());
+    let anchor = elements()
+        .rfind(|c| after.contains(&c.tag_name().name()))
+        .or_else(|| elements().next_back());
+    if let Some(anchor) = anchor {
+        let at = anchor.range().end;
+        return format!("{}\n{indent}{new}{}", &text[..at], &text[at..]);
+    }
+
+    // An empty element: replace whatever whitespace is between its tags.
+    let range = parent.range();
+    let element = &text[range.clone()];
+    let (Some(open_end), Some(close)) = (element.find('>'), element.rfind("</")) else {
+        return text.to_string();
+    };
+    let parent_indent = indent_before(text, range.start);
+    format!(
+        "{}\n{indent}{new}\n{parent_indent}{}",
+        &text[..range.start + open_end + 1],
+        &text[range.start + close..]
+    )
+}
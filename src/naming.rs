@@ -0,0 +1,227 @@
+//! File name conventions for license files.
+
+use crate::{Error, Result};
+use std::path::{Path, PathBuf};
+
+/// How to name the file a license is written to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FileNaming {
+    /// `LICENSE`
+    #[default]
+    License,
+    /// `LICENSE.md`
+    Markdown,
+    /// `LICENSE.txt`
+    Text,
+    /// `COPYING` for GPL/AGPL, `COPYING.LESSER` for LGPL, `LICENSE` otherwise
+    Gnu,
+    /// `LICENSE-MIT`, `LICENSE-APACHE`, ... as used for dual licensing
+    Suffixed,
+    /// `LICENSES/MIT.txt`, `LICENSES/Apache-2.0.txt`, ... as the REUSE specification requires
+    Reuse,
+}
+
+impl FileNaming {
+    pub fn file_name(self, spdx_id: &str) -> String {
+        let id = spdx_id.to_ascii_uppercase();
+        match self {
+            FileNaming::License => "LICENSE".to_string(),
+            FileNaming::Markdown => "LICENSE.md".to_string(),
+            FileNaming::Text => "LICENSE.txt".to_string(),
+            FileNaming::Gnu if id.starts_with("LGPL-") => "COPYING.LESSER".to_string(),
+            FileNaming::Gnu if id.starts_with("GPL-") || id.starts_with("AGPL-") => {
+                "COPYING".to_string()
+            }
+            FileNaming::Gnu => "LICENSE".to_string(),
+            FileNaming::Suffixed => format!("LICENSE-{}", suffix(&id)),
+            FileNaming::Reuse => format!("{}/{spdx_id}.txt", crate::reuse::LICENSES_DIR),
+        }
+    }
+}
+
+/// Where the licenses with `spdx_ids` are written: `output` itself for a
+/// single license unless it is a directory, otherwise files named by `naming`
+/// in `output` or, without one, in `root`. Several licenses are named
+/// `LICENSE-<ID>` when `naming` would give two of them the same file name.
+pub fn target_paths(
+    naming: FileNaming,
+    output: Option<&Path>,
+    root: &Path,
+    spdx_ids: &[&str],
+) -> Result<Vec<PathBuf>> {
+    let dir = match output {
+        Some(path) if path.is_dir() => path,
+        Some(path) if spdx_ids.len() == 1 => return Ok(vec![path.to_path_buf()]),
+        Some(path) => {
+            return Err(Error::NotADirectory {
+                path: path.to_path_buf(),
+                count: spdx_ids.len(),
+            });
+        }
+        None => root,
+    };
+
+    let names = |naming: FileNaming| -> Vec<String> {
+        spdx_ids.iter().map(|id| naming.file_name(id)).collect()
+    };
+    let has_duplicates = |names: &[String]| {
+        names
+            .iter()
+            .enumerate()
+            .any(|(i, name)| names[..i].contains(name))
+    };
+    let mut file_names = names(naming);
+    if has_duplicates(&file_names) {
+        file_names = names(FileNaming::Suffixed);
+    }
+    if has_duplicates(&file_names) {
+        return Err(Error::SameFileName(file_names));
+    }
+    Ok(file_names.into_iter().map(|name| dir.join(name)).collect())
+}
+
+/// `APACHE` for every Apache version, otherwise the upper-cased SPDX id
+/// without the GNU `-ONLY`/`-OR-LATER` qualifiers.
+fn suffix(id: &str) -> &str {
+    if id.starts_with("APACHE-") {
+        return "APACHE";
+    }
+    id.trim_end_matches("-ONLY")
+        .trim_end_matches("-OR-LATER")
+        .trim_end_matches('+')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_names_per_scheme() {
+        let cases = [
+            (FileNaming::License, "MIT", "LICENSE"),
+            (FileNaming::Markdown, "MIT", "LICENSE.md"),
+            (FileNaming::Text, "MIT", "LICENSE.txt"),
+            (FileNaming::Gnu, "GPL-3.0-or-later", "COPYING"),
+            (FileNaming::Gnu, "AGPL-3.0-only", "COPYING"),
+            (FileNaming::Gnu, "LGPL-2.1-only", "COPYING.LESSER"),
+            (FileNaming::Gnu, "MIT", "LICENSE"),
+            (FileNaming::Suffixed, "MIT", "LICENSE-MIT"),
+            (FileNaming::Suffixed, "Apache-2.0", "LICENSE-APACHE"),
+            (FileNaming::Suffixed, "GPL-2.0-or-later", "LICENSE-GPL-2.0"),
+            (FileNaming::Suffixed, "LGPL-3.0-only", "LICENSE-LGPL-3.0"),
+            (FileNaming::Suffixed, "BSD-3-Clause", "LICENSE-BSD-3-CLAUSE"),
+            (FileNaming::Reuse, "Apache-2.0", "LICENSES/Apache-2.0.txt"),
+            (
+                FileNaming::Reuse,
+                "LicenseRef-Custom",
+                "LICENSES/LicenseRef-Custom.txt",
+            ),
+        ];
+        for (naming, id, expected) in cases {
+            assert_eq!(naming.file_name(id), expected, "{naming:?} {id}");
+        }
+    }
+
+    #[test]
+    fn single_license_targets() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let out = root.join("out");
+        std::fs::create_dir(&out).unwrap();
+        let file = root.join("docs/LICENSE.txt");
+
+        let cases: [(FileNaming, Option<&Path>, PathBuf); 5] = [
+            (FileNaming::License, None, root.join("LICENSE")),
+            (FileNaming::Gnu, None, root.join("COPYING")),
+            (FileNaming::Markdown, Some(&out), out.join("LICENSE.md")),
+            (
+                FileNaming::Reuse,
+                Some(&out),
+                out.join("LICENSES/GPL-3.0-only.txt"),
+            ),
+            (FileNaming::Suffixed, Some(&file), file.clone()),
+        ];
+        for (naming, output, expected) in cases {
+            let paths = target_paths(naming, output, root, &["GPL-3.0-only"]).unwrap();
+            assert_eq!(paths, [expected], "{naming:?} {output:?}");
+        }
+    }
+
+    #[test]
+    fn multi_license_targets() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let cases: [(FileNaming, &[&str], &[&str]); 6] = [
+            (
+                FileNaming::Suffixed,
+                &["MIT", "Apache-2.0"],
+                &["LICENSE-MIT", "LICENSE-APACHE"],
+            ),
+            // Names that would collide fall back to suffixes.
+            (
+                FileNaming::License,
+                &["MIT", "Apache-2.0"],
+                &["LICENSE-MIT", "LICENSE-APACHE"],
+            ),
+            (
+                FileNaming::Gnu,
+                &["GPL-3.0-only", "MIT"],
+                &["COPYING", "LICENSE"],
+            ),
+            (
+                FileNaming::Gnu,
+                &["GPL-3.0-only", "AGPL-3.0-only"],
+                &["LICENSE-GPL-3.0", "LICENSE-AGPL-3.0"],
+            ),
+            (
+                FileNaming::Reuse,
+                &["MIT", "Apache-2.0"],
+                &["LICENSES/MIT.txt", "LICENSES/Apache-2.0.txt"],
+            ),
+            (
+                FileNaming::Reuse,
+                &["GPL-2.0-only", "GPL-2.0-or-later"],
+                &["LICENSES/GPL-2.0-only.txt", "LICENSES/GPL-2.0-or-later.txt"],
+            ),
+        ];
+        for (naming, ids, expected) in cases {
+            let paths = target_paths(naming, None, root, ids).unwrap();
+            let expected: Vec<_> = expected.iter().map(|name| root.join(name)).collect();
+            assert_eq!(paths, expected, "{naming:?} {ids:?}");
+        }
+    }
+
+    #[test]
+    fn multi_license_targets_need_distinct_names_and_a_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        assert!(matches!(
+            target_paths(
+                FileNaming::Suffixed,
+                None,
+                root,
+                &["Apache-2.0", "Apache-1.1"]
+            ),
+            Err(Error::SameFileName(_))
+        ));
+        assert!(matches!(
+            target_paths(
+                FileNaming::License,
+                None,
+                root,
+                &["GPL-2.0-only", "GPL-2.0-or-later"]
+            ),
+            Err(Error::SameFileName(_))
+        ));
+        let file = root.join("LICENSE");
+        assert!(matches!(
+            target_paths(
+                FileNaming::License,
+                Some(&file),
+                root,
+                &["MIT", "Apache-2.0"]
+            ),
+            Err(Error::NotADirectory { count: 2, .. })
+        ));
+    }
+}
@@ -0,0 +1,1269 @@
+//! Third-party notices: the license and NOTICE files shipped by each
+//! dependency, de-duplicated into one bundle for distribution with binaries.
+//!
+//! Apache-2.0 section 4(d) also asks redistributions to carry the NOTICE
+//! files of Apache-licensed dependencies, which [`render_notice`] collects
+//! into the project's own `NOTICE`.
+//!
+//! HTML bundles are rendered from a template in a subset of Handlebars:
+//!
+//! - `{{path}}` inserts a value, HTML-escaped for HTML output, and `{{{path}}}`
+//!   inserts it as is. A path is dotted keys such as `license.name`, looked up
+//!   from the innermost `#each` item outwards, `this` for that item, or `../`
+//!   for the item around it. Missing values and `null` insert nothing; lists
+//!   and objects are errors.
+//! - `{{#each path}}...{{else}}...{{/each}}` repeats for every item of a list
+//!   or object, with `@index`, `@key`, `@first` and `@last`; `{{else}}` renders
+//!   when there are none.
+//! - `{{#if path}}...{{else}}...{{/if}}` and `{{#unless path}}`, where missing
+//!   values, `false`, `null`, `0`, `""` and `[]` are false.
+//! - `{{! comment }}`, `{{!-- comment --}}` and `~` whitespace control.
+//!
+//! Helpers, partials and `{{#with}}` are not supported; unknown blocks are
+//! errors.
+
+use crate::deps::{Dependency, LicenseStatus};
+use crate::expression::{self, Expression};
+use crate::{copyright, detect};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The default template for `--format html`.
+pub const HTML_TEMPLATE: &str = r##"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>Third-party licenses</title>
+<style>
+body { font-family: sans-serif; max-width: 60em; margin: 2em auto; padding: 0 1em; }
+pre { white-space: pre-wrap; background: #f6f8fa; padding: 1em; }
+</style>
+</head>
+<body>
+<h1>Third-party licenses</h1>
+<p>This software includes the following third-party components.</p>
+<h2>Overview</h2>
+<ul>
+{{#each overview}}<li><a href="#{{anchor}}">{{name}}</a> ({{count}})</li>
+{{/each}}</ul>
+{{#each licenses}}<h2 id="{{anchor}}">{{name}}</h2>
+<p>Used by: {{#each used_by}}{{#if @index}}, {{/if}}{{name}} {{version}}{{/each}}</p>
+<pre>{{text}}</pre>
+{{/each}}{{#if missing}}<h2>No license text found</h2>
+<ul>
+{{#each missing}}<li>{{name}} {{version}}</li>
+{{/each}}</ul>
+{{/if}}</body>
+</html>
+"##;
+
+/// The project NOTICE file, in the project root.
+pub const NOTICE_FILE: &str = "NOTICE";
+
+/// The line that separates the project's own notice from those of its dependencies.
+const DEPENDENCIES_INTRO: &str =
+    "This product includes third-party software with the following notices.";
+
+/// File names, compared case-insensitively, that hold license texts or notices.
+const PREFIXES: &[&str] = &[
+    "LICENSE",
+    "LICENCE",
+    "COPYING",
+    "COPYRIGHT",
+    "NOTICE",
+    "UNLICENSE",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Package {
+    pub ecosystem: &'static str,
+    pub name: String,
+    pub version: String,
+}
+
+impl Package {
+    fn of(dependency: &Dependency) -> Self {
+        Package {
+            ecosystem: dependency.ecosystem.name(),
+            name: dependency.name.clone(),
+            version: dependency.version.clone(),
+        }
+    }
+}
+
+/// One distinct license or NOTICE text and the packages that ship it.
+#[derive(Debug, Serialize)]
+pub struct LicenseText {
+    /// The SPDX id of the recognized license; `None` for NOTICE files and
+    /// license files that match no declared license.
+    pub id: Option<String>,
+    /// The license's full name, else the file and package it came from.
+    pub name: String,
+    /// A NOTICE file rather than a license.
+    pub notice: bool,
+    /// Unique within the bundle, for links.
+    pub anchor: String,
+    pub text: String,
+    pub used_by: Vec<Package>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct Bundle {
+    pub licenses: Vec<LicenseText>,
+    /// Dependencies that ship no license file and declare no SPDX license.
+    pub missing: Vec<Package>,
+}
+
+/// A text found for a dependency, before de-duplication.
+struct Found {
+    /// The SPDX id when the text came from the SPDX License List.
+    id: Option<String>,
+    file_name: String,
+    text: String,
+}
+
+/// Gathers the texts each dependency ships, falling back to the SPDX License
+/// List text of its declared licenses when it ships none.
+pub fn bundle(dependencies: &[Dependency]) -> Bundle {
+    let mut bundle = Bundle::default();
+    let mut keys: Vec<String> = Vec::new();
+    for dependency in dependencies {
+        let package = Package::of(dependency);
+        let mut found = dependency
+            .path
+            .as_deref()
+            .map(|dir| shipped_texts(dir, dependency.license_file.as_deref()))
+            .unwrap_or_default();
+        if found.is_empty() {
+            found = spdx_texts(dependency);
+        }
+        if found.is_empty() {
+            bundle.missing.push(package);
+            continue;
+        }
+        for found in found {
+            let key = normalize(&found.text);
+            if let Some(i) = keys.iter().position(|k| *k == key) {
+                if !bundle.licenses[i].used_by.contains(&package) {
+                    bundle.licenses[i].used_by.push(package.clone());
+                }
+                continue;
+            }
+            let notice = found.file_name.to_uppercase().starts_with("NOTICE");
+            let id = match found.id {
+                Some(id) => Some(id),
+                None if notice => None,
+                None => recognize(&key, dependency),
+            };
+            let name = match &id {
+                Some(id) => spdx::license_id(id)
+                    .map(|l| l.full_name.to_string())
+                    .unwrap_or_else(|| id.clone()),
+                None => format!("{} of {}", found.file_name, dependency.name),
+            };
+            keys.push(key);
+            bundle.licenses.push(LicenseText {
+                id,
+                name,
+                notice,
+                anchor: String::new(),
+                text: found.text.trim_end().to_string(),
+                used_by: vec![package.clone()],
+            });
+        }
+    }
+
+    // Recognized licenses first, then other license files, then NOTICE files.
+    bundle.licenses.sort_by(|a, b| {
+        let key = |l: &LicenseText| {
+            (
+                l.notice,
+                l.id.is_none(),
+                l.id.clone(),
+                l.used_by[0].name.clone(),
+            )
+        };
+        key(a).cmp(&key(b))
+    });
+    let mut anchors: Vec<String> = Vec::new();
+    for license in &mut bundle.licenses {
+        let slug: String = match (&license.id, license.notice) {
+            (Some(id), _) => id
+                .chars()
+                .map(|c| {
+                    if c.is_ascii_alphanumeric() {
+                        c.to_ascii_lowercase()
+                    } else {
+                        '-'
+                    }
+                })
+                .collect(),
+            (None, false) => "other".to_string(),
+            (None, true) => "notice".to_string(),
+        };
+        let count = anchors.iter().filter(|a| a.as_str() == slug).count();
+        license.anchor = match count {
+            0 => slug.clone(),
+            n => format!("{slug}-{}", n + 1),
+        };
+        anchors.push(slug);
+    }
+    bundle
+}
+
+/// The SPDX id of the license in `text`: the declared license it matches, or
+/// for dependencies that declare none, whatever the text is closest to.
+fn recognize(text: &str, dependency: &Dependency) -> Option<String> {
+    let declared = dependency
+        .license
+        .as_deref()
+        .and_then(|license| expression::parse(license).ok())
+        .map(|parsed| parsed.license_ids())
+        .unwrap_or_default();
+    if declared.is_empty() {
+        return detect::detect(text)
+            .filter(|d| d.is_confident())
+            .map(|d| d.spdx_id);
+    }
+    declared
+        .into_iter()
+        .filter_map(|id| Some((detect::similarity(text, &id)?, id)))
+        .filter(|(score, _)| *score >= detect::MIN_CONFIDENCE)
+        .max_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, id)| id)
+}
+
+/// The license and NOTICE files at the top of a package's sources, and in
+/// its REUSE `LICENSES/` directory.
+fn shipped_texts(dir: &Path, license_file: Option<&Path>) -> Vec<Found> {
+    let mut paths: Vec<PathBuf> = Vec::new();
+    for dir in [dir.to_path_buf(), dir.join("LICENSES")] {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_uppercase();
+            let licenses_dir = dir.ends_with("LICENSES");
+            if entry.path().is_file()
+                && (licenses_dir || PREFIXES.iter().any(|p| name.starts_with(p)))
+            {
+                paths.push(entry.path());
+            }
+        }
+    }
+    if let Some(file) = license_file.map(|f| dir.join(f))
+        && file.is_file()
+        && !paths.contains(&file)
+    {
+        paths.push(file);
+    }
+    paths.sort();
+    paths
+        .into_iter()
+        .filter_map(|path| {
+            let text = fs::read_to_string(&path).ok()?;
+            let file_name = path.file_name()?.to_string_lossy().to_string();
+            (!text.trim().is_empty()).then_some(Found {
+                id: None,
+                file_name,
+                text,
+            })
+        })
+        .collect()
+}
+
+/// The SPDX License List texts of every license and exception `dependency` declares.
+fn spdx_texts(dependency: &Dependency) -> Vec<Found> {
+    let Some(license) = dependency
+        .license
+        .as_deref()
+        .filter(|_| dependency.status == LicenseStatus::Spdx)
+    else {
+        return Vec::new();
+    };
+    let Ok(parsed) = expression::parse(license) else {
+        return Vec::new();
+    };
+    let mut found = Vec::new();
+    for term in parsed.terms() {
+        let id = term.id.trim_end_matches('+');
+        if let Some(license) = spdx::license_id(id) {
+            found.push(Found {
+                id: Some(license.name.to_string()),
+                file_name: license.name.to_string(),
+                text: license.text().to_string(),
+            });
+        }
+        if let Some(exception) = term.exception.as_deref().and_then(spdx::exception_id) {
+            found.push(Found {
+                id: Some(exception.name.to_string()),
+                file_name: exception.name.to_string(),
+                text: exception.text().to_string(),
+            });
+        }
+    }
+    found
+}
+
+/// The text with line endings and trailing whitespace normalized, so that
+/// copies that differ only in those compare equal.
+fn normalize(text: &str) -> String {
+    let lines: Vec<&str> = text.lines().map(str::trim_end).collect();
+    lines.join("\n").trim().to_string()
+}
+
+/// A NOTICE text shipped by Apache-2.0 licensed dependencies.
+#[derive(Debug, Serialize)]
+pub struct DependencyNotice {
+    pub used_by: Vec<Package>,
+    pub text: String,
+}
+
+impl DependencyNotice {
+    /// The section heading in the project NOTICE.
+    fn heading(&self) -> String {
+        format!("=== {} ===", used_by(&self.used_by))
+    }
+}
+
+/// Whether `expression` cannot be used without Apache-2.0, so that its NOTICE
+/// rules apply: every alternative of an `OR` needs it, or any part of an `AND`.
+/// `MIT OR Apache-2.0` does not, since MIT can be chosen.
+pub fn requires_apache(expression: &Expression) -> bool {
+    match expression {
+        Expression::License(term) => term.id == "Apache-2.0",
+        Expression::And(terms) => terms.iter().any(requires_apache),
+        Expression::Or(terms) => terms.iter().all(requires_apache),
+    }
+}
+
+/// Whether any term of `expression` is Apache-2.0, so that `lic init` adds a
+/// NOTICE: also for `MIT OR Apache-2.0`, where users may choose Apache-2.0.
+pub fn includes_apache(expression: &Expression) -> bool {
+    expression
+        .terms()
+        .iter()
+        .any(|term| term.id == "Apache-2.0")
+}
+
+/// [`requires_apache`] for a dependency's declared license.
+fn license_requires_apache(license: &str) -> bool {
+    expression::parse(license).is_ok_and(|parsed| requires_apache(&parsed.normalize()))
+}
+
+/// The NOTICE files of the dependencies that can only be used under
+/// Apache-2.0, with identical texts merged.
+pub fn apache_notices(dependencies: &[Dependency]) -> Vec<DependencyNotice> {
+    let mut notices: Vec<DependencyNotice> = Vec::new();
+    for dependency in dependencies {
+        let (Some(license), Some(dir)) = (&dependency.license, &dependency.path) else {
+            continue;
+        };
+        if !license_requires_apache(license) {
+            continue;
+        }
+        let found = shipped_texts(dir, None)
+            .into_iter()
+            .filter(|f| f.file_name.to_uppercase().starts_with("NOTICE"));
+        for found in found {
+            let text = normalize(&found.text);
+            let package = Package::of(dependency);
+            match notices.iter_mut().find(|n| n.text == text) {
+                Some(notice) => notice.used_by.push(package),
+                None => notices.push(DependencyNotice {
+                    used_by: vec![package],
+                    text,
+                }),
+            }
+        }
+    }
+    notices
+}
+
+/// The project NOTICE: `header` (the project's own notice) followed by the
+/// notices of its dependencies.
+pub fn render_notice(header: &str, notices: &[DependencyNotice]) -> String {
+    let mut out = format!("{}\n", header.trim_end());
+    if !notices.is_empty() {
+        out.push_str(&format!("\n{DEPENDENCIES_INTRO}\n"));
+    }
+    for notice in notices {
+        out.push_str(&format!("\n{}\n\n{}\n", notice.heading(), notice.text));
+    }
+    out
+}
+
+/// The project's own notice in an existing NOTICE file, without the
+/// dependency notices [`render_notice`] appended.
+pub fn notice_header(text: &str) -> &str {
+    match text.find(DEPENDENCIES_INTRO) {
+        Some(index) => text[..index].trim_end(),
+        None => text.trim_end(),
+    }
+}
+
+/// Why a project NOTICE is not up to date.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NoticeProblem {
+    Missing,
+    /// No `Copyright` line in the project's own notice.
+    NoCopyright,
+    /// The latest copyright year, older than the expected one.
+    StaleYear(u32),
+    /// A dependency NOTICE that is not reproduced, by the packages that ship it.
+    MissingDependency(String),
+    /// A dependency section for packages that no longer ship that notice.
+    StaleDependency(String),
+}
+
+impl fmt::Display for NoticeProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NoticeProblem::Missing => write!(f, "{NOTICE_FILE} is missing"),
+            NoticeProblem::NoCopyright => write!(f, "{NOTICE_FILE} has no copyright notice"),
+            NoticeProblem::StaleYear(year) => {
+                write!(f, "the copyright notice ends in {year}")
+            }
+            NoticeProblem::MissingDependency(packages) => {
+                write!(f, "the NOTICE of {packages} is not included")
+            }
+            NoticeProblem::StaleDependency(packages) => write!(
+                f,
+                "the notice for {packages} does not match a current dependency"
+            ),
+        }
+    }
+}
+
+/// Checks an existing project NOTICE (`None` when there is none) against the
+/// current dependency notices and, for Apache-2.0 projects, the copyright `year`.
+pub fn check_notice(
+    text: Option<&str>,
+    notices: &[DependencyNotice],
+    year: Option<u32>,
+) -> Vec<NoticeProblem> {
+    let Some(text) = text else {
+        return vec![NoticeProblem::Missing];
+    };
+    let mut problems = Vec::new();
+    let latest = detect::copyright_notices(notice_header(text))
+        .iter()
+        .flat_map(|(_, notice)| copyright::years_in(&notice.years))
+        .map(|(_, _, year)| year)
+        .max();
+    match (latest, year) {
+        (_, None) => {}
+        (None, Some(_)) => problems.push(NoticeProblem::NoCopyright),
+        (Some(latest), Some(year)) if latest < year => {
+            problems.push(NoticeProblem::StaleYear(latest))
+        }
+        _ => {}
+    }
+    let normalized = normalize(text);
+    for notice in notices {
+        if !normalized.contains(&notice.text) {
+            problems.push(NoticeProblem::MissingDependency(used_by(&notice.used_by)));
+        }
+    }
+    let headings: Vec<String> = notices.iter().map(DependencyNotice::heading).collect();
+    for line in normalized.lines() {
+        if let Some(packages) = line
+            .strip_prefix("=== ")
+            .and_then(|l| l.strip_suffix(" ==="))
+            && !headings.iter().any(|h| h == line)
+        {
+            problems.push(NoticeProblem::StaleDependency(packages.to_string()));
+        }
+    }
+    problems
+}
+
+/// A new NOTICE for the project in `dir`: its name, a copyright line for
+/// `author` and the notices of its Apache-2.0 `dependencies`.
+pub fn new_notice(dir: &Path, dependencies: &[Dependency], author: &str, year: &str) -> String {
+    render_notice(
+        &format!("{}\nCopyright {year} {author}\n", crate::project_name(dir)),
+        &apache_notices(dependencies),
+    )
+}
+
+/// The project NOTICE in `dir`, as it is and as it should be.
+#[derive(Debug)]
+pub struct NoticeUpdate {
+    pub path: PathBuf,
+    /// The current text, empty when there is no NOTICE yet.
+    pub old: String,
+    pub new: String,
+    /// How many dependencies the reproduced notices come from.
+    pub dependencies: usize,
+}
+
+/// The project NOTICE in `dir` with `author`'s copyright extended to `year`
+/// and the dependency notices replaced by the current ones, or a
+/// [`new_notice`] when there is none.
+pub fn update_notice(
+    dir: &Path,
+    dependencies: &[Dependency],
+    author: &str,
+    year: u32,
+) -> NoticeUpdate {
+    let path = dir.join(NOTICE_FILE);
+    let old = fs::read_to_string(&path).ok();
+    let notices = apache_notices(dependencies);
+    let new = match &old {
+        Some(old) => render_notice(
+            &copyright::bump_years(notice_header(old), author, year).0,
+            &notices,
+        ),
+        None => new_notice(dir, dependencies, author, &year.to_string()),
+    };
+    NoticeUpdate {
+        path,
+        old: old.unwrap_or_default(),
+        new,
+        dependencies: notices.iter().map(|n| n.used_by.len()).sum(),
+    }
+}
+
+/// Checks the project NOTICE in `dir`, see [`check_notice`]. `None` when no
+/// NOTICE is needed: the project does not require Apache-2.0 and no
+/// Apache-2.0 dependency ships one.
+pub fn check_project_notice(
+    dir: &Path,
+    dependencies: &[Dependency],
+    project_apache: bool,
+    year: u32,
+) -> Option<Vec<NoticeProblem>> {
+    let notices = apache_notices(dependencies);
+    if !project_apache && notices.is_empty() {
+        return None;
+    }
+    let text = fs::read_to_string(dir.join(NOTICE_FILE)).ok();
+    Some(check_notice(
+        text.as_deref(),
+        &notices,
+        project_apache.then_some(year),
+    ))
+}
+
+impl Bundle {
+    /// One entry per license id with the number of packages under it, linking
+    /// to its first text.
+    pub fn overview(&self) -> Vec<Value> {
+        let mut overview: Vec<(Option<&str>, &LicenseText, Vec<&Package>)> = Vec::new();
+        for license in self.licenses.iter().filter(|l| !l.notice) {
+            let id = license.id.as_deref();
+            let index = match overview.iter().position(|(other, ..)| *other == id) {
+                Some(index) => index,
+                None => {
+                    overview.push((id, license, Vec::new()));
+                    overview.len() - 1
+                }
+            };
+            let packages = &mut overview[index].2;
+            for package in &license.used_by {
+                if !packages.contains(&package) {
+                    packages.push(package);
+                }
+            }
+        }
+        overview
+            .into_iter()
+            .map(|(id, license, packages)| {
+                let name = match id {
+                    Some(_) => license.name.as_str(),
+                    None => "Other license files",
+                };
+                serde_json::json!({
+                    "id": id,
+                    "name": name,
+                    "anchor": license.anchor,
+                    "count": packages.len(),
+                })
+            })
+            .collect()
+    }
+
+    pub fn render_text(&self) -> String {
+        let rule = "=".repeat(80);
+        let mut out = String::from(
+            "THIRD-PARTY LICENSES\n\nThis software includes the following third-party components.\n",
+        );
+        for license in &self.licenses {
+            out.push_str(&format!(
+                "\n{rule}\n{}\nUsed by: {}\n{rule}\n\n{}\n",
+                license.name,
+                used_by(&license.used_by),
+                license.text
+            ));
+        }
+        if !self.missing.is_empty() {
+            out.push_str(&format!(
+                "\n{rule}\nNo license text found for: {}\n",
+                used_by(&self.missing)
+            ));
+        }
+        out
+    }
+
+    pub fn render_markdown(&self) -> String {
+        let mut out = String::from(
+            "# Third-party licenses\n\nThis software includes the following third-party components.\n\n## Overview\n\n",
+        );
+        for entry in self.overview() {
+            out.push_str(&format!(
+                "- [{}](#{}) ({})\n",
+                entry["name"].as_str().unwrap_or_default(),
+                entry["anchor"].as_str().unwrap_or_default(),
+                entry["count"]
+            ));
+        }
+        for license in &self.licenses {
+            // A fence longer than any run of backticks in the text.
+            let longest = license
+                .text
+                .split(|c| c != '`')
+                .map(str::len)
+                .max()
+                .unwrap_or(0);
+            let fence = "`".repeat(longest.max(2) + 1);
+            out.push_str(&format!(
+                "\n<a id=\"{}\"></a>\n\n## {}\n\nUsed by: {}\n\n{fence}text\n{}\n{fence}\n",
+                license.anchor,
+                license.name,
+                used_by(&license.used_by),
+                license.text
+            ));
+        }
+        if !self.missing.is_empty() {
+            out.push_str(&format!(
+                "\n## No license text found\n\n{}\n",
+                used_by(&self.missing)
+            ));
+        }
+        out
+    }
+
+    /// Renders `template` with `overview`, `licenses` and `missing`, escaping
+    /// `{{...}}` values for HTML when `html` is set.
+    ///
+    /// As in cargo-about, each `used_by` entry also has its package under
+    /// `crate`, and each overview entry the `indices` of its licenses.
+    pub fn render_template(&self, template: &str, html: bool) -> Result<String, String> {
+        let mut context = match serde_json::to_value(self) {
+            Ok(Value::Object(map)) => map,
+            _ => Map::new(),
+        };
+        if let Some(Value::Array(licenses)) = context.get_mut("licenses") {
+            for license in licenses {
+                if let Some(Value::Array(packages)) = license.get_mut("used_by") {
+                    for package in packages {
+                        let alias = package.clone();
+                        if let Value::Object(package) = package {
+                            package.insert("crate".to_string(), alias);
+                        }
+                    }
+                }
+            }
+        }
+        let mut overview = self.overview();
+        for entry in &mut overview {
+            let indices: Vec<usize> = self
+                .licenses
+                .iter()
+                .enumerate()
+                .filter(|(_, l)| !l.notice && l.id.as_deref() == entry["id"].as_str())
+                .map(|(index, _)| index)
+                .collect();
+            entry["indices"] = indices.into();
+        }
+        context.insert("overview".to_string(), Value::Array(overview));
+
+        let nodes = parse_template(template)?;
+        let mut out = String::new();
+        let root = Scope {
+            this: Value::Object(context),
+            data: Map::new(),
+        };
+        render(&nodes, &mut vec![root], html, &mut out)?;
+        Ok(out)
+    }
+}
+
+fn used_by(packages: &[Package]) -> String {
+    let labels: Vec<String> = packages
+        .iter()
+        .map(|p| format!("{} {}", p.name, p.version))
+        .collect();
+    labels.join(", ")
+}
+
+#[derive(Debug)]
+enum Node {
+    Text(String),
+    Value {
+        path: String,
+        raw: bool,
+    },
+    Each {
+        path: String,
+        body: Vec<Node>,
+        otherwise: Vec<Node>,
+    },
+    If {
+        path: String,
+        then: Vec<Node>,
+        otherwise: Vec<Node>,
+    },
+}
+
+/// An open `{{#each}}`, `{{#if}}` or `{{#unless}}` while parsing.
+struct Block {
+    name: &'static str,
+    path: String,
+    /// The nodes before the block opened.
+    before: Vec<Node>,
+    /// The nodes between the block's start and `{{else}}`.
+    then: Option<Vec<Node>>,
+}
+
+/// Parses the template into nodes; blocks nest.
+fn parse_template(template: &str) -> Result<Vec<Node>, String> {
+    let mut stack: Vec<Block> = Vec::new();
+    let mut nodes: Vec<Node> = Vec::new();
+    let mut rest = template;
+    let mut trim_next = false;
+    let mut offset = 0;
+    while let Some(start) = rest.find("{{") {
+        let mut text = &rest[..start];
+        if trim_next {
+            text = text.trim_start();
+        }
+        let tag_start = &rest[start..];
+        let raw = tag_start.starts_with("{{{");
+        let long_comment = tag_start.starts_with("{{!--") || tag_start.starts_with("{{~!--");
+        let (open, close) = match () {
+            _ if raw => ("{{{", "}}}"),
+            _ if long_comment => ("{{", "--}}"),
+            _ => ("{{", "}}"),
+        };
+        let after = &rest[start + open.len()..];
+        let end = after
+            .find(close)
+            .ok_or_else(|| format!("unclosed '{open}' at byte {}", offset + start))?;
+        let mut tag = &after[..end];
+        let consumed = start + open.len() + end + close.len();
+        offset += consumed;
+        rest = &rest[consumed..];
+
+        if let Some(trimmed) = tag.strip_prefix('~') {
+            text = text.trim_end();
+            tag = trimmed;
+        }
+        trim_next = tag.ends_with('~');
+        let tag = tag.strip_suffix('~').unwrap_or(tag).trim();
+        if !text.is_empty() {
+            nodes.push(Node::Text(text.to_string()));
+        }
+
+        if tag.starts_with('!') {
+            continue;
+        } else if let Some(block) = tag.strip_prefix('#') {
+            let (name, path) = block.split_once(' ').unwrap_or((block, ""));
+            let name = match name {
+                "each" => "each",
+                "if" => "if",
+                "unless" => "unless",
+                other => return Err(format!("unknown block '#{other}'")),
+            };
+            stack.push(Block {
+                name,
+                path: path.trim().to_string(),
+                before: std::mem::take(&mut nodes),
+                then: None,
+            });
+        } else if tag == "else" {
+            match stack.last_mut() {
+                Some(block) if block.then.is_none() => {
+                    block.then = Some(std::mem::take(&mut nodes));
+                }
+                _ => return Err("'{{else}}' outside a block".to_string()),
+            }
+        } else if let Some(name) = tag.strip_prefix('/') {
+            let Some(Block {
+                name: open,
+                path,
+                before,
+                then,
+            }) = stack.pop()
+            else {
+                return Err(format!("'{{{{/{name}}}}}' without a block to close"));
+            };
+            if open != name {
+                return Err(format!("'{{{{/{name}}}}}' closes '{{{{#{open}}}}}'"));
+            }
+            let last = std::mem::replace(&mut nodes, before);
+            let (then, otherwise) = match then {
+                Some(then) => (then, last),
+                None => (last, Vec::new()),
+            };
+            nodes.push(match open {
+                "each" => Node::Each {
+                    path,
+                    body: then,
+                    otherwise,
+                },
+                "unless" => Node::If {
+                    path,
+                    then: otherwise,
+                    otherwise: then,
+                },
+                _ => Node::If {
+                    path,
+                    then,
+                    otherwise,
+                },
+            });
+        } else if tag.contains(char::is_whitespace) {
+            return Err(format!("'{{{{{tag}}}}}': helpers are not supported"));
+        } else {
+            nodes.push(Node::Value {
+                path: tag.to_string(),
+                raw,
+            });
+        }
+    }
+    if let Some(block) = stack.last() {
+        return Err(format!("'{{{{#{}}}}}' is not closed", block.name));
+    }
+    let rest = if trim_next { rest.trim_start() } else { rest };
+    if !rest.is_empty() {
+        nodes.push(Node::Text(rest.to_string()));
+    }
+    Ok(nodes)
+}
+
+/// The context of one `{{#each}}` item, or of the whole template.
+struct Scope {
+    this: Value,
+    /// `@index`, `@first`, `@last` and `@key`.
+    data: Map<String, Value>,
+}
+
+/// The value at a dotted `path` (numbers index lists): `@` data of the innermost `#each`, `this`,
+/// or keys looked up from the innermost scope out, after skipping one scope
+/// per `../`. `None` when there is no such value.
+fn lookup<'a>(path: &str, scopes: &'a [Scope]) -> Option<&'a Value> {
+    let mut path = path;
+    let mut scopes = scopes;
+    while let Some(rest) = path.strip_prefix("../") {
+        path = rest;
+        scopes = &scopes[..scopes.len().saturating_sub(1).max(1)];
+    }
+    if path.starts_with('@') {
+        return scopes.last()?.data.get(path);
+    }
+    let mut keys = path.split('.');
+    let first = keys.next().unwrap_or_default();
+    let mut value = if first == "this" {
+        &scopes.last()?.this
+    } else {
+        scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.this.get(first))?
+    };
+    for key in keys {
+        value = match value {
+            Value::Array(items) => items.get(key.parse::<usize>().ok()?)?,
+            value => value.get(key)?,
+        };
+    }
+    Some(value)
+}
+
+fn truthy(value: Option<&Value>) -> bool {
+    match value {
+        None | Some(Value::Null) => false,
+        Some(Value::Bool(b)) => *b,
+        Some(Value::Number(n)) => n.as_f64() != Some(0.0),
+        Some(Value::String(s)) => !s.is_empty(),
+        Some(Value::Array(a)) => !a.is_empty(),
+        Some(Value::Object(_)) => true,
+    }
+}
+
+fn render(
+    nodes: &[Node],
+    scopes: &mut Vec<Scope>,
+    html: bool,
+    out: &mut String,
+) -> Result<(), String> {
+    for node in nodes {
+        match node {
+            Node::Text(text) => out.push_str(text),
+            Node::Value { path, raw } => {
+                let text = match lookup(path, scopes) {
+                    None | Some(Value::Null) => String::new(),
+                    Some(Value::String(s)) => s.clone(),
+                    Some(Value::Array(_) | Value::Object(_)) => {
+                        return Err(format!(
+                            "'{path}' is a list or object; use {{{{#each {path}}}}}"
+                        ));
+                    }
+                    Some(other) => other.to_string(),
+                };
+                if html && !raw {
+                    out.push_str(&escape_html(&text));
+                } else {
+                    out.push_str(&text);
+                }
+            }
+            Node::Each {
+                path,
+                body,
+                otherwise,
+            } => {
+                let items: Vec<(Value, Value)> = match lookup(path, scopes) {
+                    Some(Value::Array(items)) => items
+                        .iter()
+                        .enumerate()
+                        .map(|(index, item)| (index.into(), item.clone()))
+                        .collect(),
+                    Some(Value::Object(map)) => map
+                        .iter()
+                        .map(|(key, item)| (key.as_str().into(), item.clone()))
+                        .collect(),
+                    None | Some(Value::Null) => Vec::new(),
+                    Some(_) => return Err(format!("'{path}' is not a list")),
+                };
+                if items.is_empty() {
+                    render(otherwise, scopes, html, out)?;
+                }
+                let count = items.len();
+                for (index, (key, item)) in items.into_iter().enumerate() {
+                    let mut data = Map::new();
+                    data.insert("@index".to_string(), index.into());
+                    data.insert("@key".to_string(), key);
+                    data.insert("@first".to_string(), (index == 0).into());
+                    data.insert("@last".to_string(), (index + 1 == count).into());
+                    scopes.push(Scope { this: item, data });
+                    let rendered = render(body, scopes, html, out);
+                    scopes.pop();
+                    rendered?;
+                }
+            }
+            Node::If {
+                path,
+                then,
+                otherwise,
+            } => {
+                let branch = if truthy(lookup(path, scopes)) {
+                    then
+                } else {
+                    otherwise
+                };
+                render(branch, scopes, html, out)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deps::Ecosystem;
+    use tempfile::TempDir;
+
+    fn requires(expr: &str) -> bool {
+        requires_apache(&expression::parse(expr).unwrap().normalize())
+    }
+
+    fn package(name: &str) -> Package {
+        Package {
+            ecosystem: "cargo",
+            name: name.to_string(),
+            version: "1.0.0".to_string(),
+        }
+    }
+
+    fn bundle() -> Bundle {
+        Bundle {
+            licenses: vec![LicenseText {
+                id: Some("MIT".to_string()),
+                name: "MIT License".to_string(),
+                notice: false,
+                anchor: "mit".to_string(),
+                text: "Copyright <Jane & Joe>".to_string(),
+                used_by: vec![package("a"), package("b")],
+            }],
+            missing: Vec::new(),
+        }
+    }
+
+    fn render_html(template: &str) -> Result<String, String> {
+        bundle().render_template(template, true)
+    }
+
+    #[test]
+    fn values_are_escaped_unless_raw() {
+        assert_eq!(
+            render_html("{{#each licenses}}{{text}}|{{{text}}}{{/each}}").unwrap(),
+            "Copyright &lt;Jane &amp; Joe&gt;|Copyright <Jane & Joe>"
+        );
+        assert_eq!(
+            bundle()
+                .render_template("{{#each licenses}}{{text}}{{/each}}", false)
+                .unwrap(),
+            "Copyright <Jane & Joe>"
+        );
+    }
+
+    #[test]
+    fn each_with_index_and_outer_scopes() {
+        let template = "{{#each licenses}}{{#each used_by}}{{#if @index}}, {{/if}}\
+                        {{name}} ({{anchor}}){{/each}}{{/each}}";
+        assert_eq!(render_html(template).unwrap(), "a (mit), b (mit)");
+        assert_eq!(
+            render_html("{{#each overview}}{{name}}: {{count}}{{/each}}").unwrap(),
+            "MIT License: 2"
+        );
+    }
+
+    #[test]
+    fn if_else_truthiness() {
+        let template = "{{#if missing}}some{{else}}none{{/if}}";
+        assert_eq!(render_html(template).unwrap(), "none");
+        let mut with_missing = bundle();
+        with_missing.missing.push(package("c"));
+        assert_eq!(
+            with_missing.render_template(template, true).unwrap(),
+            "some"
+        );
+        assert_eq!(
+            render_html(
+                "{{#each licenses}}{{#if notice}}N{{else}}L{{/if}}{{#if id}}!{{/if}}{{/each}}"
+            )
+            .unwrap(),
+            "L!"
+        );
+    }
+
+    #[test]
+    fn this_and_dotted_paths() {
+        let template = "{{#each licenses}}{{#each used_by}}{{this.name}}{{/each}}{{/each}}";
+        assert_eq!(render_html(template).unwrap(), "ab");
+    }
+
+    #[test]
+    fn malformed_templates_are_errors() {
+        for (template, error) in [
+            ("{{name", "unclosed '{{' at byte 0"),
+            ("{{#each licenses}}", "'{{#each}}' is not closed"),
+            ("{{#each licenses}}{{/if}}", "'{{/if}}' closes '{{#each}}'"),
+            ("{{/each}}", "'{{/each}}' without a block to close"),
+            ("{{else}}", "'{{else}}' outside a block"),
+            (
+                "{{#if a}}{{else}}{{else}}{{/if}}",
+                "'{{else}}' outside a block",
+            ),
+            ("{{#with licenses}}{{/with}}", "unknown block '#with'"),
+            (
+                "{{json this}}",
+                "'{{json this}}': helpers are not supported",
+            ),
+            (
+                "{{licenses}}",
+                "'licenses' is a list or object; use {{#each licenses}}",
+            ),
+            (
+                "{{#each licenses.0.text}}{{/each}}",
+                "'licenses.0.text' is not a list",
+            ),
+        ] {
+            assert_eq!(render_html(template).unwrap_err(), error, "{template}");
+        }
+    }
+
+    #[test]
+    fn missing_values_are_falsy_and_empty() {
+        assert_eq!(
+            render_html("[{{nope}}]{{#if nope.x}}yes{{else}}no{{/if}}{{#unless nope}}!{{/unless}}")
+                .unwrap(),
+            "[]no!"
+        );
+        assert_eq!(
+            render_html(
+                "{{#each nope}}x{{else}}empty{{/each}}{{#each missing}}x{{else}}none{{/each}}"
+            )
+            .unwrap(),
+            "emptynone"
+        );
+    }
+
+    #[test]
+    fn each_data_for_every_item() {
+        let template = "{{#each licenses}}{{#each used_by}}{{@index}}{{#if @first}}<{{/if}}\
+                        {{#each this}}{{#if @last}}{{@key}}={{this}}{{/if}}{{/each}}\
+                        {{#unless @last}},{{/unless}}{{/each}}{{/each}}";
+        assert_eq!(
+            render_html(template).unwrap(),
+            "0<version=1.0.0,1version=1.0.0"
+        );
+        let names = "{{#each licenses}}{{#each used_by}}{{#each ../used_by}}{{@index}}{{/each}}{{/each}}{{/each}}";
+        assert_eq!(render_html(names).unwrap(), "0101");
+    }
+
+    #[test]
+    fn comments_and_whitespace_control() {
+        let template = "{{! a comment }}{{!-- {{#each}} --}}\n  {{~#each licenses~}}\n  {{name}}\n{{~/each}}  !";
+        assert_eq!(render_html(template).unwrap(), "MIT License  !");
+    }
+
+    #[test]
+    fn cargo_about_templates_render() {
+        let template = "{{#each overview}}<a href=\"#{{id}}\">{{name}}</a> ({{count}}, {{#each indices}}{{this}}{{/each}})\
+                        {{/each}}{{#each licenses}}{{#each used_by}}\
+                        <a href=\"{{#if crate.repository}}{{crate.repository}}{{else}}https://crates.io/crates/{{crate.name}}{{/if}}\">\
+                        {{crate.name}} {{crate.version}}</a>{{/each}}{{/each}}";
+        assert_eq!(
+            render_html(template).unwrap(),
+            "<a href=\"#MIT\">MIT License</a> (2, 0)\
+             <a href=\"https://crates.io/crates/a\">a 1.0.0</a>\
+             <a href=\"https://crates.io/crates/b\">b 1.0.0</a>"
+        );
+    }
+
+    #[test]
+    fn default_html_template_renders() {
+        let html = render_html(HTML_TEMPLATE).unwrap();
+        assert!(html.contains(r##"<li><a href="#mit">MIT License</a> (2)</li>"##));
+        assert!(html.contains("<p>Used by: a 1.0.0, b 1.0.0</p>"));
+        assert!(html.contains("<pre>Copyright &lt;Jane &amp; Joe&gt;</pre>"));
+        assert!(!html.contains("No license text found"));
+    }
+
+    #[test]
+    fn apache_is_required_only_without_an_alternative() {
+        assert!(requires("Apache-2.0"));
+        assert!(requires("apache-2.0"));
+        assert!(requires("Apache-2.0 WITH LLVM-exception"));
+        assert!(requires("MIT AND Apache-2.0"));
+        assert!(requires("(MIT OR Apache-2.0) AND Apache-2.0"));
+        assert!(requires("Apache-2.0 OR Apache-2.0 AND MIT"));
+        assert!(!requires("MIT OR Apache-2.0"));
+        assert!(!requires("Apache-2.0 OR MIT"));
+        assert!(!requires("(Apache-2.0 AND ISC) OR MIT"));
+        assert!(!requires("MIT"));
+    }
+
+    #[test]
+    fn apache_is_included_as_any_term() {
+        let includes = |expr: &str| includes_apache(&expression::parse(expr).unwrap().normalize());
+        assert!(includes("Apache-2.0"));
+        assert!(includes("apache-2.0 WITH LLVM-exception"));
+        assert!(includes("MIT OR Apache-2.0"));
+        assert!(includes("(Apache-2.0 AND ISC) OR MIT"));
+        assert!(!includes("MIT OR Apache-1.1"));
+        assert!(!includes("MIT"));
+    }
+
+    /// A dependency under `license` whose sources in `dir` ship `notice`.
+    fn shipping(dir: &TempDir, license: &str, notice: &str) -> Dependency {
+        fs::write(dir.path().join(NOTICE_FILE), notice).unwrap();
+        Dependency {
+            ecosystem: Ecosystem::Cargo,
+            name: "dep".to_string(),
+            version: "1.0.0".to_string(),
+            license: Some(license.to_string()),
+            license_file: None,
+            status: LicenseStatus::Spdx,
+            path: Some(dir.path().to_path_buf()),
+            via: vec!["app 0.1.0".to_string()],
+        }
+    }
+
+    #[test]
+    fn new_notice_names_the_project() {
+        let root = TempDir::new().unwrap();
+        let project = root.path().join("demo");
+        fs::create_dir(&project).unwrap();
+        let dep = TempDir::new().unwrap();
+        let deps = [shipping(
+            &dep,
+            "Apache-2.0",
+            "Dep\nCopyright 2019 Dep Authors\n",
+        )];
+
+        let text = new_notice(&project, &deps, "Jane Doe", "2026");
+        assert!(
+            text.starts_with("demo\nCopyright 2026 Jane Doe\n"),
+            "{text}"
+        );
+        assert!(text.contains("=== dep 1.0.0 ===\n\nDep\nCopyright 2019 Dep Authors\n"));
+    }
+
+    #[test]
+    fn dual_licensed_dependencies_need_no_notice() {
+        let project = TempDir::new().unwrap();
+        let dep = TempDir::new().unwrap();
+        let deps = [shipping(&dep, "MIT OR Apache-2.0", "Dep\n")];
+
+        assert!(!new_notice(project.path(), &deps, "Jane Doe", "2026").contains("Dep"));
+        assert_eq!(
+            check_project_notice(project.path(), &deps, false, 2026),
+            None
+        );
+        assert_eq!(
+            check_project_notice(project.path(), &deps, true, 2026),
+            Some(vec![NoticeProblem::Missing])
+        );
+    }
+
+    #[test]
+    fn update_keeps_the_project_notice_and_bumps_the_year() {
+        let project = TempDir::new().unwrap();
+        let dep = TempDir::new().unwrap();
+        let deps = [shipping(&dep, "Apache-2.0", "Dep\n")];
+        fs::write(
+            project.path().join(NOTICE_FILE),
+            "Demo\nCopyright 2020 Jane Doe\n",
+        )
+        .unwrap();
+        assert_eq!(
+            check_project_notice(project.path(), &deps, true, 2026),
+            Some(vec![
+                NoticeProblem::StaleYear(2020),
+                NoticeProblem::MissingDependency("dep 1.0.0".to_string()),
+            ])
+        );
+
+        let update = update_notice(project.path(), &deps, "Jane Doe", 2026);
+        assert_eq!(update.dependencies, 1);
+        assert!(
+            update
+                .new
+                .starts_with("Demo\nCopyright 2020-2026 Jane Doe\n")
+        );
+        fs::write(&update.path, &update.new).unwrap();
+
+        let again = update_notice(project.path(), &deps, "Jane Doe", 2026);
+        assert_eq!(again.old, again.new);
+        assert_eq!(
+            check_project_notice(project.path(), &deps, true, 2026),
+            Some(Vec::new())
+        );
+    }
+}
@@ -0,0 +1,431 @@
+//! License policy for third-party dependencies, read from `lic-policy.toml`.
+//!
+//! A dependency passes when one choice of its license expression is allowed
+//! by the policy and compatible with the project license (see [`crate::compat`]).
+
+use crate::compat::{self, Compatibility};
+use crate::deps::{Dependency, LicenseStatus};
+use crate::expression::{self, Expression, LicenseTerm};
+use crate::{Error, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::{fmt, fs};
+
+pub const POLICY_FILE: &str = "lic-policy.toml";
+
+/// The starting policy `lic policy init` writes.
+pub const TEMPLATE: &str = r#"# License policy for third-party dependencies, checked by `lic deps --check`.
+# Dependencies must also be compatible with the project license.
+
+# Project license (defaults to the license detected in the project)
+# project = "MIT"
+
+# When not empty, the only licenses dependencies may use
+allow = [
+    "0BSD",
+    "Apache-2.0",
+    "BSD-2-Clause",
+    "BSD-3-Clause",
+    "ISC",
+    "MIT",
+    "Unicode-3.0",
+    "Zlib",
+]
+
+# Licenses no dependency may use, even when compatible
+deny = ["AGPL-3.0-only", "AGPL-3.0-or-later", "SSPL-1.0"]
+
+# Dependencies accepted whatever their license
+# [[exceptions]]
+# name = "ring"
+# version = "0.17.8"        # any version when omitted
+# license = "ISC AND OpenSSL" # only while it declares this license
+# reason = "Reviewed by legal"
+"#;
+
+/// The contents of `lic-policy.toml`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Policy {
+    /// The project license, instead of the one detected from its license files.
+    pub project: Option<String>,
+    /// When not empty, the only licenses dependencies may use.
+    pub allow: Vec<String>,
+    /// Licenses no dependency may use, even when compatible.
+    pub deny: Vec<String>,
+    /// Dependencies accepted whatever their license.
+    pub exceptions: Vec<Exception>,
+}
+
+/// A dependency the policy accepts as is.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Exception {
+    pub name: String,
+    /// Only this version; any version when absent.
+    pub version: Option<String>,
+    /// Only while the dependency declares this license.
+    pub license: Option<String>,
+    pub reason: Option<String>,
+}
+
+impl Exception {
+    fn covers(&self, dependency: &Dependency) -> bool {
+        self.name == dependency.name
+            && self
+                .version
+                .as_ref()
+                .is_none_or(|v| *v == dependency.version)
+            && self
+                .license
+                .as_ref()
+                .is_none_or(|l| dependency.license.as_deref() == Some(l.as_str()))
+    }
+}
+
+impl Policy {
+    /// Loads the policy at `path`, or the default policy when it does not exist.
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Self::read(path)
+    }
+
+    fn read(path: &Path) -> Result<Self> {
+        let raw = fs::read_to_string(path).map_err(|e| Error::io(path, e))?;
+        toml::from_str(&raw).map_err(|source| Error::Config {
+            path: path.to_path_buf(),
+            source,
+        })
+    }
+
+    /// The policy at `path` when given, which must exist, or else `dir`'s
+    /// [`POLICY_FILE`] or the default policy.
+    pub fn find(dir: &Path, path: Option<&Path>) -> Result<Self> {
+        match path {
+            Some(path) => Self::read(path),
+            None => Self::load(&dir.join(POLICY_FILE)),
+        }
+    }
+
+    fn listed(list: &[String], term: &LicenseTerm) -> bool {
+        let with_exception = term.to_string();
+        list.iter().any(|entry| {
+            entry.eq_ignore_ascii_case(&with_exception) || entry.eq_ignore_ascii_case(&term.id)
+        })
+    }
+
+    /// Whether `term` may be used under this policy, regardless of the project license.
+    fn permits(&self, term: &LicenseTerm) -> Compatibility {
+        if Self::listed(&self.deny, term) {
+            return Compatibility::Incompatible(format!("{term} is denied by the policy"));
+        }
+        if !self.allow.is_empty() && !Self::listed(&self.allow, term) {
+            return Compatibility::Incompatible(format!("{term} is not in the allow list"));
+        }
+        Compatibility::Compatible
+    }
+}
+
+/// The project license dependencies must be compatible with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Project {
+    License(Expression),
+    /// No usable project license, so only the policy lists are checked.
+    Unchecked(String),
+}
+
+impl Project {
+    /// The project license `license`, when there is one and every license in
+    /// it is part of the compatibility model.
+    pub fn resolve(license: Option<&str>) -> Result<Self> {
+        let Some(license) = license else {
+            return Ok(Project::Unchecked(format!(
+                "no project license found (set `project` in {POLICY_FILE})"
+            )));
+        };
+        let parsed = expression::parse(license)?.normalize();
+        if parsed.terms().iter().all(|t| compat::category(t).is_some()) {
+            Ok(Project::License(parsed))
+        } else {
+            Ok(Project::Unchecked(format!(
+                "{parsed} is not in the compatibility model"
+            )))
+        }
+    }
+
+    pub fn license(&self) -> Option<&Expression> {
+        match self {
+            Project::License(license) => Some(license),
+            Project::Unchecked(_) => None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Finding<'a> {
+    #[serde(flatten)]
+    pub dependency: &'a Dependency,
+    pub reason: String,
+}
+
+/// `name version (license): reason`, followed by the dependency chain.
+impl fmt::Display for Finding<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let dep = self.dependency;
+        let license = dep.license.as_deref().unwrap_or("no license");
+        write!(
+            f,
+            "{} {} ({license}): {}",
+            dep.name, dep.version, self.reason
+        )?;
+        if !dep.via.is_empty() {
+            write!(
+                f,
+                "\n  via {} -> {} {}",
+                dep.via.join(" -> "),
+                dep.name,
+                dep.version
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// The outcome of checking every dependency against a policy.
+#[derive(Debug, Default, Serialize)]
+pub struct Report<'a> {
+    pub violations: Vec<Finding<'a>>,
+    /// Licenses the compatibility model does not know and the policy does not allow.
+    pub warnings: Vec<Finding<'a>>,
+}
+
+/// Checks `dependencies` against `policy` and, when known, the `project` license.
+pub fn check<'a>(
+    dependencies: &'a [Dependency],
+    policy: &Policy,
+    project: Option<&Expression>,
+) -> Report<'a> {
+    let mut report = Report::default();
+    for dependency in dependencies {
+        if policy.exceptions.iter().any(|e| e.covers(dependency)) {
+            continue;
+        }
+        let finding = |reason: String| Finding { dependency, reason };
+        let expression = match (dependency.status, &dependency.license) {
+            (LicenseStatus::Spdx, Some(license)) => expression::parse(license).ok(),
+            _ => None,
+        };
+        let Some(expression) = expression else {
+            let reason = match dependency.status {
+                LicenseStatus::Missing => "no license declared".to_string(),
+                _ => "the license is not an SPDX expression".to_string(),
+            };
+            report.violations.push(finding(reason));
+            continue;
+        };
+        let verdict = compat::evaluate(&expression, &|term| match policy.permits(term) {
+            Compatibility::Compatible => match project {
+                Some(project) => {
+                    match compat::compatibility(&Expression::License(term.clone()), project) {
+                        // Licenses on the allow list are trusted even when the model does not know them.
+                        Compatibility::Unknown(_) if Policy::listed(&policy.allow, term) => {
+                            Compatibility::Compatible
+                        }
+                        verdict => verdict,
+                    }
+                }
+                None => Compatibility::Compatible,
+            },
+            verdict => verdict,
+        });
+        match verdict {
+            Compatibility::Compatible => {}
+            Compatibility::Incompatible(reason) => report.violations.push(finding(reason)),
+            Compatibility::Unknown(reason) => report.warnings.push(finding(reason)),
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deps::Ecosystem;
+
+    fn dependency(name: &str, license: Option<&str>) -> Dependency {
+        let status = match license {
+            Some(license) if expression::parse(license).is_ok() => LicenseStatus::Spdx,
+            Some(_) => LicenseStatus::NonSpdx,
+            None => LicenseStatus::Missing,
+        };
+        Dependency {
+            ecosystem: Ecosystem::Cargo,
+            name: name.to_string(),
+            version: "1.0.0".to_string(),
+            license: license.map(str::to_string),
+            license_file: None,
+            status,
+            path: None,
+            via: vec!["app 0.1.0".to_string()],
+        }
+    }
+
+    fn policy(toml: &str) -> Policy {
+        toml::from_str(toml).unwrap()
+    }
+
+    fn project(license: &str) -> Expression {
+        expression::parse(license).unwrap().normalize()
+    }
+
+    /// `(name, reason)` of the violations and warnings.
+    fn findings(report: &Report) -> (Vec<String>, Vec<String>) {
+        let list = |findings: &[Finding]| {
+            findings
+                .iter()
+                .map(|f| format!("{}: {}", f.dependency.name, f.reason))
+                .collect()
+        };
+        (list(&report.violations), list(&report.warnings))
+    }
+
+    #[test]
+    fn template_is_a_valid_policy() {
+        let policy = policy(TEMPLATE);
+        assert!(policy.allow.contains(&"MIT".to_string()));
+        assert!(policy.deny.contains(&"SSPL-1.0".to_string()));
+    }
+
+    #[test]
+    fn unknown_keys_are_rejected() {
+        assert!(toml::from_str::<Policy>("alow = [\"MIT\"]").is_err());
+    }
+
+    #[test]
+    fn deny_and_allow_lists() {
+        let deps = [
+            dependency("ok", Some("MIT")),
+            dependency("denied", Some("SSPL-1.0")),
+            dependency("unlisted", Some("Zlib")),
+            dependency("either", Some("SSPL-1.0 OR MIT")),
+        ];
+        let policy = policy("allow = [\"MIT\", \"SSPL-1.0\"]\ndeny = [\"sspl-1.0\"]");
+        let (violations, warnings) = findings(&check(&deps, &policy, None));
+        assert_eq!(
+            violations,
+            [
+                "denied: SSPL-1.0 is denied by the policy",
+                "unlisted: Zlib is not in the allow list",
+            ]
+        );
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn incompatible_with_the_project() {
+        let deps = [
+            dependency("gpl", Some("GPL-3.0-only")),
+            dependency("dual", Some("GPL-3.0-only OR MIT")),
+            dependency("mit", Some("MIT")),
+        ];
+        let report = check(&deps, &Policy::default(), Some(&project("MIT")));
+        let (violations, _) = findings(&report);
+        assert_eq!(violations.len(), 1);
+        assert!(violations[0].starts_with("gpl: "));
+        assert!(
+            check(&deps, &Policy::default(), Some(&project("GPL-3.0-only")))
+                .violations
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn missing_and_free_text_licenses() {
+        let deps = [
+            dependency("none", None),
+            dependency("text", Some("SEE LICENSE IN LICENSE.md")),
+        ];
+        let (violations, _) = findings(&check(&deps, &Policy::default(), None));
+        assert_eq!(
+            violations,
+            [
+                "none: no license declared",
+                "text: the license is not an SPDX expression",
+            ]
+        );
+    }
+
+    #[test]
+    fn exceptions_match_name_version_and_license() {
+        let deps = [
+            dependency("ring", Some("SSPL-1.0")),
+            dependency("other", Some("SSPL-1.0")),
+        ];
+        let excepted = |exception: &str| {
+            let policy = policy(&format!(
+                "deny = [\"SSPL-1.0\"]\n[[exceptions]]\n{exception}"
+            ));
+            let (violations, _) = findings(&check(&deps, &policy, None));
+            violations.iter().all(|v| !v.starts_with("ring:"))
+        };
+        assert!(excepted("name = \"ring\""));
+        assert!(excepted(
+            "name = \"ring\"\nversion = \"1.0.0\"\nlicense = \"SSPL-1.0\""
+        ));
+        assert!(!excepted("name = \"ring\"\nversion = \"2.0.0\""));
+        assert!(!excepted("name = \"ring\"\nlicense = \"MIT\""));
+    }
+
+    #[test]
+    fn allowed_licenses_outside_the_model_are_trusted() {
+        let deps = [
+            dependency("custom", Some("LicenseRef-Acme")),
+            dependency("odd", Some("Beerware")),
+        ];
+        let policy = policy("allow = [\"MIT\", \"LicenseRef-Acme\", \"Beerware\"]");
+        let report = check(&deps, &policy, Some(&project("MIT")));
+        assert!(report.violations.is_empty());
+        assert!(report.warnings.is_empty());
+        let report = check(&deps, &Policy::default(), Some(&project("MIT")));
+        assert_eq!(report.warnings.len(), 2);
+    }
+
+    #[test]
+    fn project_resolution() {
+        assert_eq!(
+            Project::resolve(Some("mit or apache-2.0")).unwrap(),
+            Project::License(project("MIT OR Apache-2.0"))
+        );
+        assert!(matches!(
+            Project::resolve(Some("LicenseRef-Acme")).unwrap(),
+            Project::Unchecked(reason) if reason == "LicenseRef-Acme is not in the compatibility model"
+        ));
+        assert!(matches!(
+            Project::resolve(None).unwrap(),
+            Project::Unchecked(_)
+        ));
+        assert!(Project::resolve(Some("MIT OR")).is_err());
+    }
+
+    #[test]
+    fn finding_display_shows_the_chain() {
+        let dep = dependency("gpl", Some("GPL-3.0-only"));
+        let finding = Finding {
+            dependency: &dep,
+            reason: "incompatible".to_string(),
+        };
+        assert_eq!(
+            finding.to_string(),
+            "gpl 1.0.0 (GPL-3.0-only): incompatible\n  via app 0.1.0 -> gpl 1.0.0"
+        );
+    }
+
+    #[test]
+    fn explicit_policy_file_must_exist() {
+        let dir = tempfile::TempDir::new().unwrap();
+        assert!(Policy::find(dir.path(), None).unwrap().allow.is_empty());
+        assert!(Policy::find(dir.path(), Some(&dir.path().join("missing.toml"))).is_err());
+    }
+}
@@ -0,0 +1,324 @@
+use crate::bundled::BundledProvider;
+use crate::fuzzy::{self, Match};
+use crate::spdx_list::SpdxLicenses;
+use crate::{Error, LicenseDetail, LicenseMeta, Result};
+use async_trait::async_trait;
+
+/// A source of license templates.
+#[async_trait]
+pub trait LicenseProvider: Send + Sync {
+    /// Short name used in warnings, e.g. `github` or `spdx`.
+    fn name(&self) -> &str;
+
+    async fn list(&self) -> Result<Vec<LicenseMeta>>;
+
+    /// Returns `Ok(None)` when this provider does not know `key`.
+    async fn get(&self, key: &str) -> Result<Option<LicenseDetail>>;
+
+    /// The listed license whose key or SPDX id is `key`, ignoring case.
+    async fn find(&self, key: &str) -> Result<Option<LicenseMeta>> {
+        Ok(self
+            .list()
+            .await?
+            .into_iter()
+            .find(|l| l.key.eq_ignore_ascii_case(key) || l.spdx_id.eq_ignore_ascii_case(key)))
+    }
+
+    /// Licenses whose key, SPDX id or name contains `query`, ignoring case.
+    async fn search(&self, query: &str) -> Result<Vec<LicenseMeta>> {
+        let query = query.to_lowercase();
+        Ok(self
+            .list()
+            .await?
+            .into_iter()
+            .filter(|l| {
+                l.key.to_lowercase().contains(&query)
+                    || l.spdx_id.to_lowercase().contains(&query)
+                    || l.name.to_lowercase().contains(&query)
+            })
+            .collect())
+    }
+}
+
+/// Providers consulted in order; a failing provider is reported and skipped.
+pub struct ProviderChain {
+    providers: Vec<Box<dyn LicenseProvider>>,
+}
+
+impl ProviderChain {
+    pub fn new(providers: Vec<Box<dyn LicenseProvider>>) -> Self {
+        Self { providers }
+    }
+
+    /// The bundled GitHub templates followed by the compiled-in SPDX License List.
+    pub fn offline() -> Self {
+        Self::new(vec![
+            Box::new(BundledProvider),
+            Box::new(SpdxLicenses::new(None)),
+        ])
+    }
+
+    /// Fetches `key`, also accepting names, aliases and loose spellings such as
+    /// `Apache2` (see [`fuzzy::find`]). Fails with [`Error::AmbiguousLicense`] or
+    /// with [`Error::UnknownLicense`] and close matches.
+    pub async fn resolve(&self, key: &str) -> Result<LicenseDetail> {
+        if let Some(detail) = self.get(key).await? {
+            return Ok(detail);
+        }
+        if let Some(license) = self.lookup(key).await?
+            && let Some(detail) = self.get(&license.key).await?
+        {
+            return Ok(detail);
+        }
+        let licenses = self.list().await?;
+        let suggestions = fuzzy::suggestions(key, &licenses)
+            .into_iter()
+            .map(|l| l.key.clone())
+            .collect();
+        Err(Error::UnknownLicense {
+            key: key.to_string(),
+            suggestions,
+        })
+    }
+
+    /// The listed license `input` refers to, see [`fuzzy::find`]. Fails with
+    /// [`Error::AmbiguousLicense`] when several licenses match equally well.
+    pub async fn lookup(&self, input: &str) -> Result<Option<LicenseMeta>> {
+        let licenses = self.list().await?;
+        match fuzzy::find(input, &licenses) {
+            Match::One(license) => Ok(Some(license.clone())),
+            Match::Ambiguous(candidates) => {
+                let mut keys: Vec<String> =
+                    candidates.iter().take(8).map(|l| l.key.clone()).collect();
+                if candidates.len() > keys.len() {
+                    keys.push(format!("and {} more", candidates.len() - keys.len()));
+                }
+                Err(Error::AmbiguousLicense {
+                    key: input.to_string(),
+                    candidates: keys,
+                })
+            }
+            Match::None => Ok(None),
+        }
+    }
+
+    /// The SPDX id of `key`, or `key` itself for licenses that are not listed
+    /// but can be fetched. Fails like [`resolve`](Self::resolve) otherwise.
+    pub async fn spdx_id(&self, key: &str) -> Result<String> {
+        if let Some(license) = self.find(key).await? {
+            return Ok(license.spdx_id);
+        }
+        if self.get(key).await?.is_some() {
+            return Ok(key.to_string());
+        }
+        Ok(match self.lookup(key).await? {
+            Some(license) => license.spdx_id,
+            None => self.resolve(key).await.map(|_| key.to_string())?,
+        })
+    }
+}
+
+#[async_trait]
+impl LicenseProvider for ProviderChain {
+    fn name(&self) -> &str {
+        "chain"
+    }
+
+    /// Merges every provider's list, skipping SPDX ids an earlier provider already listed.
+    async fn list(&self) -> Result<Vec<LicenseMeta>> {
+        let mut licenses: Vec<LicenseMeta> = Vec::new();
+        for provider in &self.providers {
+            let listed = match provider.list().await {
+                Ok(listed) => listed,
+                Err(e) => {
+                    eprintln!("warning: {}: {e}", provider.name());
+                    continue;
+                }
+            };
+            for license in listed {
+                if !licenses
+                    .iter()
+                    .any(|l| l.spdx_id.eq_ignore_ascii_case(&license.spdx_id))
+                {
+                    licenses.push(license);
+                }
+            }
+        }
+        Ok(licenses)
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<LicenseDetail>> {
+        for provider in &self.providers {
+            match provider.get(key).await {
+                Ok(Some(detail)) => return Ok(Some(detail)),
+                Ok(None) => {}
+                Err(e) => eprintln!("warning: {}: {e}", provider.name()),
+            }
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// A provider serving fixed `(key, spdx_id)` licenses whose body names the provider.
+    struct Fake {
+        name: &'static str,
+        licenses: &'static [(&'static str, &'static str)],
+        failing: bool,
+        gets: Arc<AtomicUsize>,
+    }
+
+    impl Fake {
+        fn new(name: &'static str, licenses: &'static [(&'static str, &'static str)]) -> Self {
+            Self {
+                name,
+                licenses,
+                failing: false,
+                gets: Arc::default(),
+            }
+        }
+
+        fn failing(name: &'static str) -> Self {
+            Self {
+                failing: true,
+                ..Self::new(name, &[])
+            }
+        }
+
+        fn fail(&self) -> Result<()> {
+            match self.failing {
+                true => Err(Error::RateLimited(format!("{} is down", self.name))),
+                false => Ok(()),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl LicenseProvider for Fake {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        async fn list(&self) -> Result<Vec<LicenseMeta>> {
+            self.fail()?;
+            Ok(self
+                .licenses
+                .iter()
+                .map(|(key, spdx_id)| LicenseMeta {
+                    key: key.to_string(),
+                    name: format!("{spdx_id} from {}", self.name),
+                    spdx_id: spdx_id.to_string(),
+                })
+                .collect())
+        }
+
+        async fn get(&self, key: &str) -> Result<Option<LicenseDetail>> {
+            self.gets.fetch_add(1, Ordering::SeqCst);
+            self.fail()?;
+            Ok(self
+                .licenses
+                .iter()
+                .find(|(k, _)| *k == key)
+                .map(|(_, spdx_id)| LicenseDetail {
+                    name: spdx_id.to_string(),
+                    body: self.name.to_string(),
+                }))
+        }
+    }
+
+    fn chain(providers: Vec<Fake>) -> ProviderChain {
+        ProviderChain::new(
+            providers
+                .into_iter()
+                .map(|p| Box::new(p) as Box<dyn LicenseProvider>)
+                .collect(),
+        )
+    }
+
+    #[tokio::test]
+    async fn first_provider_that_knows_the_key_wins() {
+        let second = Fake::new("second", &[("mit", "MIT")]);
+        let second_gets = second.gets.clone();
+        let chain = chain(vec![Fake::new("first", &[("mit", "MIT")]), second]);
+        assert_eq!(chain.get("mit").await.unwrap().unwrap().body, "first");
+        assert_eq!(second_gets.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn unknown_keys_fall_through() {
+        let chain = chain(vec![
+            Fake::new("first", &[("mit", "MIT")]),
+            Fake::new("second", &[("isc", "ISC")]),
+        ]);
+        assert_eq!(chain.get("isc").await.unwrap().unwrap().body, "second");
+        assert!(chain.get("zlib").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn failing_providers_are_skipped() {
+        let chain = chain(vec![
+            Fake::failing("down"),
+            Fake::new("up", &[("mit", "MIT")]),
+        ]);
+        assert_eq!(chain.get("mit").await.unwrap().unwrap().body, "up");
+        let listed = chain.list().await.unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].name, "MIT from up");
+    }
+
+    #[tokio::test]
+    async fn resolve_fails_when_every_provider_fails() {
+        let chain = chain(vec![Fake::failing("a"), Fake::failing("b")]);
+        assert!(chain.get("mit").await.unwrap().is_none());
+        assert!(matches!(
+            chain.resolve("mit").await,
+            Err(Error::UnknownLicense { key, suggestions }) if key == "mit" && suggestions.is_empty()
+        ));
+    }
+
+    #[tokio::test]
+    async fn list_keeps_the_first_provider_for_each_spdx_id() {
+        let chain = chain(vec![
+            Fake::new("first", &[("mit", "MIT")]),
+            Fake::new("second", &[("MIT", "mit"), ("isc", "ISC")]),
+        ]);
+        let names: Vec<String> = chain
+            .list()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|l| l.name)
+            .collect();
+        assert_eq!(names, ["MIT from first", "ISC from second"]);
+    }
+
+    #[tokio::test]
+    async fn resolve_accepts_names_and_suggests_close_keys() {
+        let chain = chain(vec![Fake::new(
+            "first",
+            &[("apache-2.0", "Apache-2.0"), ("mit", "MIT")],
+        )]);
+        assert_eq!(chain.resolve("Apache2").await.unwrap().name, "Apache-2.0");
+        match chain.resolve("mti").await {
+            Err(Error::UnknownLicense { suggestions, .. }) => assert_eq!(suggestions, ["mit"]),
+            other => panic!("expected UnknownLicense, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn ambiguous_input_is_an_error() {
+        let chain = chain(vec![Fake::new(
+            "first",
+            &[("gpl-2.0", "GPL-2.0"), ("gpl-3.0", "GPL-3.0")],
+        )]);
+        assert!(matches!(
+            chain.resolve("gpl").await,
+            Err(Error::AmbiguousLicense { candidates, .. }) if candidates == ["gpl-2.0", "gpl-3.0"]
+        ));
+    }
+}
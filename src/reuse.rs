@@ -0,0 +1,1008 @@
+//! The [REUSE specification](https://reuse.software/spec-3.3/): license texts
+//! in `LICENSES/`, `REUSE.toml` and legacy `.reuse/dep5` annotations, `.license`
+//! sidecar files, and linting a project the way the reference `reuse` tool does.
+
+use crate::detect::is_notice;
+use crate::expression;
+use crate::header::{self, COPYRIGHT_TAG, CommentStyle, LICENSE_TAG, tag_value};
+use crate::{Error, Result};
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use ignore::WalkBuilder;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const LICENSES_DIR: &str = "LICENSES";
+pub const REUSE_TOML: &str = "REUSE.toml";
+pub const DEP5: &str = ".reuse/dep5";
+
+const SNIPPET_COPYRIGHT_TAG: &str = "SPDX-SnippetCopyrightText:";
+
+/// The SPDX text of a license or exception, `None` for unknown ids and `LicenseRef-`s.
+pub fn license_text(id: &str) -> Option<&'static str> {
+    spdx::license_id(id)
+        .map(|license| license.text())
+        .or_else(|| spdx::exception_id(id).map(|exception| exception.text()))
+}
+
+/// Where the text of license `id` goes: `LICENSES/<id>.txt` under `root`.
+pub fn license_path(root: &Path, id: &str) -> PathBuf {
+    root.join(LICENSES_DIR).join(format!("{id}.txt"))
+}
+
+/// The licenses in `root`'s `LICENSES/` directory, without exceptions.
+pub fn stored_licenses(root: &Path) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(root.join(LICENSES_DIR)) else {
+        return Vec::new();
+    };
+    let mut ids: Vec<String> = entries
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            let id = path.file_stem()?.to_str()?;
+            (spdx::license_id(id).is_some() || id.starts_with("LicenseRef-"))
+                .then(|| id.to_string())
+        })
+        .collect();
+    ids.sort();
+    ids
+}
+
+/// The `<file>.license` sidecar that holds the information for `path`.
+pub fn sidecar_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".license");
+    path.with_file_name(name)
+}
+
+/// Whether `bytes` are not text: they hold a NUL byte or are not UTF-8.
+pub fn is_binary(bytes: &[u8]) -> bool {
+    bytes.contains(&0) || std::str::from_utf8(bytes).is_err()
+}
+
+/// Copyright notices and license expressions that apply to a file.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Info {
+    pub copyright: Vec<String>,
+    pub licenses: Vec<String>,
+}
+
+impl Info {
+    /// The `SPDX-FileCopyrightText`/`SPDX-License-Identifier` tags and
+    /// `Copyright` notices in `text`, outside `REUSE-IgnoreStart`/`REUSE-IgnoreEnd`.
+    pub fn read(text: &str) -> Self {
+        let mut info = Info::default();
+        let mut ignoring = false;
+        for line in text.lines() {
+            if line.contains("REUSE-IgnoreStart") {
+                ignoring = true;
+                continue;
+            } else if line.contains("REUSE-IgnoreEnd") {
+                ignoring = false;
+                continue;
+            } else if ignoring {
+                continue;
+            }
+            if let Some(value) = tag_value(line, LICENSE_TAG) {
+                push_unique(&mut info.licenses, value);
+            } else if let Some(value) =
+                tag_value(line, COPYRIGHT_TAG).or_else(|| tag_value(line, SNIPPET_COPYRIGHT_TAG))
+            {
+                push_unique(&mut info.copyright, value);
+            } else if is_notice(line) {
+                push_unique(&mut info.copyright, line.trim());
+            }
+        }
+        info
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.copyright.is_empty() && self.licenses.is_empty()
+    }
+
+    fn merge(&mut self, other: &Info) {
+        for value in &other.copyright {
+            push_unique(&mut self.copyright, value);
+        }
+        for value in &other.licenses {
+            push_unique(&mut self.licenses, value);
+        }
+    }
+}
+
+fn push_unique(values: &mut Vec<String>, value: &str) {
+    if !value.is_empty() && !values.iter().any(|v| v == value) {
+        values.push(value.to_string());
+    }
+}
+
+/// How an annotation combines with the information in the file itself.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Precedence {
+    /// The file's own copyright or licensing information wins, the annotation fills gaps.
+    #[default]
+    Closest,
+    /// Both apply.
+    Aggregate,
+    /// Only the annotation applies; the file is not read.
+    Override,
+}
+
+#[derive(Deserialize)]
+struct ReuseToml {
+    version: u32,
+    #[serde(default)]
+    annotations: Vec<TomlAnnotation>,
+}
+
+#[derive(Deserialize)]
+struct TomlAnnotation {
+    #[serde(deserialize_with = "one_or_many")]
+    path: Vec<String>,
+    #[serde(default)]
+    precedence: Precedence,
+    #[serde(
+        rename = "SPDX-FileCopyrightText",
+        default,
+        deserialize_with = "one_or_many"
+    )]
+    copyright: Vec<String>,
+    #[serde(
+        rename = "SPDX-License-Identifier",
+        default,
+        deserialize_with = "one_or_many"
+    )]
+    licenses: Vec<String>,
+}
+
+fn one_or_many<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(value) => vec![value],
+        OneOrMany::Many(values) => values,
+    })
+}
+
+struct Rule {
+    /// The directory of the `REUSE.toml` relative to the root, `""` or ending
+    /// in `/`; `paths` are relative to it.
+    dir: String,
+    paths: GlobSet,
+    precedence: Precedence,
+    info: Info,
+}
+
+impl Rule {
+    fn matches(&self, path: &str) -> bool {
+        path.strip_prefix(&self.dir)
+            .is_some_and(|path| self.paths.is_match(path))
+    }
+
+    fn depth(&self) -> usize {
+        self.dir.matches('/').count()
+    }
+}
+
+/// The `REUSE.toml` or `.reuse/dep5` annotations of a project.
+#[derive(Default)]
+pub struct Annotations {
+    /// Outer `REUSE.toml` files first, each in file order.
+    rules: Vec<Rule>,
+}
+
+impl Annotations {
+    /// Reads `.reuse/dep5` or the `REUSE.toml` files in `root` and its
+    /// subdirectories; empty when there are none.
+    pub fn load(root: &Path) -> Result<Self> {
+        let toml_path = root.join(REUSE_TOML);
+        let dep5_path = root.join(DEP5);
+        match (toml_path.is_file(), dep5_path.is_file()) {
+            (true, true) => Err(Error::Parse {
+                path: dep5_path,
+                message: format!("cannot be used together with {REUSE_TOML}"),
+            }),
+            (false, true) => Self::from_dep5(&dep5_path),
+            _ => {
+                let mut rules = Vec::new();
+                for path in reuse_tomls(root)? {
+                    let dir = relative_path(root, path.parent().unwrap_or(root));
+                    let dir = if dir.is_empty() {
+                        dir
+                    } else {
+                        format!("{dir}/")
+                    };
+                    rules.extend(Self::from_toml(&path, &dir)?.rules);
+                }
+                rules.sort_by_key(Rule::depth);
+                Ok(Self { rules })
+            }
+        }
+    }
+
+    fn from_toml(path: &Path, dir: &str) -> Result<Self> {
+        let text = fs::read_to_string(path).map_err(|e| Error::io(path, e))?;
+        let parse_error = |message: String| Error::Parse {
+            path: path.to_path_buf(),
+            message,
+        };
+        let file: ReuseToml = toml::from_str(&text).map_err(|e| parse_error(e.to_string()))?;
+        if file.version != 1 {
+            return Err(parse_error(format!(
+                "unsupported version {}, expected 1",
+                file.version
+            )));
+        }
+        let rules = file
+            .annotations
+            .into_iter()
+            .map(|annotation| {
+                Ok(Rule {
+                    dir: dir.to_string(),
+                    // `*` stays within a directory, `**` crosses them.
+                    paths: glob_set(&annotation.path, true).map_err(parse_error)?,
+                    precedence: annotation.precedence,
+                    info: Info {
+                        copyright: annotation.copyright,
+                        licenses: annotation.licenses,
+                    },
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { rules })
+    }
+
+    fn from_dep5(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path).map_err(|e| Error::io(path, e))?;
+        let mut rules = Vec::new();
+        for paragraph in dep5_paragraphs(&text) {
+            let Some(files) = paragraph.get("Files") else {
+                continue;
+            };
+            let patterns: Vec<String> = files.split_whitespace().map(str::to_string).collect();
+            let copyright = paragraph
+                .get("Copyright")
+                .map(|value| value.lines().map(str::trim).map(str::to_string).collect())
+                .unwrap_or_default();
+            let licenses = paragraph
+                .get("License")
+                .and_then(|value| value.lines().next())
+                .map(|line| vec![line.trim().to_string()])
+                .unwrap_or_default();
+            rules.push(Rule {
+                dir: String::new(),
+                // In DEP-5 `*` also matches `/`.
+                paths: glob_set(&patterns, false).map_err(|message| Error::Parse {
+                    path: path.to_path_buf(),
+                    message,
+                })?,
+                precedence: Precedence::Aggregate,
+                info: Info {
+                    copyright,
+                    licenses,
+                },
+            });
+        }
+        Ok(Self { rules })
+    }
+
+    /// The annotation covering `path`, given relative to the project root: an
+    /// `override` in the outermost `REUSE.toml` that has one, or else the last
+    /// match in the `REUSE.toml` closest to the file.
+    fn find(&self, path: &str) -> Option<&Rule> {
+        self.rules
+            .iter()
+            .rev()
+            .filter(|rule| rule.precedence == Precedence::Override && rule.matches(path))
+            .min_by_key(|rule| rule.depth())
+            .or_else(|| self.rules.iter().rev().find(|rule| rule.matches(path)))
+    }
+
+    /// The information for `path` (relative to the project root) combined with
+    /// `read`, which returns the file's own.
+    pub fn info(&self, path: &str, read: impl FnOnce() -> Info) -> Info {
+        let Some(rule) = self.find(path) else {
+            return read();
+        };
+        match rule.precedence {
+            Precedence::Override => rule.info.clone(),
+            Precedence::Aggregate => {
+                let mut info = read();
+                info.merge(&rule.info);
+                info
+            }
+            Precedence::Closest => {
+                let mut info = read();
+                if info.copyright.is_empty() {
+                    info.copyright = rule.info.copyright.clone();
+                }
+                if info.licenses.is_empty() {
+                    info.licenses = rule.info.licenses.clone();
+                }
+                info
+            }
+        }
+    }
+}
+
+fn glob_set(patterns: &[String], literal_separator: bool) -> Result<GlobSet, String> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = GlobBuilder::new(pattern)
+            .literal_separator(literal_separator)
+            .backslash_escape(true)
+            .build()
+            .map_err(|e| e.to_string())?;
+        builder.add(glob);
+    }
+    builder.build().map_err(|e| e.to_string())
+}
+
+/// The fields of each DEP-5 paragraph, with continuation lines joined by newlines.
+fn dep5_paragraphs(text: &str) -> Vec<BTreeMap<String, String>> {
+    let mut paragraphs = Vec::new();
+    let mut current: BTreeMap<String, String> = BTreeMap::new();
+    let mut field = None;
+    for line in text.lines() {
+        if line.trim().is_empty() {
+            if !current.is_empty() {
+                paragraphs.push(std::mem::take(&mut current));
+            }
+            field = None;
+        } else if line.starts_with([' ', '\t']) {
+            if let Some(value) = field.as_ref().and_then(|f| current.get_mut(f)) {
+                let line = line.trim();
+                value.push('\n');
+                value.push_str(if line == "." { "" } else { line });
+            }
+        } else if let Some((name, value)) = line.split_once(':') {
+            current.insert(name.to_string(), value.trim().to_string());
+            field = Some(name.to_string());
+        }
+    }
+    if !current.is_empty() {
+        paragraphs.push(current);
+    }
+    paragraphs
+}
+
+/// A `[[annotations]]` table for `REUSE.toml`.
+pub fn toml_annotation(paths: &[String], copyright: &str, license: &str) -> String {
+    let quote = |s: &str| toml::Value::String(s.to_string()).to_string();
+    let path = match paths {
+        [path] => quote(path),
+        paths => format!(
+            "[{}]",
+            paths
+                .iter()
+                .map(|p| quote(p))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    };
+    format!(
+        "[[annotations]]\npath = {path}\nprecedence = \"aggregate\"\nSPDX-FileCopyrightText = {}\nSPDX-License-Identifier = {}\n",
+        quote(copyright),
+        quote(license)
+    )
+}
+
+/// `existing` (or a new `REUSE.toml`) with `annotation` appended.
+pub fn append_toml_annotation(existing: Option<&str>, annotation: &str) -> String {
+    let mut text = existing.unwrap_or("version = 1\n").to_string();
+    if !text.ends_with('\n') {
+        text.push('\n');
+    }
+    text.push('\n');
+    text.push_str(annotation);
+    text
+}
+
+/// A DEP-5 `Files` paragraph for `.reuse/dep5`.
+pub fn dep5_paragraph(paths: &[String], copyright: &str, license: &str) -> String {
+    format!(
+        "Files: {}\nCopyright: {copyright}\nLicense: {license}\n",
+        paths.join(" ")
+    )
+}
+
+/// `existing` (or a new `.reuse/dep5` for project `name`) with `paragraph` appended.
+pub fn append_dep5_paragraph(existing: Option<&str>, name: &str, paragraph: &str) -> String {
+    let header = format!(
+        "Format: https://www.debian.org/doc/packaging-manuals/copyright-format/1.0/\nUpstream-Name: {name}\n"
+    );
+    let mut text = existing.map_or(header, str::to_string);
+    if !text.ends_with('\n') {
+        text.push('\n');
+    }
+    text.push('\n');
+    text.push_str(paragraph);
+    text
+}
+
+/// The copyright and license [`annotate`] adds.
+#[derive(Debug, Clone)]
+pub struct Annotation<'a> {
+    pub year: &'a str,
+    pub author: &'a str,
+    pub expression: &'a str,
+    /// Annotate in `.reuse/dep5` instead of `REUSE.toml`.
+    pub dep5: bool,
+    /// Write `.license` sidecars instead of annotating text files that
+    /// cannot hold a header.
+    pub sidecar: bool,
+}
+
+/// The files [`annotate`] writes, none of them written yet.
+#[derive(Debug, Default)]
+pub struct Annotated {
+    /// Source files with a header added.
+    pub headers: Vec<(PathBuf, String)>,
+    /// `.license` sidecars, for binaries and with [`Annotation::sidecar`].
+    pub sidecars: Vec<(PathBuf, String)>,
+    /// `REUSE.toml` or `.reuse/dep5` with the other files appended.
+    pub annotations: Option<(PathBuf, String)>,
+    /// The files annotated there, relative to the root.
+    pub annotated: Vec<String>,
+    /// How many files already had copyright and licensing information.
+    pub present: usize,
+}
+
+/// Adds `annotation` to every file under `paths` (`root` when empty) that
+/// lacks copyright or licensing information: as a header where the comment
+/// syntax is known, as a `.license` sidecar for binaries, and in `REUSE.toml`
+/// or `.reuse/dep5` otherwise.
+pub fn annotate(root: &Path, paths: &[PathBuf], annotation: &Annotation) -> Result<Annotated> {
+    let lines = header::header_lines(annotation.year, annotation.author, annotation.expression);
+    let annotations = Annotations::load(root)?;
+    let mut files = if paths.is_empty() {
+        covered_files(root)?
+    } else {
+        Vec::new()
+    };
+    for path in paths {
+        if path.is_dir() {
+            files.extend(covered_files(path)?);
+        } else {
+            files.push(path.clone());
+        }
+    }
+
+    let mut annotated = Annotated::default();
+    for path in files {
+        let relative = relative_path(root, &path);
+        let info = annotations.info(&relative, || file_info(&path).unwrap_or_default());
+        if !info.copyright.is_empty() && !info.licenses.is_empty() {
+            annotated.present += 1;
+            continue;
+        }
+        let bytes = fs::read(&path).map_err(|e| Error::io(&path, e))?;
+        let text = (!is_binary(&bytes)).then(|| String::from_utf8_lossy(&bytes));
+        let style = CommentStyle::for_path(&path);
+        if let (Some(text), Some(style)) = (&text, style)
+            && !header::is_generated(text)
+            && let Some(updated) = header::add_header(text, style, &lines)
+        {
+            annotated.headers.push((path, updated));
+        } else if text.is_none() || annotation.sidecar {
+            annotated
+                .sidecars
+                .push((sidecar_path(&path), format!("{}\n", lines.join("\n"))));
+        } else {
+            annotated.annotated.push(relative);
+        }
+    }
+
+    if !annotated.annotated.is_empty() {
+        let copyright = format!("{} {}", annotation.year, annotation.author);
+        let files = &annotated.annotated;
+        annotated.annotations = Some(if annotation.dep5 {
+            let path = root.join(DEP5);
+            let existing = fs::read_to_string(&path).ok();
+            let paragraph = dep5_paragraph(files, &copyright, annotation.expression);
+            let name = crate::project_name(root);
+            let text = append_dep5_paragraph(existing.as_deref(), &name, &paragraph);
+            (path, text)
+        } else {
+            let path = root.join(REUSE_TOML);
+            let existing = fs::read_to_string(&path).ok();
+            let toml = toml_annotation(files, &copyright, annotation.expression);
+            (path, append_toml_annotation(existing.as_deref(), &toml))
+        });
+    }
+    Ok(annotated)
+}
+
+/// The files REUSE requires information for: everything under `root` except
+/// VCS and REUSE metadata, license files, `.license` sidecars, SPDX documents,
+/// symlinks, empty files and anything the VCS ignores.
+pub fn covered_files(root: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for entry in walk(root) {
+        let entry = entry?;
+        if !entry.file_type().is_some_and(|t| t.is_file()) {
+            continue;
+        }
+        let name = entry.file_name().to_string_lossy();
+        if is_ignored_name(&name) || entry.metadata().is_ok_and(|m| m.len() == 0) {
+            continue;
+        }
+        files.push(entry.into_path());
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Every `REUSE.toml` under `root`, in the directories [`covered_files`] walks.
+fn reuse_tomls(root: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for entry in walk(root) {
+        let entry = entry?;
+        if entry.file_type().is_some_and(|t| t.is_file()) && entry.file_name() == REUSE_TOML {
+            files.push(entry.into_path());
+        }
+    }
+    Ok(files)
+}
+
+/// Walks `root` in file name order, skipping VCS and REUSE metadata
+/// directories and anything the VCS ignores.
+fn walk(root: &Path) -> ignore::Walk {
+    WalkBuilder::new(root)
+        .hidden(false)
+        .ignore(false)
+        .sort_by_file_name(|a, b| a.cmp(b))
+        .filter_entry(|entry| {
+            let name = entry.file_name().to_string_lossy();
+            let is_dir = entry.file_type().is_some_and(|t| t.is_dir());
+            !(is_dir
+                && matches!(
+                    name.as_ref(),
+                    ".git" | ".hg" | ".sl" | ".reuse" | LICENSES_DIR
+                ))
+        })
+        .build()
+}
+
+fn is_ignored_name(name: &str) -> bool {
+    let license_like = |prefix: &str| {
+        name.strip_prefix(prefix)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with(['-', '.']))
+    };
+    let spdx_document = name.ends_with(".spdx")
+        || ["rdf", "json", "xml", "yaml", "yml"]
+            .iter()
+            .any(|ext| name.ends_with(&format!(".spdx.{ext}")));
+    license_like("LICENSE")
+        || license_like("LICENCE")
+        || license_like("COPYING")
+        || matches!(name, ".git" | ".hgtags" | REUSE_TOML)
+        || name.ends_with(".license")
+        || spdx_document
+}
+
+/// The information for `path` from its sidecar or contents, without annotations.
+pub fn file_info(path: &Path) -> Result<Info> {
+    let sidecar = sidecar_path(path);
+    let source = if sidecar.is_file() {
+        sidecar.as_path()
+    } else {
+        path
+    };
+    let bytes = fs::read(source).map_err(|e| Error::io(source, e))?;
+    if is_binary(&bytes) {
+        return Ok(Info::default());
+    }
+    Ok(Info::read(&String::from_utf8_lossy(&bytes)))
+}
+
+/// `path` relative to `root` with `/` separators, as annotations match it.
+pub fn relative_path(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+/// What `lic reuse lint` found, with paths relative to the project root.
+#[derive(Debug, Default, Serialize)]
+pub struct Lint {
+    /// Files in `LICENSES/` that are not named after an SPDX id or `LicenseRef-`.
+    pub bad_licenses: Vec<String>,
+    pub deprecated_licenses: Vec<String>,
+    pub licenses_without_extension: Vec<String>,
+    /// Licenses used by files but missing from `LICENSES/`, with the files using them.
+    pub missing_licenses: BTreeMap<String, Vec<String>>,
+    /// Licenses in `LICENSES/` that no file uses.
+    pub unused_licenses: Vec<String>,
+    pub used_licenses: Vec<String>,
+    pub read_errors: Vec<String>,
+    /// Files with an expression that does not parse, and the expression.
+    pub invalid_expressions: Vec<(String, String)>,
+    pub missing_copyright: Vec<String>,
+    pub missing_licensing: Vec<String>,
+    pub files: usize,
+}
+
+impl Lint {
+    pub fn is_compliant(&self) -> bool {
+        self.bad_licenses.is_empty()
+            && self.deprecated_licenses.is_empty()
+            && self.licenses_without_extension.is_empty()
+            && self.missing_licenses.is_empty()
+            && self.unused_licenses.is_empty()
+            && self.read_errors.is_empty()
+            && self.invalid_expressions.is_empty()
+            && self.missing_copyright.is_empty()
+            && self.missing_licensing.is_empty()
+    }
+}
+
+/// Checks `root` against the REUSE specification.
+pub fn lint(root: &Path) -> Result<Lint> {
+    let annotations = Annotations::load(root)?;
+    let mut lint = Lint::default();
+
+    // License id -> file in LICENSES/.
+    let mut license_files: BTreeMap<String, String> = BTreeMap::new();
+    let licenses_dir = root.join(LICENSES_DIR);
+    if licenses_dir.is_dir() {
+        let entries = fs::read_dir(&licenses_dir).map_err(|e| Error::io(&licenses_dir, e))?;
+        for entry in entries {
+            let path = entry.map_err(|e| Error::io(&licenses_dir, e))?.path();
+            if !path.is_file() {
+                continue;
+            }
+            let relative = relative_path(root, &path);
+            let Some(id) = path.file_stem().map(|s| s.to_string_lossy().to_string()) else {
+                continue;
+            };
+            if path.extension().is_none() {
+                lint.licenses_without_extension.push(relative.clone());
+            }
+            if let Some(license) = spdx::license_id(&id) {
+                if license.is_deprecated() {
+                    lint.deprecated_licenses.push(id.clone());
+                }
+            } else if let Some(exception) = spdx::exception_id(&id) {
+                if exception.is_deprecated() {
+                    lint.deprecated_licenses.push(id.clone());
+                }
+            } else if !id.starts_with("LicenseRef-") {
+                lint.bad_licenses.push(relative.clone());
+            }
+            license_files.insert(id, relative);
+        }
+    }
+
+    let mut used: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for path in covered_files(root)? {
+        lint.files += 1;
+        let relative = relative_path(root, &path);
+        let mut read_error = false;
+        let info = annotations.info(&relative, || {
+            file_info(&path).unwrap_or_else(|_| {
+                read_error = true;
+                Info::default()
+            })
+        });
+        if read_error {
+            lint.read_errors.push(relative.clone());
+        }
+        if info.copyright.is_empty() {
+            lint.missing_copyright.push(relative.clone());
+        }
+        if info.licenses.is_empty() {
+            lint.missing_licensing.push(relative.clone());
+        }
+        for expr in &info.licenses {
+            let Ok(parsed) = expression::parse(expr) else {
+                lint.invalid_expressions
+                    .push((relative.clone(), expr.clone()));
+                continue;
+            };
+            for term in parsed.terms() {
+                let ids = std::iter::once(&term.id).chain(term.exception.as_ref());
+                for id in ids {
+                    let files = used.entry(id.clone()).or_default();
+                    if !files.contains(&relative) {
+                        files.push(relative.clone());
+                    }
+                }
+            }
+        }
+    }
+
+    for (id, files) in &used {
+        if !license_files.contains_key(id) {
+            lint.missing_licenses.insert(id.clone(), files.clone());
+        }
+    }
+    lint.unused_licenses = license_files
+        .keys()
+        .filter(|id| !used.contains_key(*id))
+        .cloned()
+        .collect();
+    lint.used_licenses = used.into_keys().collect();
+    Ok(lint)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write(root: &Path, path: &str, text: &str) {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, text).unwrap();
+    }
+
+    #[test]
+    fn info_reads_tags_in_comments_only() {
+        let text = "\
+#!/bin/sh
+# SPDX-FileCopyrightText: 2024 Jane Doe
+# SPDX-License-Identifier: MIT
+LICENSE_TAG=\"SPDX-License-Identifier:\"
+echo \"SPDX-FileCopyrightText: $year $holder\"
+# REUSE-IgnoreStart
+# SPDX-License-Identifier: GPL-3.0-only
+# REUSE-IgnoreEnd
+<!-- SPDX-SnippetCopyrightText: 2025 Acme -->
+Copyright (c) 2023 John Roe
+";
+        assert_eq!(
+            Info::read(text),
+            Info {
+                copyright: vec![
+                    "2024 Jane Doe".to_string(),
+                    "2025 Acme".to_string(),
+                    "Copyright (c) 2023 John Roe".to_string(),
+                ],
+                licenses: vec!["MIT".to_string()],
+            }
+        );
+        let rust = "pub(crate) const LICENSE_TAG: &str = \"SPDX-License-Identifier:\";\n";
+        assert!(Info::read(rust).is_empty());
+        let vim = "\" SPDX-License-Identifier: Vim\n";
+        assert_eq!(Info::read(vim).licenses, ["Vim"]);
+    }
+
+    #[test]
+    fn toml_precedence() {
+        let dir = TempDir::new().unwrap();
+        write(
+            dir.path(),
+            REUSE_TOML,
+            r#"version = 1
+
+[[annotations]]
+path = "*.png"
+SPDX-FileCopyrightText = "2024 Jane Doe"
+SPDX-License-Identifier = "CC0-1.0"
+
+[[annotations]]
+path = ["docs/**", "*.md"]
+precedence = "aggregate"
+SPDX-FileCopyrightText = "2024 Docs"
+SPDX-License-Identifier = "CC-BY-4.0"
+
+[[annotations]]
+path = "vendor/**"
+precedence = "override"
+SPDX-License-Identifier = "Apache-2.0"
+"#,
+        );
+        let annotations = Annotations::load(dir.path()).unwrap();
+        let own = || Info {
+            copyright: vec!["2023 Own".to_string()],
+            licenses: Vec::new(),
+        };
+
+        // `closest` only fills the gaps, and `*` stays within a directory.
+        let info = annotations.info("logo.png", own);
+        assert_eq!(info.copyright, ["2023 Own"]);
+        assert_eq!(info.licenses, ["CC0-1.0"]);
+        assert_eq!(annotations.info("img/logo.png", own), own());
+
+        let info = annotations.info("docs/guide/intro.txt", own);
+        assert_eq!(info.copyright, ["2023 Own", "2024 Docs"]);
+
+        let info = annotations.info("vendor/lib.c", || unreachable!());
+        assert!(info.copyright.is_empty());
+        assert_eq!(info.licenses, ["Apache-2.0"]);
+    }
+
+    #[test]
+    fn nested_toml_files_apply_below_their_directory() {
+        let dir = TempDir::new().unwrap();
+        let annotation = |path: &str, precedence: &str, license: &str| {
+            format!(
+                "version = 1\n\n[[annotations]]\npath = \"{path}\"\nprecedence = \"{precedence}\"\nSPDX-License-Identifier = \"{license}\"\n"
+            )
+        };
+        write(dir.path(), REUSE_TOML, &annotation("**", "closest", "MIT"));
+        write(
+            dir.path(),
+            "assets/REUSE.toml",
+            &annotation("*.svg", "closest", "CC0-1.0"),
+        );
+        write(
+            dir.path(),
+            "third_party/REUSE.toml",
+            &annotation("**", "closest", "BSD-3-Clause"),
+        );
+        let annotations = Annotations::load(dir.path()).unwrap();
+        let licenses = |path: &str| annotations.info(path, Info::default).licenses;
+
+        assert_eq!(licenses("src/main.rs"), ["MIT"]);
+        // Paths in a nested REUSE.toml are relative to its directory.
+        assert_eq!(licenses("assets/logo.svg"), ["CC0-1.0"]);
+        assert_eq!(licenses("assets/icons/logo.svg"), ["MIT"]);
+        assert_eq!(licenses("svg/logo.svg"), ["MIT"]);
+        assert_eq!(licenses("third_party/zlib/zlib.h"), ["BSD-3-Clause"]);
+
+        // An override in an outer file beats the closer one.
+        write(dir.path(), REUSE_TOML, &annotation("**", "override", "MIT"));
+        let annotations = Annotations::load(dir.path()).unwrap();
+        let info = annotations.info("assets/logo.svg", Info::default);
+        assert_eq!(info.licenses, ["MIT"]);
+    }
+
+    #[test]
+    fn dep5_paragraphs_aggregate() {
+        let dir = TempDir::new().unwrap();
+        write(
+            dir.path(),
+            DEP5,
+            "Format: https://www.debian.org/doc/packaging-manuals/copyright-format/1.0/\n\
+             Upstream-Name: demo\n\
+             \n\
+             Files: assets/*\n\
+             Copyright: 2024 Jane Doe\n 2025 Acme\n\
+             License: CC0-1.0\n",
+        );
+        let annotations = Annotations::load(dir.path()).unwrap();
+        let own = || Info {
+            copyright: Vec::new(),
+            licenses: vec!["MIT".to_string()],
+        };
+        // In DEP-5 `*` crosses directories.
+        let info = annotations.info("assets/img/logo.png", own);
+        assert_eq!(info.copyright, ["2024 Jane Doe", "2025 Acme"]);
+        assert_eq!(info.licenses, ["MIT", "CC0-1.0"]);
+
+        write(dir.path(), REUSE_TOML, "version = 1\n");
+        assert!(Annotations::load(dir.path()).is_err());
+    }
+
+    #[test]
+    fn covered_files_skip_license_and_reuse_files() {
+        let dir = TempDir::new().unwrap();
+        for path in [
+            "src/main.rs",
+            "logo.png.license",
+            "LICENSE",
+            "LICENSE-MIT",
+            "COPYING.LESSER",
+            "LICENSES/MIT.txt",
+            "REUSE.toml",
+            "sbom.spdx.json",
+            ".git/config",
+        ] {
+            write(dir.path(), path, "x\n");
+        }
+        write(dir.path(), "logo.png", "\0");
+        write(dir.path(), "empty.txt", "");
+        write(dir.path(), "LICENSE_NOTES.md", "x\n");
+
+        let files: Vec<String> = covered_files(dir.path())
+            .unwrap()
+            .iter()
+            .map(|path| relative_path(dir.path(), path))
+            .collect();
+        assert_eq!(files, ["LICENSE_NOTES.md", "logo.png", "src/main.rs"]);
+    }
+
+    #[test]
+    fn lint_classifies_invalid_expressions() {
+        let dir = TempDir::new().unwrap();
+        let header = |license: &str| {
+            format!(
+                "// SPDX-FileCopyrightText: 2024 Jane Doe\n// SPDX-License-Identifier: {license}\n"
+            )
+        };
+        write(dir.path(), "a.rs", &header("MIT OR Apache-2.0"));
+        write(dir.path(), "b.rs", &header("\";"));
+        write(dir.path(), "c.rs", &header("MIT AND"));
+        write(dir.path(), "d.rs", "fn main() {}\n");
+        write(dir.path(), "LICENSES/MIT.txt", "MIT\n");
+        write(dir.path(), "LICENSES/GPL-3.0.txt", "GPL\n");
+        write(dir.path(), "LICENSES/Foo.txt", "Foo\n");
+
+        let lint = lint(dir.path()).unwrap();
+        assert_eq!(lint.files, 4);
+        assert_eq!(
+            lint.invalid_expressions,
+            [
+                ("b.rs".to_string(), "\";".to_string()),
+                ("c.rs".to_string(), "MIT AND".to_string()),
+            ]
+        );
+        assert_eq!(lint.used_licenses, ["Apache-2.0", "MIT"]);
+        assert_eq!(
+            lint.missing_licenses,
+            BTreeMap::from([("Apache-2.0".to_string(), vec!["a.rs".to_string()])])
+        );
+        assert_eq!(lint.unused_licenses, ["Foo", "GPL-3.0"]);
+        assert_eq!(lint.bad_licenses, ["LICENSES/Foo.txt"]);
+        assert_eq!(lint.deprecated_licenses, ["GPL-3.0"]);
+        assert_eq!(lint.missing_copyright, ["d.rs"]);
+        assert_eq!(lint.missing_licensing, ["d.rs"]);
+        assert!(!lint.is_compliant());
+    }
+
+    #[test]
+    fn annotate_picks_header_sidecar_or_toml() {
+        let dir = TempDir::new().unwrap();
+        write(dir.path(), "main.rs", "fn main() {}\n");
+        write(
+            dir.path(),
+            "done.rs",
+            "// SPDX-FileCopyrightText: 2020 Acme\n// SPDX-License-Identifier: MIT\n",
+        );
+        write(dir.path(), "logo.png", "\u{0}PNG");
+        write(dir.path(), "notes.txt", "Notes\n");
+        let annotation = Annotation {
+            year: "2026",
+            author: "Jane Doe",
+            expression: "MIT",
+            dep5: false,
+            sidecar: false,
+        };
+
+        let annotated = annotate(dir.path(), &[], &annotation).unwrap();
+        assert_eq!(annotated.present, 1);
+        assert_eq!(
+            annotated.headers,
+            [(
+                dir.path().join("main.rs"),
+                "// SPDX-FileCopyrightText: 2026 Jane Doe\n// SPDX-License-Identifier: MIT\n\nfn main() {}\n"
+                    .to_string()
+            )]
+        );
+        assert_eq!(
+            annotated.sidecars,
+            [(
+                dir.path().join("logo.png.license"),
+                "SPDX-FileCopyrightText: 2026 Jane Doe\nSPDX-License-Identifier: MIT\n".to_string()
+            )]
+        );
+        assert_eq!(annotated.annotated, ["notes.txt"]);
+        let (path, text) = annotated.annotations.unwrap();
+        assert_eq!(path, dir.path().join(REUSE_TOML));
+        assert_eq!(
+            text,
+            "version = 1\n\n[[annotations]]\npath = \"notes.txt\"\nprecedence = \"aggregate\"\n\
+             SPDX-FileCopyrightText = \"2026 Jane Doe\"\nSPDX-License-Identifier = \"MIT\"\n"
+        );
+
+        let sidecars = annotate(
+            dir.path(),
+            &[dir.path().join("notes.txt")],
+            &Annotation {
+                sidecar: true,
+                ..annotation
+            },
+        )
+        .unwrap();
+        assert_eq!(sidecars.sidecars[0].0, dir.path().join("notes.txt.license"));
+        assert!(sidecars.annotations.is_none());
+    }
+}
@@ -0,0 +1,183 @@
+use crate::provider::LicenseProvider;
+use crate::{Error, LicenseDetail, LicenseMeta, Result};
+use async_trait::async_trait;
+use serde::Deserialize;
+use std::fs;
+use std::path::PathBuf;
+
+/// Licenses from the SPDX License List, either compiled in (via the `spdx`
+/// crate) or read from a local checkout of `spdx/license-list-data`.
+pub struct SpdxLicenses {
+    data_dir: Option<PathBuf>,
+}
+
+#[derive(Deserialize)]
+struct LicenseList {
+    licenses: Vec<LicenseListEntry>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LicenseListEntry {
+    license_id: String,
+    name: String,
+    #[serde(default)]
+    is_deprecated_license_id: bool,
+}
+
+impl SpdxLicenses {
+    pub fn new(data_dir: Option<PathBuf>) -> Self {
+        Self { data_dir }
+    }
+
+    /// Lists every non-deprecated license, keyed by its SPDX id.
+    pub fn licenses_list(&self) -> Result<Vec<LicenseMeta>> {
+        let meta = |id: &str, name: &str| LicenseMeta {
+            key: id.to_string(),
+            name: name.to_string(),
+            spdx_id: id.to_string(),
+        };
+
+        let Some(dir) = &self.data_dir else {
+            return Ok(spdx::identifiers::LICENSES
+                .iter()
+                .filter(|(_, _, flags)| flags & spdx::identifiers::IS_DEPRECATED == 0)
+                .map(|(id, name, _)| meta(id, name))
+                .collect());
+        };
+
+        let path = dir.join("json").join("licenses.json");
+        let raw = fs::read_to_string(&path).map_err(|e| Error::io(&path, e))?;
+        let list: LicenseList =
+            serde_json::from_str(&raw).map_err(|source| Error::Json { path, source })?;
+        Ok(list
+            .licenses
+            .iter()
+            .filter(|l| !l.is_deprecated_license_id)
+            .map(|l| meta(&l.license_id, &l.name))
+            .collect())
+    }
+
+    /// Looks up a license text by SPDX id, ignoring case.
+    pub fn license_body(&self, id: &str) -> Result<Option<LicenseDetail>> {
+        let Some(dir) = &self.data_dir else {
+            return Ok(spdx::identifiers::LICENSES
+                .iter()
+                .find(|(name, _, _)| name.eq_ignore_ascii_case(id))
+                .and_then(|(name, _, _)| spdx::license_id(name))
+                .map(|license| LicenseDetail {
+                    name: license.full_name.to_string(),
+                    body: license.text().to_string(),
+                }));
+        };
+
+        let Some(entry) = self
+            .licenses_list()?
+            .into_iter()
+            .find(|l| l.spdx_id.eq_ignore_ascii_case(id))
+        else {
+            return Ok(None);
+        };
+        let path = dir.join("text").join(format!("{}.txt", entry.spdx_id));
+        let body = fs::read_to_string(&path).map_err(|e| Error::io(&path, e))?;
+        Ok(Some(LicenseDetail {
+            name: entry.name,
+            body,
+        }))
+    }
+}
+
+#[async_trait]
+impl LicenseProvider for SpdxLicenses {
+    fn name(&self) -> &str {
+        "spdx"
+    }
+
+    async fn list(&self) -> Result<Vec<LicenseMeta>> {
+        self.licenses_list()
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<LicenseDetail>> {
+        self.license_body(key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(licenses: &[LicenseMeta]) -> Vec<&str> {
+        licenses.iter().map(|l| l.spdx_id.as_str()).collect()
+    }
+
+    #[test]
+    fn compiled_in_list_has_current_licenses_only() {
+        let spdx = SpdxLicenses::new(None);
+        let licenses = spdx.licenses_list().unwrap();
+        let ids = ids(&licenses);
+        for id in ["0BSD", "BSL-1.0", "Zlib", "MIT-0", "EUPL-1.2", "CC-BY-4.0"] {
+            assert!(ids.contains(&id), "{id}");
+        }
+        assert!(!ids.contains(&"GPL-3.0"));
+        assert!(licenses.iter().all(|l| l.key == l.spdx_id));
+    }
+
+    #[test]
+    fn compiled_in_texts_are_found_ignoring_case() {
+        let spdx = SpdxLicenses::new(None);
+        let zlib = spdx.license_body("zlib").unwrap().unwrap();
+        assert_eq!(zlib.name, "zlib License");
+        assert!(zlib.body.contains("This software is provided 'as-is'"));
+        assert!(spdx.license_body("No-Such-License").unwrap().is_none());
+    }
+
+    #[test]
+    fn every_listed_license_has_a_text() {
+        let spdx = SpdxLicenses::new(None);
+        for license in spdx.licenses_list().unwrap() {
+            let detail = spdx.license_body(&license.spdx_id).unwrap();
+            assert!(detail.is_some(), "{}", license.spdx_id);
+        }
+    }
+
+    #[test]
+    fn license_list_data_checkout() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("json")).unwrap();
+        fs::create_dir_all(dir.path().join("text")).unwrap();
+        fs::write(
+            dir.path().join("json/licenses.json"),
+            r#"{"licenses": [
+                {"licenseId": "MIT", "name": "MIT License"},
+                {"licenseId": "GPL-3.0", "name": "GNU GPL v3.0", "isDeprecatedLicenseId": true},
+                {"licenseId": "Missing", "name": "Listed Without Text"}
+            ]}"#,
+        )
+        .unwrap();
+        fs::write(dir.path().join("text/MIT.txt"), "MIT text\n").unwrap();
+        let spdx = SpdxLicenses::new(Some(dir.path().to_path_buf()));
+
+        assert_eq!(ids(&spdx.licenses_list().unwrap()), ["MIT", "Missing"]);
+        let mit = spdx.license_body("mit").unwrap().unwrap();
+        assert_eq!(
+            (mit.name.as_str(), mit.body.as_str()),
+            ("MIT License", "MIT text\n")
+        );
+        assert!(spdx.license_body("GPL-3.0").unwrap().is_none());
+        assert!(matches!(
+            spdx.license_body("Missing"),
+            Err(Error::Io { .. })
+        ));
+    }
+
+    #[test]
+    fn broken_checkouts_are_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let spdx = SpdxLicenses::new(Some(dir.path().to_path_buf()));
+        assert!(matches!(spdx.licenses_list(), Err(Error::Io { .. })));
+
+        fs::create_dir_all(dir.path().join("json")).unwrap();
+        fs::write(dir.path().join("json/licenses.json"), "{").unwrap();
+        assert!(matches!(spdx.licenses_list(), Err(Error::Json { .. })));
+    }
+}
@@ -0,0 +1,146 @@
+//! Cache expiry and ETag revalidation against a local stand-in server.
+
+mod common;
+
+use common::{Reply, Server, client};
+use lic::cache::{self, Cache};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+async fn fetch(cache: &Cache, server: &Server, force: bool) -> lic::Result<Vec<String>> {
+    let request = client().get(format!("{}/licenses", server.url));
+    cache::fetch_json(cache, request, "licenses", force, |response| {
+        Ok(response.error_for_status()?)
+    })
+    .await
+}
+
+/// Replies `["v1"]` with ETag `"1"` at first, and `["v2"]` with `"2"` from the
+/// request number `change_at` on; a matching `If-None-Match` gets a 304.
+async fn versioned_server(change_at: usize) -> Server {
+    let count = Arc::new(AtomicUsize::new(0));
+    Server::start(move |request| {
+        let version = if count.fetch_add(1, Ordering::SeqCst) + 1 >= change_at {
+            "2"
+        } else {
+            "1"
+        };
+        let etag = format!("\"{version}\"");
+        if request.header("if-none-match") == Some(etag.as_str()) {
+            Reply::status(304).with_header("etag", &etag)
+        } else {
+            Reply::json(&format!("[\"v{version}\"]")).with_header("etag", &etag)
+        }
+    })
+    .await
+}
+
+#[tokio::test]
+async fn fresh_entries_are_used_without_a_request() {
+    let dir = tempfile::tempdir().unwrap();
+    let cache = Cache::in_dir(dir.path().to_path_buf(), 3600);
+    let server = versioned_server(2).await;
+
+    assert_eq!(fetch(&cache, &server, false).await.unwrap(), ["v1"]);
+    assert_eq!(fetch(&cache, &server, false).await.unwrap(), ["v1"]);
+    assert_eq!(server.requests().len(), 1);
+
+    let entry = cache.read::<Vec<String>>("licenses").unwrap();
+    assert_eq!(entry.etag.as_deref(), Some("\"1\""));
+    assert!(cache.is_fresh(&entry));
+}
+
+#[tokio::test]
+async fn expired_entries_are_revalidated_and_reused_on_304() {
+    let dir = tempfile::tempdir().unwrap();
+    let cache = Cache::in_dir(dir.path().to_path_buf(), 0);
+    let server = versioned_server(usize::MAX).await;
+
+    assert_eq!(fetch(&cache, &server, false).await.unwrap(), ["v1"]);
+    let first = cache.read::<Vec<String>>("licenses").unwrap();
+    assert!(!cache.is_fresh(&first));
+
+    assert_eq!(fetch(&cache, &server, false).await.unwrap(), ["v1"]);
+    let requests = server.requests();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[0].header("if-none-match"), None);
+    assert_eq!(requests[1].header("if-none-match"), Some("\"1\""));
+
+    let revalidated = cache.read::<Vec<String>>("licenses").unwrap();
+    assert_eq!(revalidated.data, ["v1"]);
+    assert!(revalidated.fetched_at > first.fetched_at);
+}
+
+#[tokio::test]
+async fn changed_responses_replace_the_entry() {
+    let dir = tempfile::tempdir().unwrap();
+    let cache = Cache::in_dir(dir.path().to_path_buf(), 0);
+    let server = versioned_server(2).await;
+
+    assert_eq!(fetch(&cache, &server, false).await.unwrap(), ["v1"]);
+    assert_eq!(fetch(&cache, &server, false).await.unwrap(), ["v2"]);
+    assert_eq!(server.requests()[1].header("if-none-match"), Some("\"1\""));
+
+    let entry = cache.read::<Vec<String>>("licenses").unwrap();
+    assert_eq!(entry.data, ["v2"]);
+    assert_eq!(entry.etag.as_deref(), Some("\"2\""));
+}
+
+#[tokio::test]
+async fn forced_fetches_skip_fresh_entries() {
+    let dir = tempfile::tempdir().unwrap();
+    let cache = Cache::in_dir(dir.path().to_path_buf(), 3600);
+    let server = versioned_server(2).await;
+
+    assert_eq!(fetch(&cache, &server, false).await.unwrap(), ["v1"]);
+    assert_eq!(fetch(&cache, &server, true).await.unwrap(), ["v2"]);
+    assert_eq!(server.requests().len(), 2);
+}
+
+#[tokio::test]
+async fn failed_requests_fall_back_to_stale_entries() {
+    let dir = tempfile::tempdir().unwrap();
+    let cache = Cache::in_dir(dir.path().to_path_buf(), 0);
+    let count = Arc::new(AtomicUsize::new(0));
+    let server = Server::start(move |_| {
+        if count.fetch_add(1, Ordering::SeqCst) == 0 {
+            Reply::json(r#"["v1"]"#)
+        } else {
+            Reply::status(500)
+        }
+    })
+    .await;
+
+    assert_eq!(fetch(&cache, &server, false).await.unwrap(), ["v1"]);
+    assert_eq!(fetch(&cache, &server, false).await.unwrap(), ["v1"]);
+    assert_eq!(server.requests().len(), 2);
+}
+
+#[tokio::test]
+async fn failed_requests_without_an_entry_are_errors() {
+    let dir = tempfile::tempdir().unwrap();
+    let cache = Cache::in_dir(dir.path().to_path_buf(), 3600);
+    let server = Server::start(|_| Reply::status(500)).await;
+
+    assert!(fetch(&cache, &server, false).await.is_err());
+    assert!(cache.entries().is_empty());
+}
+
+#[test]
+fn clear_removes_every_entry() {
+    let dir = tempfile::tempdir().unwrap();
+    let cache_dir = dir.path().join("lic");
+    std::fs::create_dir_all(cache_dir.join("api.github.com")).unwrap();
+    std::fs::write(
+        cache_dir.join("api.github.com/licenses.json"),
+        r#"{"etag": null, "fetched_at": "2024-01-01T00:00:00Z", "data": []}"#,
+    )
+    .unwrap();
+    let cache = Cache::in_dir(cache_dir.clone(), 3600);
+
+    let names: Vec<_> = cache.entries().into_iter().map(|(name, _)| name).collect();
+    assert_eq!(names, ["api.github.com/licenses"]);
+    cache.clear().unwrap();
+    assert!(!cache_dir.exists());
+    assert!(cache.entries().is_empty());
+}
@@ -0,0 +1,182 @@
+//! The license API providers against a local stand-in server.
+
+mod common;
+
+use common::{Reply, Server, client};
+use lic::cache::Cache;
+use lic::github::GitHubApi;
+use lic::gitlab::GitLabApi;
+use lic::{Error, LicenseProvider};
+
+const GITHUB_LIST: &str = r#"[
+    {"key": "mit", "name": "MIT License", "spdx_id": "MIT"},
+    {"key": "apache-2.0", "name": "Apache License 2.0", "spdx_id": "Apache-2.0"},
+    {"key": "gpl-3.0", "name": "GNU General Public License v3.0", "spdx_id": "GPL-3.0"}
+]"#;
+
+const GITHUB_MIT: &str =
+    r#"{"name": "MIT License", "body": "MIT License\n\nCopyright (c) [year] [fullname]\n"}"#;
+
+fn github(server: &Server, token: Option<&str>) -> (GitHubApi, tempfile::TempDir) {
+    let dir = tempfile::tempdir().unwrap();
+    let cache = Cache::in_dir(dir.path().to_path_buf(), 3600);
+    let api = GitHubApi::new(client(), cache, &server.url, token.map(str::to_string));
+    (api, dir)
+}
+
+#[tokio::test]
+async fn github_lists_and_fetches_licenses() {
+    let server = Server::start(|request| match request.path.as_str() {
+        "/licenses" => Reply::json(GITHUB_LIST),
+        "/licenses/mit" => Reply::json(GITHUB_MIT),
+        _ => Reply::status(404),
+    })
+    .await;
+    let (api, _dir) = github(&server, Some("secret"));
+
+    let list = api.list().await.unwrap();
+    let ids: Vec<_> = list.iter().map(|l| l.spdx_id.as_str()).collect();
+    assert_eq!(ids, ["MIT", "Apache-2.0", "GPL-3.0-only"]);
+
+    let mit = api.get("mit").await.unwrap().unwrap();
+    assert_eq!(mit.name, "MIT License");
+    assert!(mit.body.contains("[fullname]"));
+
+    assert!(api.get("no-such-license").await.unwrap().is_none());
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 3);
+    for request in &requests {
+        assert_eq!(request.header("authorization"), Some("Bearer secret"));
+        assert_eq!(request.header("user-agent"), Some("git-license-cli-rust"));
+    }
+}
+
+#[tokio::test]
+async fn github_serves_fresh_entries_from_the_cache() {
+    let server = Server::start(|_| Reply::json(GITHUB_LIST)).await;
+    let (api, _dir) = github(&server, None);
+
+    api.list().await.unwrap();
+    api.list().await.unwrap();
+    assert_eq!(server.requests().len(), 1);
+    assert_eq!(server.requests()[0].header("authorization"), None);
+
+    api.licenses_list(true).await.unwrap();
+    assert_eq!(server.requests().len(), 2);
+}
+
+#[tokio::test]
+async fn github_refuses_keys_that_are_not_license_keys() {
+    let server = Server::start(|_| Reply::json(GITHUB_MIT)).await;
+    let (api, _dir) = github(&server, None);
+
+    assert!(api.get("../mit").await.unwrap().is_none());
+    assert!(server.requests().is_empty());
+}
+
+#[tokio::test]
+async fn github_reports_rate_limits() {
+    let server = Server::start(|_| {
+        Reply::status(403)
+            .with_header("x-ratelimit-limit", "60")
+            .with_header("x-ratelimit-remaining", "0")
+            .with_header("x-ratelimit-reset", "0")
+    })
+    .await;
+    let (api, _dir) = github(&server, None);
+
+    match api.list().await {
+        Err(Error::RateLimited(message)) => {
+            assert!(
+                message.contains("rate limit of 60 requests/hour"),
+                "{message}"
+            );
+            assert!(message.contains("Pass --token"), "{message}");
+        }
+        other => panic!("expected a rate limit error, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn github_reports_secondary_rate_limits() {
+    let server = Server::start(|_| Reply::status(429).with_header("retry-after", "30")).await;
+    let (api, _dir) = github(&server, Some("secret"));
+
+    match api.list().await {
+        Err(Error::RateLimited(message)) => assert!(message.contains("retry in 30 seconds")),
+        other => panic!("expected a rate limit error, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn github_reports_bad_tokens() {
+    let server = Server::start(|_| Reply::status(401)).await;
+    let (api, _dir) = github(&server, Some("expired"));
+
+    assert!(matches!(api.list().await, Err(Error::Unauthorized)));
+}
+
+#[tokio::test]
+async fn github_passes_on_server_errors() {
+    let server = Server::start(|_| Reply::status(500)).await;
+    let (api, _dir) = github(&server, None);
+
+    let error = api.get("mit").await.unwrap_err();
+    assert!(!error.is_not_found());
+    assert!(matches!(error, Error::Http(_)), "{error:?}");
+}
+
+#[tokio::test]
+async fn gitlab_lists_and_fetches_templates() {
+    let server = Server::start(|request| match request.path.as_str() {
+        "/templates/licenses?per_page=100" => Reply::json(
+            r#"[
+                {"key": "mit", "name": "MIT License", "content": "MIT License\n"},
+                {"key": "apache-2.0", "name": "Apache License 2.0"},
+                {"key": "lgpl-2.1", "name": "GNU Lesser General Public License v2.1"},
+                {"key": "made-up", "name": "Made Up License"}
+            ]"#,
+        ),
+        "/templates/licenses/mit" => Reply::json(
+            r#"{"key": "mit", "name": "MIT License", "content": "MIT License\n\nCopyright (c) [year] [fullname]\n"}"#,
+        ),
+        _ => Reply::status(404),
+    })
+    .await;
+    let api = GitLabApi::new(client(), &format!("{}/", server.url));
+
+    let list = api.list().await.unwrap();
+    let ids: Vec<_> = list
+        .iter()
+        .map(|l| (l.key.as_str(), l.spdx_id.as_str()))
+        .collect();
+    assert_eq!(
+        ids,
+        [
+            ("mit", "MIT"),
+            ("apache-2.0", "Apache-2.0"),
+            ("lgpl-2.1", "LGPL-2.1-only"),
+            ("made-up", "made-up")
+        ]
+    );
+
+    let mit = api.get("mit").await.unwrap().unwrap();
+    assert_eq!(mit.name, "MIT License");
+    assert!(mit.body.contains("[fullname]"));
+
+    assert!(api.get("no-such-license").await.unwrap().is_none());
+    assert_eq!(
+        server.requests()[0].header("user-agent"),
+        Some("git-license-cli-rust")
+    );
+}
+
+#[tokio::test]
+async fn gitlab_passes_on_server_errors() {
+    let server = Server::start(|_| Reply::status(503)).await;
+    let api = GitLabApi::new(client(), &server.url);
+
+    assert!(api.list().await.is_err());
+    assert!(api.get("mit").await.is_err());
+}